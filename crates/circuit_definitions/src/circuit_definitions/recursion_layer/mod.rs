@@ -113,6 +113,71 @@ impl ZkSyncRecursionLayerStorageType {
             ..=ZkSyncRecursionLayerStorageType::LeafLayerCircuitForECPairing as u8
     }
 
+    pub fn from_numeric_value(value: u8) -> Option<Self> {
+        match value {
+            a if a == Self::SchedulerCircuit as u8 => Some(Self::SchedulerCircuit),
+            a if a == Self::NodeLayerCircuit as u8 => Some(Self::NodeLayerCircuit),
+            a if a == Self::LeafLayerCircuitForMainVM as u8 => {
+                Some(Self::LeafLayerCircuitForMainVM)
+            }
+            a if a == Self::LeafLayerCircuitForCodeDecommittmentsSorter as u8 => {
+                Some(Self::LeafLayerCircuitForCodeDecommittmentsSorter)
+            }
+            a if a == Self::LeafLayerCircuitForCodeDecommitter as u8 => {
+                Some(Self::LeafLayerCircuitForCodeDecommitter)
+            }
+            a if a == Self::LeafLayerCircuitForLogDemuxer as u8 => {
+                Some(Self::LeafLayerCircuitForLogDemuxer)
+            }
+            a if a == Self::LeafLayerCircuitForKeccakRoundFunction as u8 => {
+                Some(Self::LeafLayerCircuitForKeccakRoundFunction)
+            }
+            a if a == Self::LeafLayerCircuitForSha256RoundFunction as u8 => {
+                Some(Self::LeafLayerCircuitForSha256RoundFunction)
+            }
+            a if a == Self::LeafLayerCircuitForECRecover as u8 => {
+                Some(Self::LeafLayerCircuitForECRecover)
+            }
+            a if a == Self::LeafLayerCircuitForRAMPermutation as u8 => {
+                Some(Self::LeafLayerCircuitForRAMPermutation)
+            }
+            a if a == Self::LeafLayerCircuitForStorageSorter as u8 => {
+                Some(Self::LeafLayerCircuitForStorageSorter)
+            }
+            a if a == Self::LeafLayerCircuitForStorageApplication as u8 => {
+                Some(Self::LeafLayerCircuitForStorageApplication)
+            }
+            a if a == Self::LeafLayerCircuitForEventsSorter as u8 => {
+                Some(Self::LeafLayerCircuitForEventsSorter)
+            }
+            a if a == Self::LeafLayerCircuitForL1MessagesSorter as u8 => {
+                Some(Self::LeafLayerCircuitForL1MessagesSorter)
+            }
+            a if a == Self::LeafLayerCircuitForL1MessagesHasher as u8 => {
+                Some(Self::LeafLayerCircuitForL1MessagesHasher)
+            }
+            a if a == Self::LeafLayerCircuitForTransientStorageSorter as u8 => {
+                Some(Self::LeafLayerCircuitForTransientStorageSorter)
+            }
+            a if a == Self::LeafLayerCircuitForSecp256r1Verify as u8 => {
+                Some(Self::LeafLayerCircuitForSecp256r1Verify)
+            }
+            a if a == Self::LeafLayerCircuitForEIP4844Repack as u8 => {
+                Some(Self::LeafLayerCircuitForEIP4844Repack)
+            }
+            a if a == Self::LeafLayerCircuitForModexp as u8 => {
+                Some(Self::LeafLayerCircuitForModexp)
+            }
+            a if a == Self::LeafLayerCircuitForECAdd as u8 => Some(Self::LeafLayerCircuitForECAdd),
+            a if a == Self::LeafLayerCircuitForECMul as u8 => Some(Self::LeafLayerCircuitForECMul),
+            a if a == Self::LeafLayerCircuitForECPairing as u8 => {
+                Some(Self::LeafLayerCircuitForECPairing)
+            }
+            a if a == Self::RecursionTipCircuit as u8 => Some(Self::RecursionTipCircuit),
+            _ => None,
+        }
+    }
+
     pub fn from_leaf_u8_to_basic_u8(value: u8) -> u8 {
         match value {
             a if a == Self::LeafLayerCircuitForMainVM as u8 => BaseLayerCircuitType::VM as u8,
@@ -1029,3 +1094,47 @@ pub fn base_circuit_type_into_recursive_leaf_circuit_type(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::verifier_builder::dyn_verifier_builder_for_recursive_circuit_type;
+    use super::*;
+    use crate::boojum::cs::implementations::verifier::Verifier;
+
+    fn gate_names(verifier: &Verifier<GoldilocksField, GoldilocksExt2>) -> Vec<String> {
+        verifier
+            .evaluators_over_specialized_columns
+            .iter()
+            .chain(verifier.evaluators_over_general_purpose_columns.iter())
+            .map(|el| el.unique_name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_from_numeric_value() {
+        for circuit_type in ZkSyncRecursionLayerStorageType::as_iter_u8() {
+            let storage_type =
+                ZkSyncRecursionLayerStorageType::from_numeric_value(circuit_type).unwrap();
+            assert_eq!(storage_type as u8, circuit_type);
+        }
+
+        assert!(ZkSyncRecursionLayerStorageType::from_numeric_value(0).is_none());
+        let after_leafs = ZkSyncRecursionLayerStorageType::LeafLayerCircuitForECPairing as u8 + 1;
+        assert!(ZkSyncRecursionLayerStorageType::from_numeric_value(after_leafs).is_none());
+    }
+
+    #[test]
+    fn test_recursion_tip_verifier_builder() {
+        let builder = dyn_verifier_builder_for_recursive_circuit_type(
+            ZkSyncRecursionLayerStorageType::RecursionTipCircuit,
+        );
+        let expected = ConcreteRecursionTipCircuitBuilder::dyn_verifier_builder::<GoldilocksExt2>();
+
+        assert_eq!(builder.geometry(), expected.geometry());
+        assert_eq!(builder.lookup_parameters(), expected.lookup_parameters());
+        assert_eq!(
+            gate_names(&builder.create_verifier()),
+            gate_names(&expected.create_verifier())
+        );
+    }
+}
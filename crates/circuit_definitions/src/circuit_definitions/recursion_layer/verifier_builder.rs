@@ -22,6 +22,9 @@ where
         ZkSyncRecursionLayerStorageType::NodeLayerCircuit => {
             ConcreteNodeLayerCircuitBuilder::dyn_verifier_builder::<EXT>()
         }
+        ZkSyncRecursionLayerStorageType::RecursionTipCircuit => {
+            ConcreteRecursionTipCircuitBuilder::dyn_verifier_builder::<EXT>()
+        }
         _ => ConcreteLeafLayerCircuitBuilder::dyn_verifier_builder::<EXT>(),
     }
 }
@@ -47,6 +50,9 @@ where
         ZkSyncRecursionLayerStorageType::NodeLayerCircuit => {
             ConcreteNodeLayerCircuitBuilder::dyn_recursive_verifier_builder::<EXT, CS>()
         }
+        ZkSyncRecursionLayerStorageType::RecursionTipCircuit => {
+            ConcreteRecursionTipCircuitBuilder::dyn_recursive_verifier_builder::<EXT, CS>()
+        }
         _ => ConcreteLeafLayerCircuitBuilder::dyn_recursive_verifier_builder::<EXT, CS>(),
     }
 }
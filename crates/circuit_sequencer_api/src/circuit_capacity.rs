@@ -0,0 +1,379 @@
+//! Prediction of the number of base layer circuits for a batch, so the sequencer can decide when to
+//! seal it. Splitting mirrors the witness generation in `zkevm_test_harness::witness`: every
+//! circuit type gets `ceil(usage / capacity)` circuits, and no circuits at all if it's unused.
+
+use crate::geometry_config::{GeometryConfig, ProtocolGeometry};
+
+/// Numeric ids of the base layer circuits, same as
+/// `zkevm_circuits::scheduler::aux::BaseLayerCircuitType`.
+pub mod circuit_type {
+    pub const VM: u8 = 1;
+    pub const DECOMMITMENTS_FILTER: u8 = 2;
+    pub const DECOMMITER: u8 = 3;
+    pub const LOG_DEMULTIPLEXER: u8 = 4;
+    pub const KECCAK_PRECOMPILE: u8 = 5;
+    pub const SHA256_PRECOMPILE: u8 = 6;
+    pub const ECRECOVER_PRECOMPILE: u8 = 7;
+    pub const RAM_VALIDATION: u8 = 8;
+    pub const STORAGE_FILTER: u8 = 9;
+    pub const STORAGE_APPLICATOR: u8 = 10;
+    pub const EVENTS_REVERTS_FILTER: u8 = 11;
+    pub const L1_MESSAGES_REVERTS_FILTER: u8 = 12;
+    pub const L1_MESSAGES_HASHER: u8 = 13;
+    pub const TRANSIENT_STORAGE_CHECKER: u8 = 14;
+    pub const SECP256R1_VERIFY: u8 = 15;
+    pub const MODEXP_PRECOMPILE: u8 = 16;
+    pub const ECADD_PRECOMPILE: u8 = 17;
+    pub const ECMUL_PRECOMPILE: u8 = 18;
+    pub const ECPAIRING_PRECOMPILE: u8 = 19;
+}
+
+/// Number of base layer circuit types that depend on the batch contents.
+pub const NUM_BASE_LAYER_CIRCUIT_TYPES: usize = 19;
+
+/// Raw usage counters of the batch. Precompile usage is measured in round function cycles, the
+/// same units as `cycles_per_*_circuit` in [`GeometryConfig`].
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct CircuitUsage {
+    pub main_vm_cycles: u64,
+    /// Decommitment requests before deduplication.
+    pub code_decommitter_sorter_queries: u64,
+    /// Words of the decommitted bytecodes.
+    pub code_decommitter_cycles: u64,
+    pub log_demuxer_queries: u64,
+    pub keccak256_cycles: u64,
+    pub sha256_cycles: u64,
+    pub ecrecover_cycles: u64,
+    pub ram_permutation_queries: u64,
+    pub storage_sorter_queries: u64,
+    /// Storage writes after deduplication.
+    pub storage_application_queries: u64,
+    pub events_sorter_queries: u64,
+    pub l1_messages_sorter_queries: u64,
+    /// L1 messages after deduplication.
+    pub l1_messages_hasher_queries: u64,
+    pub transient_storage_sorter_queries: u64,
+    pub secp256r1_verify_cycles: u64,
+    pub modexp_cycles: u64,
+    pub ecadd_cycles: u64,
+    pub ecmul_cycles: u64,
+    pub ecpairing_cycles: u64,
+}
+
+/// Predicted number of circuits per base layer circuit type.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct BaseLayerCircuitCounts {
+    pub main_vm: usize,
+    pub code_decommitter_sorter: usize,
+    pub code_decommitter: usize,
+    pub log_demuxer: usize,
+    pub keccak256: usize,
+    pub sha256: usize,
+    pub ecrecover: usize,
+    pub ram_permutation: usize,
+    pub storage_sorter: usize,
+    pub storage_application: usize,
+    pub events_sorter: usize,
+    pub l1_messages_sorter: usize,
+    pub l1_messages_hasher: usize,
+    pub transient_storage_sorter: usize,
+    pub secp256r1_verify: usize,
+    pub modexp: usize,
+    pub ecadd: usize,
+    pub ecmul: usize,
+    pub ecpairing: usize,
+}
+
+impl BaseLayerCircuitCounts {
+    /// Counts paired with the numeric circuit type, see [`circuit_type`].
+    pub fn by_circuit_type(&self) -> [(u8, usize); NUM_BASE_LAYER_CIRCUIT_TYPES] {
+        [
+            (circuit_type::VM, self.main_vm),
+            (
+                circuit_type::DECOMMITMENTS_FILTER,
+                self.code_decommitter_sorter,
+            ),
+            (circuit_type::DECOMMITER, self.code_decommitter),
+            (circuit_type::LOG_DEMULTIPLEXER, self.log_demuxer),
+            (circuit_type::KECCAK_PRECOMPILE, self.keccak256),
+            (circuit_type::SHA256_PRECOMPILE, self.sha256),
+            (circuit_type::ECRECOVER_PRECOMPILE, self.ecrecover),
+            (circuit_type::RAM_VALIDATION, self.ram_permutation),
+            (circuit_type::STORAGE_FILTER, self.storage_sorter),
+            (circuit_type::STORAGE_APPLICATOR, self.storage_application),
+            (circuit_type::EVENTS_REVERTS_FILTER, self.events_sorter),
+            (
+                circuit_type::L1_MESSAGES_REVERTS_FILTER,
+                self.l1_messages_sorter,
+            ),
+            (circuit_type::L1_MESSAGES_HASHER, self.l1_messages_hasher),
+            (
+                circuit_type::TRANSIENT_STORAGE_CHECKER,
+                self.transient_storage_sorter,
+            ),
+            (circuit_type::SECP256R1_VERIFY, self.secp256r1_verify),
+            (circuit_type::MODEXP_PRECOMPILE, self.modexp),
+            (circuit_type::ECADD_PRECOMPILE, self.ecadd),
+            (circuit_type::ECMUL_PRECOMPILE, self.ecmul),
+            (circuit_type::ECPAIRING_PRECOMPILE, self.ecpairing),
+        ]
+    }
+
+    pub fn total(&self) -> usize {
+        self.by_circuit_type().iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// Result of [`estimate_capacity`].
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct CircuitCapacityEstimate {
+    pub circuits: BaseLayerCircuitCounts,
+    /// Circuit types with non-zero usage that this geometry can't prove: the type is not supported
+    /// by the protocol version, or it must fit into a single circuit and doesn't.
+    pub unprovable_circuit_types: Vec<u8>,
+    pub should_seal: bool,
+}
+
+fn circuits_for(usage: u64, capacity: u32) -> Option<usize> {
+    if usage == 0 {
+        Some(0)
+    } else if capacity == 0 {
+        None
+    } else {
+        Some(usage.div_ceil(capacity as u64) as usize)
+    }
+}
+
+/// Predicts the number of base layer circuits, `Err` contains the types that can't be proven with
+/// this geometry, see [`CircuitCapacityEstimate::unprovable_circuit_types`].
+pub fn estimate_circuits(
+    usage: &CircuitUsage,
+    geometry: &GeometryConfig,
+) -> Result<BaseLayerCircuitCounts, Vec<u8>> {
+    let mut unprovable = vec![];
+    let mut count = |circuit_type: u8, usage: u64, capacity: u32| {
+        circuits_for(usage, capacity).unwrap_or_else(|| {
+            unprovable.push(circuit_type);
+            0
+        })
+    };
+
+    let counts = BaseLayerCircuitCounts {
+        main_vm: count(
+            circuit_type::VM,
+            usage.main_vm_cycles,
+            geometry.cycles_per_vm_snapshot,
+        ),
+        code_decommitter_sorter: count(
+            circuit_type::DECOMMITMENTS_FILTER,
+            usage.code_decommitter_sorter_queries,
+            geometry.cycles_code_decommitter_sorter,
+        ),
+        code_decommitter: count(
+            circuit_type::DECOMMITER,
+            usage.code_decommitter_cycles,
+            geometry.cycles_per_code_decommitter,
+        ),
+        log_demuxer: count(
+            circuit_type::LOG_DEMULTIPLEXER,
+            usage.log_demuxer_queries,
+            geometry.cycles_per_log_demuxer,
+        ),
+        keccak256: count(
+            circuit_type::KECCAK_PRECOMPILE,
+            usage.keccak256_cycles,
+            geometry.cycles_per_keccak256_circuit,
+        ),
+        sha256: count(
+            circuit_type::SHA256_PRECOMPILE,
+            usage.sha256_cycles,
+            geometry.cycles_per_sha256_circuit,
+        ),
+        ecrecover: count(
+            circuit_type::ECRECOVER_PRECOMPILE,
+            usage.ecrecover_cycles,
+            geometry.cycles_per_ecrecover_circuit,
+        ),
+        ram_permutation: count(
+            circuit_type::RAM_VALIDATION,
+            usage.ram_permutation_queries,
+            geometry.cycles_per_ram_permutation,
+        ),
+        storage_sorter: count(
+            circuit_type::STORAGE_FILTER,
+            usage.storage_sorter_queries,
+            geometry.cycles_per_storage_sorter,
+        ),
+        storage_application: count(
+            circuit_type::STORAGE_APPLICATOR,
+            usage.storage_application_queries,
+            geometry.cycles_per_storage_application,
+        ),
+        events_sorter: count(
+            circuit_type::EVENTS_REVERTS_FILTER,
+            usage.events_sorter_queries,
+            geometry.cycles_per_events_or_l1_messages_sorter,
+        ),
+        l1_messages_sorter: count(
+            circuit_type::L1_MESSAGES_REVERTS_FILTER,
+            usage.l1_messages_sorter_queries,
+            geometry.cycles_per_events_or_l1_messages_sorter,
+        ),
+        l1_messages_hasher: count(
+            circuit_type::L1_MESSAGES_HASHER,
+            usage.l1_messages_hasher_queries,
+            geometry.limit_for_l1_messages_pudata_hasher,
+        ),
+        transient_storage_sorter: count(
+            circuit_type::TRANSIENT_STORAGE_CHECKER,
+            usage.transient_storage_sorter_queries,
+            geometry.cycles_per_transient_storage_sorter,
+        ),
+        secp256r1_verify: count(
+            circuit_type::SECP256R1_VERIFY,
+            usage.secp256r1_verify_cycles,
+            geometry.cycles_per_secp256r1_verify_circuit,
+        ),
+        modexp: count(
+            circuit_type::MODEXP_PRECOMPILE,
+            usage.modexp_cycles,
+            geometry.cycles_per_modexp_circuit,
+        ),
+        ecadd: count(
+            circuit_type::ECADD_PRECOMPILE,
+            usage.ecadd_cycles,
+            geometry.cycles_per_ecadd_circuit,
+        ),
+        ecmul: count(
+            circuit_type::ECMUL_PRECOMPILE,
+            usage.ecmul_cycles,
+            geometry.cycles_per_ecmul_circuit,
+        ),
+        ecpairing: count(
+            circuit_type::ECPAIRING_PRECOMPILE,
+            usage.ecpairing_cycles,
+            geometry.cycles_per_ecpairing_circuit,
+        ),
+    };
+
+    // all L1 messages are hashed by a single circuit
+    if counts.l1_messages_hasher > 1 {
+        unprovable.push(circuit_type::L1_MESSAGES_HASHER);
+    }
+
+    if unprovable.is_empty() {
+        Ok(counts)
+    } else {
+        Err(unprovable)
+    }
+}
+
+/// Predicts the number of base layer circuits for the protocol version. The batch should be sealed
+/// once it needs more than `max_base_layer_circuits` circuits, or if some of its usage can't be
+/// proven at all.
+pub fn estimate_capacity(
+    usage: &CircuitUsage,
+    protocol: ProtocolGeometry,
+    max_base_layer_circuits: usize,
+) -> CircuitCapacityEstimate {
+    let geometry = protocol.config();
+    match estimate_circuits(usage, &geometry) {
+        Ok(circuits) => CircuitCapacityEstimate {
+            circuits,
+            unprovable_circuit_types: vec![],
+            should_seal: circuits.total() > max_base_layer_circuits,
+        },
+        Err(unprovable_circuit_types) => CircuitCapacityEstimate {
+            circuits: BaseLayerCircuitCounts::default(),
+            unprovable_circuit_types,
+            should_seal: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkevm_circuits::scheduler::aux::BaseLayerCircuitType;
+
+    #[test]
+    fn test_circuit_types_in_sync() {
+        // Ensure that the ids are in sync with `zkevm_circuits`.
+        let expected = [
+            BaseLayerCircuitType::VM,
+            BaseLayerCircuitType::DecommitmentsFilter,
+            BaseLayerCircuitType::Decommiter,
+            BaseLayerCircuitType::LogDemultiplexer,
+            BaseLayerCircuitType::KeccakPrecompile,
+            BaseLayerCircuitType::Sha256Precompile,
+            BaseLayerCircuitType::EcrecoverPrecompile,
+            BaseLayerCircuitType::RamValidation,
+            BaseLayerCircuitType::StorageFilter,
+            BaseLayerCircuitType::StorageApplicator,
+            BaseLayerCircuitType::EventsRevertsFilter,
+            BaseLayerCircuitType::L1MessagesRevertsFilter,
+            BaseLayerCircuitType::L1MessagesHasher,
+            BaseLayerCircuitType::TransientStorageChecker,
+            BaseLayerCircuitType::Secp256r1Verify,
+            BaseLayerCircuitType::ModexpPrecompile,
+            BaseLayerCircuitType::ECAddPrecompile,
+            BaseLayerCircuitType::ECMulPrecompile,
+            BaseLayerCircuitType::ECPairingPrecompile,
+        ];
+        let actual = BaseLayerCircuitCounts::default().by_circuit_type();
+        for (expected, (actual, _)) in expected.into_iter().zip(actual) {
+            assert_eq!(expected as u8, actual);
+        }
+    }
+
+    #[test]
+    fn test_estimate_circuits() {
+        let geometry = ProtocolGeometry::latest().config();
+        let usage = CircuitUsage {
+            main_vm_cycles: geometry.cycles_per_vm_snapshot as u64 * 3 + 1,
+            ram_permutation_queries: geometry.cycles_per_ram_permutation as u64,
+            storage_application_queries: 1,
+            l1_messages_hasher_queries: geometry.limit_for_l1_messages_pudata_hasher as u64,
+            ..Default::default()
+        };
+        let circuits = estimate_circuits(&usage, &geometry).unwrap();
+        assert_eq!(circuits.main_vm, 4);
+        assert_eq!(circuits.ram_permutation, 1);
+        assert_eq!(circuits.storage_application, 1);
+        assert_eq!(circuits.l1_messages_hasher, 1);
+        assert_eq!(circuits.keccak256, 0);
+        assert_eq!(circuits.total(), 7);
+
+        let estimate = estimate_capacity(&usage, ProtocolGeometry::latest(), 7);
+        assert!(!estimate.should_seal);
+        let estimate = estimate_capacity(&usage, ProtocolGeometry::latest(), 6);
+        assert!(estimate.should_seal);
+    }
+
+    #[test]
+    fn test_unprovable_usage() {
+        let usage = CircuitUsage {
+            main_vm_cycles: 1,
+            modexp_cycles: 1,
+            ..Default::default()
+        };
+        assert!(estimate_circuits(&usage, &ProtocolGeometry::V1_5_2.config()).is_ok());
+
+        let estimate = estimate_capacity(&usage, ProtocolGeometry::V1_5_0, usize::MAX);
+        assert!(estimate.should_seal);
+        assert_eq!(
+            estimate.unprovable_circuit_types,
+            vec![circuit_type::MODEXP_PRECOMPILE]
+        );
+
+        let geometry = ProtocolGeometry::latest().config();
+        let usage = CircuitUsage {
+            l1_messages_hasher_queries: geometry.limit_for_l1_messages_pudata_hasher as u64 + 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            estimate_circuits(&usage, &geometry),
+            Err(vec![circuit_type::L1_MESSAGES_HASHER])
+        );
+    }
+}
@@ -0,0 +1,548 @@
+//! Verification of the final (SNARK wrapper) proof of a batch, without the prover crates.
+//!
+//! The proof is checked against the public input that the L1 verifier derives from the batch
+//! commitments: the top 224 bits of `keccak256(previous batch commitment || batch commitment)`.
+//! This is the same value as the scheduler public inputs packed by the wrapper.
+
+use std::collections::HashMap;
+
+use bellman::{
+    bn256::{Bn256, Fr},
+    plonk::{
+        better_better_cs::{
+            cs::{
+                AssembledPolynomialStorage, AssembledPolynomialStorageForMonomialForms, Circuit,
+                ConstraintSystem, Gate, GateInternal, PolyIdentifier, PolynomialInConstraint,
+            },
+            gates::selector_optimized_with_d_next::SelectorOptimizedWidth4MainGateWithDNext,
+            proof::Proof,
+            setup::VerificationKey,
+            verifier::verify,
+        },
+        commitments::transcript::keccak_transcript::RollingKeccakTranscript,
+        fft::cooley_tukey_ntt::{BitReversedOmegas, OmegasInvBitreversed},
+        polynomials::{Coefficients, Polynomial, Values},
+    },
+    worker::Worker,
+    CurveAffine, Engine, Field, PrimeField, PrimeFieldRepr, SynthesisError,
+};
+use zk_evm::ethereum_types::H256;
+use zk_evm::sha3::{Digest, Keccak256};
+
+use crate::proof::FinalProof;
+
+/// The L1 verifier drops the lowest bits of the hash, so the input fits into the scalar field.
+pub const PUBLIC_INPUT_SHIFT_BITS: usize = 32;
+
+/// Verification key of the SNARK wrapper, as stored next to the other keys. The key only depends
+/// on the gate set of the circuit, which [`FinalProofCircuit`] replicates.
+pub type FinalProofVerificationKey = VerificationKey<Bn256, FinalProofCircuit>;
+
+/// Public data of the batch the final proof is for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchPublicData {
+    pub previous_batch_commitment: H256,
+    pub batch_commitment: H256,
+}
+
+impl BatchPublicData {
+    /// Equivalent of `compress_stark_pi_to_snark_pi` applied to the scheduler public inputs.
+    pub fn public_input(&self) -> Fr {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.previous_batch_commitment.as_bytes());
+        hasher.update(self.batch_commitment.as_bytes());
+        let hash = hasher.finalize();
+
+        let mut shifted = [0u8; 32];
+        shifted[PUBLIC_INPUT_SHIFT_BITS / 8..]
+            .copy_from_slice(&hash[..32 - PUBLIC_INPUT_SHIFT_BITS / 8]);
+
+        let mut repr = <Fr as PrimeField>::Repr::default();
+        repr.read_be(&shifted[..])
+            .expect("must read from a 32 byte buffer");
+        Fr::from_repr(repr).expect("224 bit value fits into the scalar field")
+    }
+}
+
+#[derive(Debug)]
+pub enum FinalProofVerificationError {
+    /// The proof is for a different batch.
+    PublicInputMismatch { expected: Fr, actual: Vec<Fr> },
+    /// The proof is malformed, e.g. doesn't match the shape of the key.
+    Malformed(SynthesisError),
+    /// The proof doesn't pass the verification.
+    InvalidProof,
+}
+
+impl std::fmt::Display for FinalProofVerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PublicInputMismatch { expected, actual } => write!(
+                f,
+                "public input mismatch: expected [{expected}], proof has {actual:?}"
+            ),
+            Self::Malformed(err) => write!(f, "malformed final proof: {err}"),
+            Self::InvalidProof => write!(f, "final proof is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for FinalProofVerificationError {}
+
+/// Verifies the final proof of the batch described by `batch`.
+pub fn verify_final_proof(
+    proof: &FinalProof,
+    vk: &FinalProofVerificationKey,
+    batch: &BatchPublicData,
+) -> Result<(), FinalProofVerificationError> {
+    let expected = batch.public_input();
+    if proof.inputs != [expected] {
+        return Err(FinalProofVerificationError::PublicInputMismatch {
+            expected,
+            actual: proof.inputs.clone(),
+        });
+    }
+
+    let is_valid = verify::<_, _, RollingKeccakTranscript<Fr>>(vk, &with_gates(proof), None)
+        .map_err(FinalProofVerificationError::Malformed)?;
+    if !is_valid {
+        return Err(FinalProofVerificationError::InvalidProof);
+    }
+
+    Ok(())
+}
+
+/// `FinalProof` is typed with a placeholder circuit, and the verifier takes the gates from
+/// the circuit type of the proof.
+fn with_gates(proof: &FinalProof) -> Proof<Bn256, FinalProofCircuit> {
+    let mut result = Proof::empty();
+    result.n = proof.n;
+    result.inputs = proof.inputs.clone();
+    result.state_polys_commitments = proof.state_polys_commitments.clone();
+    result.witness_polys_commitments = proof.witness_polys_commitments.clone();
+    result.copy_permutation_grand_product_commitment =
+        proof.copy_permutation_grand_product_commitment;
+    result.lookup_s_poly_commitment = proof.lookup_s_poly_commitment;
+    result.lookup_grand_product_commitment = proof.lookup_grand_product_commitment;
+    result.quotient_poly_parts_commitments = proof.quotient_poly_parts_commitments.clone();
+    result.state_polys_openings_at_z = proof.state_polys_openings_at_z.clone();
+    result.state_polys_openings_at_dilations = proof.state_polys_openings_at_dilations.clone();
+    result.witness_polys_openings_at_z = proof.witness_polys_openings_at_z.clone();
+    result.witness_polys_openings_at_dilations = proof.witness_polys_openings_at_dilations.clone();
+    result.gate_setup_openings_at_z = proof.gate_setup_openings_at_z.clone();
+    result.gate_selectors_openings_at_z = proof.gate_selectors_openings_at_z.clone();
+    result.copy_permutation_polys_openings_at_z =
+        proof.copy_permutation_polys_openings_at_z.clone();
+    result.copy_permutation_grand_product_opening_at_z_omega =
+        proof.copy_permutation_grand_product_opening_at_z_omega;
+    result.lookup_s_poly_opening_at_z_omega = proof.lookup_s_poly_opening_at_z_omega;
+    result.lookup_grand_product_opening_at_z_omega = proof.lookup_grand_product_opening_at_z_omega;
+    result.lookup_t_poly_opening_at_z = proof.lookup_t_poly_opening_at_z;
+    result.lookup_t_poly_opening_at_z_omega = proof.lookup_t_poly_opening_at_z_omega;
+    result.lookup_selector_poly_opening_at_z = proof.lookup_selector_poly_opening_at_z;
+    result.lookup_table_type_poly_opening_at_z = proof.lookup_table_type_poly_opening_at_z;
+    result.quotient_poly_opening_at_z = proof.quotient_poly_opening_at_z;
+    result.linearization_poly_opening_at_z = proof.linearization_poly_opening_at_z;
+    result.opening_proof_at_z = proof.opening_proof_at_z;
+    result.opening_proof_at_z_omega = proof.opening_proof_at_z_omega;
+
+    result
+}
+
+/// Circuit with the gate set of the SNARK wrapper (`ZkSyncSnarkWrapperCircuit`). It can only be
+/// used for verification.
+#[derive(Clone)]
+pub struct FinalProofCircuit {}
+
+impl Circuit<Bn256> for FinalProofCircuit {
+    type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+    fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<Bn256>>>, SynthesisError> {
+        Ok(vec![
+            SelectorOptimizedWidth4MainGateWithDNext.into_internal(),
+            Rescue5CustomGate.into_internal(),
+        ])
+    }
+
+    fn synthesize<CS: ConstraintSystem<Bn256>>(&self, _: &mut CS) -> Result<(), SynthesisError> {
+        unreachable!("the circuit is only used for verification")
+    }
+}
+
+/// Verifier side of the `Rescue5CustomGate` from `franklin_crypto`, which requires nightly.
+/// The gate constrains `b = a^2`, `c = b^2` and `d = c * a`.
+#[derive(Clone, Debug, Hash, Default)]
+struct Rescue5CustomGate;
+
+const RESCUE_GATE_POLYS: [PolynomialInConstraint; 4] = [
+    PolynomialInConstraint::from_id(PolyIdentifier::VariablesPolynomial(0)),
+    PolynomialInConstraint::from_id(PolyIdentifier::VariablesPolynomial(1)),
+    PolynomialInConstraint::from_id(PolyIdentifier::VariablesPolynomial(2)),
+    PolynomialInConstraint::from_id(PolyIdentifier::VariablesPolynomial(3)),
+];
+
+impl<E: Engine> GateInternal<E> for Rescue5CustomGate {
+    fn name(&self) -> &'static str {
+        // must match the prover side
+        "Alpha=5 custom gate for Rescue/Poseidon"
+    }
+
+    fn degree(&self) -> usize {
+        2
+    }
+
+    fn can_include_public_inputs(&self) -> bool {
+        false
+    }
+
+    fn all_queried_polynomials(&self) -> &'static [PolynomialInConstraint] {
+        &RESCUE_GATE_POLYS
+    }
+
+    fn setup_polynomials(&self) -> &'static [PolyIdentifier] {
+        &[]
+    }
+
+    fn variable_polynomials(&self) -> &'static [PolyIdentifier] {
+        const POLYS: [PolyIdentifier; 4] = [
+            PolyIdentifier::VariablesPolynomial(0),
+            PolyIdentifier::VariablesPolynomial(1),
+            PolyIdentifier::VariablesPolynomial(2),
+            PolyIdentifier::VariablesPolynomial(3),
+        ];
+
+        &POLYS
+    }
+
+    fn benefits_from_linearization(&self) -> bool {
+        false
+    }
+
+    fn linearizes_over(&self) -> &'static [PolynomialInConstraint] {
+        &[]
+    }
+
+    fn needs_opened_for_linearization(&self) -> &'static [PolynomialInConstraint] {
+        &[]
+    }
+
+    fn num_quotient_terms(&self) -> usize {
+        3
+    }
+
+    fn verify_on_row(&self, _: usize, _: &AssembledPolynomialStorage<E>, _: bool) -> E::Fr {
+        unreachable!("the gate is only used for verification")
+    }
+
+    fn contribute_into_quotient(
+        &self,
+        _: usize,
+        _: &mut AssembledPolynomialStorage<E>,
+        _: &AssembledPolynomialStorageForMonomialForms<E>,
+        _: &[E::Fr],
+        _: &BitReversedOmegas<E::Fr>,
+        _: &OmegasInvBitreversed<E::Fr>,
+        _: &Worker,
+    ) -> Result<Polynomial<E::Fr, Values>, SynthesisError> {
+        unreachable!("the gate is only used for verification")
+    }
+
+    fn contribute_into_linearization(
+        &self,
+        _: usize,
+        _: E::Fr,
+        _: &HashMap<PolynomialInConstraint, E::Fr>,
+        _: &AssembledPolynomialStorageForMonomialForms<E>,
+        _: &[E::Fr],
+        _: &Worker,
+    ) -> Result<Polynomial<E::Fr, Coefficients>, SynthesisError> {
+        unreachable!("this gate does not contribute into linearization");
+    }
+
+    fn contribute_into_verification_equation(
+        &self,
+        _: usize,
+        _: E::Fr,
+        queried_values: &HashMap<PolynomialInConstraint, E::Fr>,
+        challenges: &[E::Fr],
+    ) -> Result<E::Fr, SynthesisError> {
+        assert_eq!(
+            challenges.len(),
+            <Self as GateInternal<E>>::num_quotient_terms(self)
+        );
+
+        let mut values = [E::Fr::zero(); 4];
+        for (value, poly) in values.iter_mut().zip(RESCUE_GATE_POLYS.iter()) {
+            *value = *queried_values
+                .get(poly)
+                .ok_or(SynthesisError::AssignmentMissing)?;
+        }
+        let [a, b, c, d] = values;
+
+        // a^2 - b, b^2 - c, c * a - d
+        let mut terms = [a, b, c];
+        terms[0].square();
+        terms[0].sub_assign(&b);
+        terms[1].square();
+        terms[1].sub_assign(&c);
+        terms[2].mul_assign(&a);
+        terms[2].sub_assign(&d);
+
+        let mut result = E::Fr::zero();
+        for (mut term, challenge) in terms.into_iter().zip(challenges.iter()) {
+            term.mul_assign(challenge);
+            result.add_assign(&term);
+        }
+
+        Ok(result)
+    }
+
+    fn put_public_inputs_into_selector_id(&self) -> Option<usize> {
+        None
+    }
+
+    fn box_clone(&self) -> Box<dyn GateInternal<E>> {
+        Box::from(self.clone())
+    }
+
+    fn contribute_into_linearization_commitment(
+        &self,
+        _: usize,
+        _: E::Fr,
+        _: &HashMap<PolynomialInConstraint, E::Fr>,
+        _: &HashMap<PolyIdentifier, E::G1Affine>,
+        _: &[E::Fr],
+    ) -> Result<<E::G1Affine as CurveAffine>::Projective, SynthesisError> {
+        unreachable!("this gate does not contribute into linearization");
+    }
+}
+
+impl<E: Engine> Gate<E> for Rescue5CustomGate {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bellman::kate_commitment::{Crs, CrsForMonomialForm};
+    use bellman::plonk::better_better_cs::cs::{
+        ArithmeticTerm, LookupTableApplication, MainGate, MainGateTerm,
+        PlonkCsWidth4WithNextStepAndCustomGatesParams, ProvingAssembly, SetupAssembly,
+    };
+    use snark_wrapper::franklin_crypto::plonk::circuit::allocated_num::AllocatedNum;
+    use snark_wrapper::franklin_crypto::plonk::circuit::custom_rescue_gate::{
+        apply_5th_power, Rescue5CustomGate as ProverRescue5CustomGate,
+    };
+    use zk_evm::ethereum_types::U256;
+
+    /// Small circuit with the gate set and lookups of the SNARK wrapper, exposing the public
+    /// input of a batch. The real wrapper needs the 2^24 CRS, this one is proven over a locally
+    /// generated one, which is enough to exercise the verifier end to end.
+    #[derive(Clone)]
+    struct TestWrapperCircuit {
+        public_input: Fr,
+    }
+
+    impl Circuit<Bn256> for TestWrapperCircuit {
+        type MainGate = SelectorOptimizedWidth4MainGateWithDNext;
+
+        fn declare_used_gates() -> Result<Vec<Box<dyn GateInternal<Bn256>>>, SynthesisError> {
+            Ok(vec![
+                SelectorOptimizedWidth4MainGateWithDNext.into_internal(),
+                ProverRescue5CustomGate.into_internal(),
+            ])
+        }
+
+        fn synthesize<CS: ConstraintSystem<Bn256>>(
+            &self,
+            cs: &mut CS,
+        ) -> Result<(), SynthesisError> {
+            let columns = vec![
+                PolyIdentifier::VariablesPolynomial(0),
+                PolyIdentifier::VariablesPolynomial(1),
+                PolyIdentifier::VariablesPolynomial(2),
+            ];
+            let range_table = cs.add_table(LookupTableApplication::new_range_table_of_width_3(
+                8, columns,
+            )?)?;
+
+            let input = AllocatedNum::alloc_input(cs, || Ok(self.public_input))?;
+            apply_5th_power(cs, &input, None)?;
+
+            // range check of a byte, the way the wrapper uses the table
+            let byte = cs.alloc(|| Ok(Fr::from_str("42").unwrap()))?;
+            let zero = cs.get_explicit_zero()?;
+            cs.begin_gates_batch_for_step()?;
+            let mut term = MainGateTerm::<Bn256>::new();
+            term.add_assign(ArithmeticTerm::from_variable_and_coeff(byte, Fr::zero()));
+            term.add_assign(ArithmeticTerm::from_variable_and_coeff(zero, Fr::zero()));
+            term.add_assign(ArithmeticTerm::from_variable_and_coeff(zero, Fr::zero()));
+            let (vars, coeffs) =
+                CS::MainGate::format_linear_term_with_duplicates(term, CS::get_dummy_variable())?;
+            cs.new_gate_in_batch(&CS::MainGate::default(), &coeffs, &vars, &[])?;
+            cs.apply_single_lookup_gate(&vars[..3], range_table)?;
+            cs.end_gates_batch_for_step()?;
+
+            Ok(())
+        }
+    }
+
+    /// Proof and key for `batch`, converted to the types a sequencer deals with.
+    fn prove(batch: &BatchPublicData) -> (FinalProof, FinalProofVerificationKey) {
+        // The prover divides the openings at `z` and `z * omega` in a `Worker::scope` that only
+        // handles the first polynomial of every chunk, so with a single CPU the second opening
+        // is left undivided and the proof doesn't verify. Two threads split them into separate
+        // chunks whatever the machine.
+        let worker = Worker::new_with_cpus(2);
+        let circuit = TestWrapperCircuit {
+            public_input: batch.public_input(),
+        };
+
+        let mut assembly = SetupAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        circuit.synthesize(&mut assembly).unwrap();
+        assembly.finalize();
+        let setup = assembly
+            .create_setup::<TestWrapperCircuit>(&worker)
+            .unwrap();
+
+        let mut assembly = ProvingAssembly::<
+            Bn256,
+            PlonkCsWidth4WithNextStepAndCustomGatesParams,
+            SelectorOptimizedWidth4MainGateWithDNext,
+        >::new();
+        circuit.synthesize(&mut assembly).unwrap();
+        assert!(assembly.is_satisfied());
+        assembly.finalize();
+
+        let crs =
+            Crs::<Bn256, CrsForMonomialForm>::crs_42(assembly.n().next_power_of_two(), &worker);
+        let proof = assembly
+            .create_proof::<TestWrapperCircuit, RollingKeccakTranscript<Fr>>(
+                &worker, &setup, &crs, None,
+            )
+            .unwrap();
+        let vk = VerificationKey::from_setup(&setup, &worker, &crs).unwrap();
+
+        // Serialized forms don't depend on the circuit type.
+        let mut encoded_proof = vec![];
+        proof.write(&mut encoded_proof).unwrap();
+        let mut encoded_vk = vec![];
+        vk.write(&mut encoded_vk).unwrap();
+
+        (
+            FinalProof::read(&encoded_proof[..]).unwrap(),
+            FinalProofVerificationKey::read(&encoded_vk[..]).unwrap(),
+        )
+    }
+
+    fn batch() -> BatchPublicData {
+        BatchPublicData {
+            previous_batch_commitment: H256::repeat_byte(0x11),
+            batch_commitment: H256::repeat_byte(0x22),
+        }
+    }
+
+    #[test]
+    fn test_public_input_matches_l1_verifier() {
+        let batch = batch();
+        let hash = Keccak256::digest(
+            [
+                batch.previous_batch_commitment.as_bytes(),
+                batch.batch_commitment.as_bytes(),
+            ]
+            .concat(),
+        );
+        // `uint256(keccak256(abi.encodePacked(prevCommitment, commitment))) >> 32`
+        let expected = U256::from_big_endian(&hash) >> PUBLIC_INPUT_SHIFT_BITS;
+
+        let mut bytes = [0u8; 32];
+        batch
+            .public_input()
+            .into_repr()
+            .write_be(&mut bytes[..])
+            .unwrap();
+        assert_eq!(U256::from_big_endian(&bytes), expected);
+    }
+
+    #[test]
+    fn test_public_input_mismatch() {
+        let mut proof = FinalProof::empty();
+        proof.inputs = vec![Fr::one()];
+
+        let vk = FinalProofVerificationKey::empty();
+        let err = verify_final_proof(&proof, &vk, &batch()).unwrap_err();
+        assert!(matches!(
+            err,
+            FinalProofVerificationError::PublicInputMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn test_rescue_gate_matches_prover() {
+        let ours: Box<dyn GateInternal<Bn256>> = Rescue5CustomGate.into_internal();
+        let prover: Box<dyn GateInternal<Bn256>> = ProverRescue5CustomGate.into_internal();
+
+        assert_eq!(ours.name(), prover.name());
+        assert_eq!(ours.degree(), prover.degree());
+        assert_eq!(ours.num_quotient_terms(), prover.num_quotient_terms());
+        assert_eq!(
+            ours.all_queried_polynomials(),
+            prover.all_queried_polynomials()
+        );
+        assert_eq!(ours.setup_polynomials(), prover.setup_polynomials());
+        assert_eq!(ours.variable_polynomials(), prover.variable_polynomials());
+        assert_eq!(
+            ours.benefits_from_linearization(),
+            prover.benefits_from_linearization()
+        );
+
+        let values = [3u64, 5, 7, 11].map(|value| Fr::from_str(&value.to_string()).unwrap());
+        let queried_values: HashMap<_, _> = RESCUE_GATE_POLYS.into_iter().zip(values).collect();
+        let challenges = [2u64, 13, 17].map(|value| Fr::from_str(&value.to_string()).unwrap());
+        assert_eq!(
+            ours.contribute_into_verification_equation(0, Fr::one(), &queried_values, &challenges)
+                .unwrap(),
+            prover
+                .contribute_into_verification_equation(0, Fr::one(), &queried_values, &challenges)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_final_proof_is_verified() {
+        let batch = batch();
+        let (proof, vk) = prove(&batch);
+        assert!(!vk.lookup_tables_commitments.is_empty());
+
+        verify_final_proof(&proof, &vk, &batch).unwrap();
+
+        let mut tampered = proof;
+        tampered.quotient_poly_opening_at_z.add_assign(&Fr::one());
+        assert!(matches!(
+            verify_final_proof(&tampered, &vk, &batch),
+            Err(FinalProofVerificationError::InvalidProof)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_final_proof_is_rejected() {
+        let batch = batch();
+        let (proof, vk) = prove(&batch);
+
+        let mut other_batch = batch;
+        other_batch.batch_commitment = H256::repeat_byte(0x33);
+        assert!(matches!(
+            verify_final_proof(&proof, &vk, &other_batch),
+            Err(FinalProofVerificationError::PublicInputMismatch { .. })
+        ));
+
+        let mut tampered = proof;
+        tampered.state_polys_openings_at_z.pop();
+        assert!(matches!(
+            verify_final_proof(&tampered, &vk, &batch),
+            Err(FinalProofVerificationError::Malformed(_))
+        ));
+    }
+}
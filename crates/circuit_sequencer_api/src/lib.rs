@@ -1,8 +1,11 @@
 #![allow(clippy::bool_comparison)]
 
+pub mod circuit_capacity;
+pub mod final_proof_verifier;
 pub mod geometry_config;
 pub mod proof;
 pub mod sort_storage_access;
+pub mod state_diff_compression;
 
 // IMPORTANT! This constant should never be just changed, since it's used in multiple versions
 // of MultiVM, e.g. for old protocol versions too.
@@ -1,5 +1,6 @@
 use rayon::prelude::*;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
 use zk_evm::{
     aux_structures::{LogQuery, LogQueryWithExtendedEnumeration, Timestamp},
     ethereum_types::{H160, U256},
@@ -13,185 +14,120 @@ pub struct StorageSlotHistoryKeeper {
     pub did_read_at_depth_zero: bool,
 }
 
-// IMPORTANT! This function is being used by all the protocol versions in MultiVM, so changing it
-// may cause a change in behavior for existing protocol versions.
-pub fn sort_storage_access_queries(
-    unsorted_storage_queries: impl IntoIterator<Item = LogQuery>,
-) -> (Vec<LogQueryWithExtendedEnumeration>, Vec<LogQuery>) {
-    let mut sorted_storage_queries_with_extra_timestamp: Vec<_> = unsorted_storage_queries
-        .into_iter()
-        .enumerate()
-        .map(|(i, el)| LogQueryWithExtendedEnumeration {
-            raw_query: el,
-            extended_timestamp: i as u32,
-        })
-        .collect();
-
-    sorted_storage_queries_with_extra_timestamp.par_sort_by(|a, b| {
-        match a.raw_query.shard_id.cmp(&b.raw_query.shard_id) {
-            Ordering::Equal => match a.raw_query.address.cmp(&b.raw_query.address) {
-                Ordering::Equal => match a.raw_query.key.cmp(&b.raw_query.key) {
-                    Ordering::Equal => a.extended_timestamp.cmp(&b.extended_timestamp),
-                    r => r,
-                },
-                r => r,
-            },
-            r => r,
+impl StorageSlotHistoryKeeper {
+    /// Applies the next query to the slot, queries must come in the order of their extended timestamps.
+    pub fn apply_query(&mut self, el: &LogQueryWithExtendedEnumeration) {
+        if self.current_value.is_none() {
+            assert!(self.initial_value.is_none(), "invalid for query {:?}", el);
+            // first read potentially
+            if el.raw_query.rw_flag == false {
+                self.did_read_at_depth_zero = true;
+            }
+        } else {
+            // explicit read at zero
+            if el.raw_query.rw_flag == false && self.changes_stack.is_empty() {
+                self.did_read_at_depth_zero = true;
+            }
         }
-    });
-
-    let mut deduplicated_storage_queries = vec![];
 
-    // now just implement the logic to sort and deduplicate
-    let mut it = sorted_storage_queries_with_extra_timestamp
-        .iter()
-        .peekable();
-
-    loop {
-        if it.peek().is_none() {
-            break;
+        if self.current_value.is_none() {
+            assert!(self.initial_value.is_none(), "invalid for query {:?}", el);
+            if el.raw_query.rw_flag == false {
+                self.initial_value = Some(el.raw_query.read_value);
+                self.current_value = Some(el.raw_query.read_value);
+            } else {
+                assert!(el.raw_query.rollback == false);
+                self.initial_value = Some(el.raw_query.read_value);
+                self.current_value = Some(el.raw_query.read_value);
+                // note: We apply updates few lines later
+            }
         }
 
-        // need it to remove "peek"'s mutable borrow
-        #[allow(suspicious_double_ref_op)]
-        let candidate = it.peek().unwrap().clone();
-
-        let subit = it.clone().take_while(|el| {
-            el.raw_query.shard_id == candidate.raw_query.shard_id
-                && el.raw_query.address == candidate.raw_query.address
-                && el.raw_query.key == candidate.raw_query.key
-        });
-
-        let mut current_element_history = StorageSlotHistoryKeeper::default();
-
-        for el in subit {
-            let _ = it.next().unwrap();
-
-            if current_element_history.current_value.is_none() {
-                assert!(
-                    current_element_history.initial_value.is_none(),
+        if el.raw_query.rw_flag == false {
+            assert_eq!(
+                &el.raw_query.read_value,
+                self.current_value.as_ref().unwrap(),
+                "invalid for query {:?}",
+                el
+            );
+            // and do not place reads into the stack
+        } else {
+            // write-like things manipulate the stack
+            if el.raw_query.rollback == false {
+                // write and push to the stack
+                assert_eq!(
+                    &el.raw_query.read_value,
+                    self.current_value.as_ref().unwrap(),
                     "invalid for query {:?}",
                     el
                 );
-                // first read potentially
-                if el.raw_query.rw_flag == false {
-                    current_element_history.did_read_at_depth_zero = true;
-                }
+                self.current_value = Some(el.raw_query.written_value);
+                self.changes_stack.push(el.clone());
             } else {
-                // explicit read at zero
-                if el.raw_query.rw_flag == false && current_element_history.changes_stack.is_empty()
-                {
-                    current_element_history.did_read_at_depth_zero = true;
-                }
-            }
-
-            if current_element_history.current_value.is_none() {
-                assert!(
-                    current_element_history.initial_value.is_none(),
+                // pop from stack and self-check
+                let popped_change = self.changes_stack.pop().unwrap();
+                // we do not explicitly swap values, and use rollback flag instead, so compare this way
+                assert_eq!(
+                    el.raw_query.read_value, popped_change.raw_query.read_value,
                     "invalid for query {:?}",
                     el
                 );
-                if el.raw_query.rw_flag == false {
-                    current_element_history.initial_value = Some(el.raw_query.read_value);
-                    current_element_history.current_value = Some(el.raw_query.read_value);
-                } else {
-                    assert!(el.raw_query.rollback == false);
-                    current_element_history.initial_value = Some(el.raw_query.read_value);
-                    current_element_history.current_value = Some(el.raw_query.read_value);
-                    // note: We apply updates few lines later
-                }
-            }
-
-            if el.raw_query.rw_flag == false {
                 assert_eq!(
-                    &el.raw_query.read_value,
-                    current_element_history.current_value.as_ref().unwrap(),
+                    el.raw_query.written_value, popped_change.raw_query.written_value,
                     "invalid for query {:?}",
                     el
                 );
-                // and do not place reads into the stack
-            } else {
-                // write-like things manipulate the stack
-                if el.raw_query.rollback == false {
-                    // write and push to the stack
-                    assert_eq!(
-                        &el.raw_query.read_value,
-                        current_element_history.current_value.as_ref().unwrap(),
-                        "invalid for query {:?}",
-                        el
-                    );
-                    current_element_history.current_value = Some(el.raw_query.written_value);
-                    current_element_history.changes_stack.push(el.clone());
-                } else {
-                    // pop from stack and self-check
-                    let popped_change = current_element_history.changes_stack.pop().unwrap();
-                    // we do not explicitly swap values, and use rollback flag instead, so compare this way
-                    assert_eq!(
-                        el.raw_query.read_value, popped_change.raw_query.read_value,
-                        "invalid for query {:?}",
-                        el
-                    );
-                    assert_eq!(
-                        el.raw_query.written_value, popped_change.raw_query.written_value,
-                        "invalid for query {:?}",
-                        el
-                    );
-                    assert_eq!(
-                        &el.raw_query.written_value,
-                        current_element_history.current_value.as_ref().unwrap(),
-                        "invalid for query {:?}",
-                        el
-                    );
-                    // check that we properly apply rollbacks
-                    assert_eq!(
-                        el.raw_query.shard_id, popped_change.raw_query.shard_id,
-                        "invalid for query {:?}",
-                        el
-                    );
-                    assert_eq!(
-                        el.raw_query.address, popped_change.raw_query.address,
-                        "invalid for query {:?}",
-                        el
-                    );
-                    assert_eq!(
-                        el.raw_query.key, popped_change.raw_query.key,
-                        "invalid for query {:?}",
-                        el
-                    );
-                    // apply rollback
-                    current_element_history.current_value = Some(el.raw_query.read_value);
-                    // our convension
-                }
+                assert_eq!(
+                    &el.raw_query.written_value,
+                    self.current_value.as_ref().unwrap(),
+                    "invalid for query {:?}",
+                    el
+                );
+                // check that we properly apply rollbacks
+                assert_eq!(
+                    el.raw_query.shard_id, popped_change.raw_query.shard_id,
+                    "invalid for query {:?}",
+                    el
+                );
+                assert_eq!(
+                    el.raw_query.address, popped_change.raw_query.address,
+                    "invalid for query {:?}",
+                    el
+                );
+                assert_eq!(
+                    el.raw_query.key, popped_change.raw_query.key,
+                    "invalid for query {:?}",
+                    el
+                );
+                // apply rollback
+                self.current_value = Some(el.raw_query.read_value);
+                // our convension
             }
         }
+    }
 
-        if current_element_history.did_read_at_depth_zero == false
-            && current_element_history.changes_stack.is_empty()
-        {
+    /// Returns the query that represents the net effect of all the applied queries, if any.
+    pub fn deduplicated_query(&self, shard_id: u8, address: H160, key: U256) -> Option<LogQuery> {
+        if self.did_read_at_depth_zero == false && self.changes_stack.is_empty() {
             // whatever happened there didn't produce any final changes
-            assert_eq!(
-                current_element_history.initial_value.unwrap(),
-                current_element_history.current_value.unwrap()
-            );
+            assert_eq!(self.initial_value.unwrap(), self.current_value.unwrap());
             // here we know that last write was a rollback, and there we no reads after it (otherwise "did_read_at_depth_zero" == true),
             // so whatever was an initial value in storage slot it's not ever observed, and we do not need to issue even read here
-            continue;
-        } else if current_element_history.initial_value.unwrap()
-            == current_element_history.current_value.unwrap()
-        {
+            return None;
+        } else if self.initial_value.unwrap() == self.current_value.unwrap() {
             // no change, but we may need protective read
-            if current_element_history.did_read_at_depth_zero {
+            if self.did_read_at_depth_zero {
                 // protective read
                 let sorted_log_query = create_partially_filled_from_fields(
-                    candidate.raw_query.shard_id,
-                    candidate.raw_query.address,
-                    candidate.raw_query.key,
-                    current_element_history.initial_value.unwrap(),
-                    current_element_history.current_value.unwrap(),
+                    shard_id,
+                    address,
+                    key,
+                    self.initial_value.unwrap(),
+                    self.current_value.unwrap(),
                     false,
                 );
 
-                deduplicated_storage_queries.push(sorted_log_query);
+                return Some(sorted_log_query);
             } else {
                 // we didn't read at depth zero, so it's something like
                 // - write cell from a into b
@@ -207,20 +143,20 @@ pub fn sort_storage_access_queries(
                 // protects us in case of write - rollback - read, so we only need to degrade write into
                 // read here if the latest write wasn't a rollback
 
-                if current_element_history.changes_stack.is_empty() == false {
+                if self.changes_stack.is_empty() == false {
                     // it means that we did accumlate some changes, even though in NET result
                     // it CLAIMS that it didn't change a value
                     // degrade to protective read
                     let sorted_log_query = create_partially_filled_from_fields(
-                        candidate.raw_query.shard_id,
-                        candidate.raw_query.address,
-                        candidate.raw_query.key,
-                        current_element_history.initial_value.unwrap(),
-                        current_element_history.current_value.unwrap(),
+                        shard_id,
+                        address,
+                        key,
+                        self.initial_value.unwrap(),
+                        self.current_value.unwrap(),
                         false,
                     );
 
-                    deduplicated_storage_queries.push(sorted_log_query);
+                    return Some(sorted_log_query);
                 } else {
                     // Whatever has happened we rolled it back completely, so unless
                     // there was a need for protective read at depth 0, we do not need
@@ -232,14 +168,82 @@ pub fn sort_storage_access_queries(
         } else {
             // it's final net write
             let sorted_log_query = create_partially_filled_from_fields(
-                candidate.raw_query.shard_id,
-                candidate.raw_query.address,
-                candidate.raw_query.key,
-                current_element_history.initial_value.unwrap(),
-                current_element_history.current_value.unwrap(),
+                shard_id,
+                address,
+                key,
+                self.initial_value.unwrap(),
+                self.current_value.unwrap(),
                 true,
             );
 
+            return Some(sorted_log_query);
+        }
+
+        None
+    }
+}
+
+// IMPORTANT! This function is being used by all the protocol versions in MultiVM, so changing it
+// may cause a change in behavior for existing protocol versions.
+pub fn sort_storage_access_queries(
+    unsorted_storage_queries: impl IntoIterator<Item = LogQuery>,
+) -> (Vec<LogQueryWithExtendedEnumeration>, Vec<LogQuery>) {
+    let mut sorted_storage_queries_with_extra_timestamp: Vec<_> = unsorted_storage_queries
+        .into_iter()
+        .enumerate()
+        .map(|(i, el)| LogQueryWithExtendedEnumeration {
+            raw_query: el,
+            extended_timestamp: i as u32,
+        })
+        .collect();
+
+    sorted_storage_queries_with_extra_timestamp.par_sort_by(|a, b| {
+        match a.raw_query.shard_id.cmp(&b.raw_query.shard_id) {
+            Ordering::Equal => match a.raw_query.address.cmp(&b.raw_query.address) {
+                Ordering::Equal => match a.raw_query.key.cmp(&b.raw_query.key) {
+                    Ordering::Equal => a.extended_timestamp.cmp(&b.extended_timestamp),
+                    r => r,
+                },
+                r => r,
+            },
+            r => r,
+        }
+    });
+
+    let mut deduplicated_storage_queries = vec![];
+
+    // now just implement the logic to sort and deduplicate
+    let mut it = sorted_storage_queries_with_extra_timestamp
+        .iter()
+        .peekable();
+
+    loop {
+        if it.peek().is_none() {
+            break;
+        }
+
+        // need it to remove "peek"'s mutable borrow
+        #[allow(suspicious_double_ref_op)]
+        let candidate = it.peek().unwrap().clone();
+
+        let subit = it.clone().take_while(|el| {
+            el.raw_query.shard_id == candidate.raw_query.shard_id
+                && el.raw_query.address == candidate.raw_query.address
+                && el.raw_query.key == candidate.raw_query.key
+        });
+
+        let mut current_element_history = StorageSlotHistoryKeeper::default();
+
+        for el in subit {
+            let _ = it.next().unwrap();
+            current_element_history.apply_query(el);
+        }
+
+        if let Some(sorted_log_query) = current_element_history.deduplicated_query(
+            candidate.raw_query.shard_id,
+            candidate.raw_query.address,
+            candidate.raw_query.key,
+        ) {
             deduplicated_storage_queries.push(sorted_log_query);
         }
     }
@@ -250,6 +254,121 @@ pub fn sort_storage_access_queries(
     )
 }
 
+type StorageSlotKey = (u8, H160, U256);
+
+#[derive(Debug, Default)]
+struct StorageSlotAccesses {
+    queries: Vec<LogQueryWithExtendedEnumeration>,
+    history: StorageSlotHistoryKeeper,
+}
+
+/// Position in the query stream of [`StorageAccessAccumulator`] that can be rolled back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageAccessSnapshot(usize);
+
+/// Incremental version of [`sort_storage_access_queries`]. Queries are grouped by storage slot
+/// and the slot state is updated as they arrive, so the final output is produced without sorting
+/// the whole batch. The output is exactly the same as of [`sort_storage_access_queries`] called
+/// on all the queries pushed (and not rolled back) in the same order.
+#[derive(Debug, Default)]
+pub struct StorageAccessAccumulator {
+    slots: BTreeMap<StorageSlotKey, StorageSlotAccesses>,
+    // slot of every pushed query, in the order of pushing
+    journal: Vec<StorageSlotKey>,
+}
+
+impl StorageAccessAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of queries pushed so far.
+    pub fn len(&self) -> usize {
+        self.journal.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.journal.is_empty()
+    }
+
+    pub fn push(&mut self, query: LogQuery) {
+        let slot_key = (query.shard_id, query.address, query.key);
+        let query = LogQueryWithExtendedEnumeration {
+            raw_query: query,
+            extended_timestamp: self.journal.len() as u32,
+        };
+        let slot = self.slots.entry(slot_key).or_default();
+        slot.history.apply_query(&query);
+        slot.queries.push(query);
+        self.journal.push(slot_key);
+    }
+
+    pub fn extend(&mut self, queries: impl IntoIterator<Item = LogQuery>) {
+        for query in queries {
+            self.push(query);
+        }
+    }
+
+    /// Takes a snapshot, e.g. before executing a transaction.
+    pub fn snapshot(&self) -> StorageAccessSnapshot {
+        StorageAccessSnapshot(self.journal.len())
+    }
+
+    /// Removes all the queries pushed after the snapshot was taken, e.g. if a transaction was
+    /// reverted by the sequencer.
+    pub fn rollback_to(&mut self, snapshot: StorageAccessSnapshot) {
+        assert!(
+            snapshot.0 <= self.journal.len(),
+            "snapshot is newer than the accumulator state"
+        );
+
+        let mut touched_slots = BTreeSet::new();
+        for slot_key in self.journal.drain(snapshot.0..) {
+            let slot = self.slots.get_mut(&slot_key).unwrap();
+            slot.queries.pop();
+            touched_slots.insert(slot_key);
+        }
+
+        // replay the remaining history of every touched slot
+        for slot_key in touched_slots {
+            let slot = self.slots.get_mut(&slot_key).unwrap();
+            if slot.queries.is_empty() {
+                self.slots.remove(&slot_key);
+                continue;
+            }
+            slot.history = StorageSlotHistoryKeeper::default();
+            for query in slot.queries.iter() {
+                slot.history.apply_query(query);
+            }
+        }
+    }
+
+    /// Current net storage diff, same as the second output of [`Self::finalize`].
+    pub fn deduplicated_queries(&self) -> Vec<LogQuery> {
+        self.slots
+            .iter()
+            .filter_map(|((shard_id, address, key), slot)| {
+                slot.history.deduplicated_query(*shard_id, *address, *key)
+            })
+            .collect()
+    }
+
+    /// Returns the sorted queries and the deduplicated ones, in the same format as
+    /// [`sort_storage_access_queries`].
+    pub fn finalize(self) -> (Vec<LogQueryWithExtendedEnumeration>, Vec<LogQuery>) {
+        let deduplicated_storage_queries = self.deduplicated_queries();
+        let mut sorted_storage_queries_with_extra_timestamp = Vec::with_capacity(self.len());
+        for slot in self.slots.into_values() {
+            sorted_storage_queries_with_extra_timestamp.extend(slot.queries);
+        }
+
+        (
+            sorted_storage_queries_with_extra_timestamp,
+            deduplicated_storage_queries,
+        )
+    }
+}
+
 pub fn sort_transient_storage_access_queries(
     unsorted_storage_queries: impl IntoIterator<Item = LogQuery>,
 ) -> Vec<LogQueryWithExtendedEnumeration> {
@@ -308,3 +427,169 @@ fn create_partially_filled_from_fields(
         is_service: false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::collections::HashMap;
+
+    /// Generates storage queries as the VM would, including nested frames and their rollbacks.
+    #[derive(Clone)]
+    struct QueryGenerator {
+        storage: HashMap<StorageSlotKey, U256>,
+        // writes of every open frame, to be rolled back on revert
+        frames: Vec<Vec<LogQuery>>,
+        timestamp: u32,
+    }
+
+    impl QueryGenerator {
+        fn new() -> Self {
+            Self {
+                storage: HashMap::new(),
+                frames: vec![vec![]],
+                timestamp: 0,
+            }
+        }
+
+        fn query(&mut self, slot_key: StorageSlotKey, written_value: Option<U256>) -> LogQuery {
+            self.timestamp += 1;
+            let read_value = *self
+                .storage
+                .entry(slot_key)
+                .or_insert_with(|| U256::from(slot_key.2.low_u64() % 3));
+            LogQuery {
+                timestamp: Timestamp(self.timestamp),
+                tx_number_in_block: 0,
+                aux_byte: 0,
+                shard_id: slot_key.0,
+                address: slot_key.1,
+                key: slot_key.2,
+                read_value,
+                written_value: written_value.unwrap_or(read_value),
+                rw_flag: written_value.is_some(),
+                rollback: false,
+                is_service: false,
+            }
+        }
+
+        fn next_queries(&mut self, rng: &mut impl Rng) -> Vec<LogQuery> {
+            let slot_key = (
+                rng.gen_range(0..2),
+                H160::from_low_u64_be(rng.gen_range(0..3)),
+                U256::from(rng.gen_range(0..4u64)),
+            );
+            match rng.gen_range(0..10) {
+                0..=3 => vec![self.query(slot_key, None)],
+                4..=6 => {
+                    let query = self.query(slot_key, Some(U256::from(rng.gen_range(0..3u64))));
+                    self.storage.insert(slot_key, query.written_value);
+                    self.frames.last_mut().unwrap().push(query);
+                    vec![query]
+                }
+                7 => {
+                    self.frames.push(vec![]);
+                    vec![]
+                }
+                8 => self.commit_frame(),
+                _ => self.revert_frame(),
+            }
+        }
+
+        fn commit_frame(&mut self) -> Vec<LogQuery> {
+            if self.frames.len() > 1 {
+                let writes = self.frames.pop().unwrap();
+                self.frames.last_mut().unwrap().extend(writes);
+            }
+            vec![]
+        }
+
+        fn revert_frame(&mut self) -> Vec<LogQuery> {
+            if self.frames.len() == 1 {
+                return vec![];
+            }
+            let writes = self.frames.pop().unwrap();
+            writes
+                .into_iter()
+                .rev()
+                .map(|mut query| {
+                    self.timestamp += 1;
+                    self.storage
+                        .insert((query.shard_id, query.address, query.key), query.read_value);
+                    query.timestamp = Timestamp(self.timestamp);
+                    query.rollback = true;
+                    query
+                })
+                .collect()
+        }
+    }
+
+    fn assert_same_output(accumulator: StorageAccessAccumulator, queries: &[LogQuery]) {
+        let expected = sort_storage_access_queries(queries.iter().copied());
+        assert_eq!(accumulator.deduplicated_queries(), expected.1);
+
+        let actual = accumulator.finalize();
+        assert_eq!(actual.0.len(), expected.0.len());
+        for (a, b) in actual.0.iter().zip(expected.0.iter()) {
+            assert_eq!(a.raw_query, b.raw_query);
+            assert_eq!(a.extended_timestamp, b.extended_timestamp);
+        }
+        assert_eq!(actual.1, expected.1);
+    }
+
+    #[test]
+    fn accumulator_matches_batch_sorting() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..50 {
+            let mut generator = QueryGenerator::new();
+            let mut accumulator = StorageAccessAccumulator::new();
+            let mut queries = vec![];
+            for _ in 0..rng.gen_range(0..200) {
+                let new_queries = generator.next_queries(&mut rng);
+                accumulator.extend(new_queries.iter().copied());
+                queries.extend(new_queries);
+            }
+            while generator.frames.len() > 1 {
+                generator.commit_frame();
+            }
+
+            assert_eq!(accumulator.len(), queries.len());
+            assert_same_output(accumulator, &queries);
+        }
+    }
+
+    #[test]
+    fn accumulator_rollback_to_snapshot() {
+        let mut rng = StdRng::seed_from_u64(1337);
+        for _ in 0..50 {
+            let mut generator = QueryGenerator::new();
+            let mut accumulator = StorageAccessAccumulator::new();
+            let mut queries = vec![];
+            for _ in 0..rng.gen_range(0..10) {
+                // every transaction is executed in its own frame
+                let snapshot = accumulator.snapshot();
+                let generator_snapshot = generator.clone();
+                let queries_len = queries.len();
+                generator.frames.push(vec![]);
+                for _ in 0..rng.gen_range(0..30) {
+                    let new_queries = generator.next_queries(&mut rng);
+                    accumulator.extend(new_queries.iter().copied());
+                    queries.extend(new_queries);
+                }
+                while generator.frames.len() > 2 {
+                    generator.commit_frame();
+                }
+                generator.commit_frame();
+
+                if rng.gen_bool(0.3) {
+                    accumulator.rollback_to(snapshot);
+                    generator = generator_snapshot;
+                    queries.truncate(queries_len);
+                }
+                assert_eq!(accumulator.len(), queries.len());
+            }
+
+            assert_same_output(accumulator, &queries);
+        }
+    }
+}
@@ -0,0 +1,681 @@
+//! Compression of state diffs for L1 pubdata, as verified by the `Compressor` system contract.
+//!
+//! Format: header (1 byte version, 3 bytes length of the rest, 1 byte enumeration index size),
+//! followed by the number of initial writes (2 bytes) and the writes themselves.
+//! Initial writes are encoded as `derived key (32 bytes) || metadata (1 byte) || value`,
+//! repeated writes as `enumeration index (enumeration index size bytes) || metadata || value`.
+//! The lowest 3 bits of the metadata are the operation, the rest is the length of the value,
+//! see [`CompressionOperation`].
+
+use std::cmp::Ordering;
+
+use zk_evm::ethereum_types::{Address, U256};
+
+pub const STATE_DIFF_COMPRESSION_VERSION_NUMBER: u8 = 1;
+/// Enumeration index size used by the encoder, in bytes.
+pub const DEFAULT_ENUMERATION_INDEX_SIZE: u8 = 4;
+pub const MAX_ENUMERATION_INDEX_SIZE: u8 = 8;
+
+const OPERATION_BITMASK: u8 = 7;
+const LENGTH_BITS_OFFSET: u8 = 3;
+const HEADER_LEN: usize = 5;
+
+/// Net change of a single storage slot over the batch, same data as `StateDiffRecord` in
+/// `circuit_encodings`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StateDiff {
+    pub address: Address,
+    pub key: U256,
+    pub derived_key: [u8; 32],
+    /// Zero for the initial writes.
+    pub enumeration_index: u64,
+    pub initial_value: U256,
+    pub final_value: U256,
+}
+
+impl StateDiff {
+    pub fn is_initial_write(&self) -> bool {
+        self.enumeration_index == 0
+    }
+}
+
+/// How the final value is derived from the initial value and the encoded one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CompressionOperation {
+    /// Final value is encoded as is, using all 32 bytes.
+    Nothing = 0,
+    /// `final = initial + value`
+    Add = 1,
+    /// `final = initial - value`
+    Sub = 2,
+    /// `final = value`
+    Transform = 3,
+}
+
+impl CompressionOperation {
+    fn from_metadata(metadata: u8) -> Option<Self> {
+        match metadata & OPERATION_BITMASK {
+            0 => Some(Self::Nothing),
+            1 => Some(Self::Add),
+            2 => Some(Self::Sub),
+            3 => Some(Self::Transform),
+            _ => None,
+        }
+    }
+}
+
+/// Compressed value of a single write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompressedValue {
+    pub operation: CompressionOperation,
+    pub value: U256,
+}
+
+fn bytes_len(value: U256) -> usize {
+    value.bits().div_ceil(8)
+}
+
+impl CompressedValue {
+    /// Picks the shortest encoding, ties are resolved in the order `Add`, `Sub`, `Transform`,
+    /// `Nothing`.
+    pub fn compress(initial_value: U256, final_value: U256) -> Self {
+        let mut candidates = vec![];
+        if let (diff, false) = final_value.overflowing_sub(initial_value) {
+            candidates.push(Self {
+                operation: CompressionOperation::Add,
+                value: diff,
+            });
+        }
+        if let (diff, false) = initial_value.overflowing_sub(final_value) {
+            candidates.push(Self {
+                operation: CompressionOperation::Sub,
+                value: diff,
+            });
+        }
+        candidates.push(Self {
+            operation: CompressionOperation::Transform,
+            value: final_value,
+        });
+
+        candidates
+            .into_iter()
+            .filter(|el| bytes_len(el.value) < 32)
+            .min_by_key(|el| bytes_len(el.value))
+            .unwrap_or(Self {
+                operation: CompressionOperation::Nothing,
+                value: final_value,
+            })
+    }
+
+    /// Computes the final value, `None` if the operation under- or overflows.
+    pub fn apply(&self, initial_value: U256) -> Option<U256> {
+        match self.operation {
+            CompressionOperation::Nothing | CompressionOperation::Transform => Some(self.value),
+            CompressionOperation::Add => initial_value.checked_add(self.value),
+            CompressionOperation::Sub => initial_value.checked_sub(self.value),
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self.operation {
+            CompressionOperation::Nothing => 32,
+            _ => bytes_len(self.value),
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        let len = self.encoded_len();
+        let metadata = match self.operation {
+            CompressionOperation::Nothing => 0,
+            operation => ((len as u8) << LENGTH_BITS_OFFSET) | operation as u8,
+        };
+        out.push(metadata);
+
+        let mut buffer = [0u8; 32];
+        self.value.to_big_endian(&mut buffer);
+        out.extend_from_slice(&buffer[32 - len..]);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompressedStateDiffKey {
+    InitialWrite { derived_key: [u8; 32] },
+    RepeatedWrite { enumeration_index: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompressedStateDiff {
+    pub key: CompressedStateDiffKey,
+    pub value: CompressedValue,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDiffDecodingError {
+    UnexpectedEnd { offset: usize },
+    UnsupportedVersion(u8),
+    LengthMismatch { expected: usize, actual: usize },
+    InvalidEnumerationIndexSize(u8),
+    InvalidOperation { offset: usize, metadata: u8 },
+    ZeroEnumerationIndex { offset: usize },
+}
+
+impl std::fmt::Display for StateDiffDecodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEnd { offset } => {
+                write!(f, "unexpected end of compressed state diffs at {offset}")
+            }
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported compression version {version}")
+            }
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "compressed state diffs length mismatch: header says {expected}, got {actual}"
+            ),
+            Self::InvalidEnumerationIndexSize(size) => {
+                write!(f, "invalid enumeration index size {size}")
+            }
+            Self::InvalidOperation { offset, metadata } => {
+                write!(f, "invalid metadata {metadata:#04x} at {offset}")
+            }
+            Self::ZeroEnumerationIndex { offset } => {
+                write!(f, "repeated write with zero enumeration index at {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateDiffDecodingError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateDiffCompressionError {
+    /// Diff at `index` is not after the previous one in the [`sort_state_diffs`] order,
+    /// or is for the same slot.
+    NotSorted {
+        index: usize,
+    },
+    TooManyInitialWrites(usize),
+    EnumerationIndexTooLarge(u64),
+    TooLong(usize),
+}
+
+impl std::fmt::Display for StateDiffCompressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotSorted { index } => write!(f, "state diff {index} is out of order"),
+            Self::TooManyInitialWrites(count) => write!(
+                f,
+                "{count} initial writes don't fit into the 2 bytes counter"
+            ),
+            Self::EnumerationIndexTooLarge(index) => write!(
+                f,
+                "enumeration index {index} doesn't fit into {DEFAULT_ENUMERATION_INDEX_SIZE} bytes"
+            ),
+            Self::TooLong(len) => write!(
+                f,
+                "compressed state diffs of {len} bytes don't fit into the 3 bytes length"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StateDiffCompressionError {}
+
+fn state_diffs_order(a: &StateDiff, b: &StateDiff) -> Ordering {
+    a.address.cmp(&b.address).then_with(|| a.key.cmp(&b.key))
+}
+
+/// Sorts state diffs in the order expected by the circuits and the L1 contracts.
+pub fn sort_state_diffs(state_diffs: &mut [StateDiff]) {
+    state_diffs.sort_by(state_diffs_order);
+}
+
+/// Compresses the state diffs for the pubdata. The diffs must be sorted with
+/// [`sort_state_diffs`]; initial writes go first, repeated writes follow, both in that order.
+pub fn compress_state_diffs(
+    state_diffs: &[StateDiff],
+) -> Result<Vec<u8>, StateDiffCompressionError> {
+    if let Some(index) = state_diffs
+        .windows(2)
+        .position(|pair| state_diffs_order(&pair[0], &pair[1]) != Ordering::Less)
+    {
+        return Err(StateDiffCompressionError::NotSorted { index: index + 1 });
+    }
+
+    let enumeration_index_size = DEFAULT_ENUMERATION_INDEX_SIZE as usize;
+    let (initial_writes, repeated_writes): (Vec<&StateDiff>, Vec<&StateDiff>) =
+        state_diffs.iter().partition(|el| el.is_initial_write());
+
+    let num_initial_writes = u16::try_from(initial_writes.len())
+        .map_err(|_| StateDiffCompressionError::TooManyInitialWrites(initial_writes.len()))?;
+    let mut body = vec![];
+    body.extend(num_initial_writes.to_be_bytes());
+    for state_diff in initial_writes {
+        body.extend(state_diff.derived_key);
+        CompressedValue::compress(state_diff.initial_value, state_diff.final_value)
+            .encode(&mut body);
+    }
+    for state_diff in repeated_writes {
+        if state_diff.enumeration_index >= 1u64 << (8 * enumeration_index_size) {
+            return Err(StateDiffCompressionError::EnumerationIndexTooLarge(
+                state_diff.enumeration_index,
+            ));
+        }
+        body.extend(&state_diff.enumeration_index.to_be_bytes()[8 - enumeration_index_size..]);
+        CompressedValue::compress(state_diff.initial_value, state_diff.final_value)
+            .encode(&mut body);
+    }
+    if body.len() >= 1 << 24 {
+        return Err(StateDiffCompressionError::TooLong(body.len()));
+    }
+
+    let mut result = Vec::with_capacity(HEADER_LEN + body.len());
+    result.push(STATE_DIFF_COMPRESSION_VERSION_NUMBER);
+    result.extend(&(body.len() as u32).to_be_bytes()[1..]);
+    result.push(DEFAULT_ENUMERATION_INDEX_SIZE);
+    result.extend(body);
+
+    Ok(result)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateDiffDecodingError> {
+        let bytes = self.data.get(self.offset..self.offset + len).ok_or(
+            StateDiffDecodingError::UnexpectedEnd {
+                offset: self.offset,
+            },
+        )?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn read_be(&mut self, len: usize) -> Result<u64, StateDiffDecodingError> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0u64, |acc, el| (acc << 8) | *el as u64))
+    }
+
+    fn read_value(&mut self) -> Result<CompressedValue, StateDiffDecodingError> {
+        let offset = self.offset;
+        let metadata = self.take(1)?[0];
+        let operation = CompressionOperation::from_metadata(metadata)
+            .ok_or(StateDiffDecodingError::InvalidOperation { offset, metadata })?;
+        let len = match operation {
+            CompressionOperation::Nothing if metadata != 0 => {
+                return Err(StateDiffDecodingError::InvalidOperation { offset, metadata })
+            }
+            CompressionOperation::Nothing => 32,
+            _ => (metadata >> LENGTH_BITS_OFFSET) as usize,
+        };
+
+        Ok(CompressedValue {
+            operation,
+            value: U256::from_big_endian(self.take(len)?),
+        })
+    }
+}
+
+/// Decodes the output of [`compress_state_diffs`].
+pub fn decompress_state_diffs(
+    data: &[u8],
+) -> Result<Vec<CompressedStateDiff>, StateDiffDecodingError> {
+    let mut reader = Reader { data, offset: 0 };
+    let version = reader.take(1)?[0];
+    if version != STATE_DIFF_COMPRESSION_VERSION_NUMBER {
+        return Err(StateDiffDecodingError::UnsupportedVersion(version));
+    }
+    let body_len = reader.read_be(3)? as usize;
+    let enumeration_index_size = reader.take(1)?[0];
+    if enumeration_index_size == 0 || enumeration_index_size > MAX_ENUMERATION_INDEX_SIZE {
+        return Err(StateDiffDecodingError::InvalidEnumerationIndexSize(
+            enumeration_index_size,
+        ));
+    }
+    if data.len() - HEADER_LEN != body_len {
+        return Err(StateDiffDecodingError::LengthMismatch {
+            expected: body_len,
+            actual: data.len() - HEADER_LEN,
+        });
+    }
+
+    let num_initial_writes = reader.read_be(2)?;
+    let mut result = vec![];
+    for _ in 0..num_initial_writes {
+        let mut derived_key = [0u8; 32];
+        derived_key.copy_from_slice(reader.take(32)?);
+        result.push(CompressedStateDiff {
+            key: CompressedStateDiffKey::InitialWrite { derived_key },
+            value: reader.read_value()?,
+        });
+    }
+    while reader.offset < data.len() {
+        let offset = reader.offset;
+        let enumeration_index = reader.read_be(enumeration_index_size as usize)?;
+        if enumeration_index == 0 {
+            return Err(StateDiffDecodingError::ZeroEnumerationIndex { offset });
+        }
+        result.push(CompressedStateDiff {
+            key: CompressedStateDiffKey::RepeatedWrite { enumeration_index },
+            value: reader.read_value()?,
+        });
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn random_value(rng: &mut impl Rng, base: U256) -> U256 {
+        match rng.gen_range(0..5) {
+            0 => base,
+            1 => base.saturating_add(U256::from(rng.gen::<u16>())),
+            2 => base.saturating_sub(U256::from(rng.gen::<u64>())),
+            3 => U256::from(rng.gen::<u32>()),
+            _ => U256(rng.gen()),
+        }
+    }
+
+    fn random_state_diffs(rng: &mut impl Rng) -> Vec<StateDiff> {
+        let mut state_diffs: Vec<StateDiff> = (0..rng.gen_range(0..100))
+            .map(|_| {
+                let enumeration_index = if rng.gen_bool(0.5) {
+                    0
+                } else {
+                    rng.gen_range(1..1u64 << 32)
+                };
+                let initial_value = if enumeration_index == 0 {
+                    U256::zero()
+                } else {
+                    random_value(rng, U256::MAX / 2)
+                };
+                StateDiff {
+                    address: Address::from_low_u64_be(rng.gen()),
+                    key: U256::from(rng.gen::<u64>()),
+                    derived_key: rng.gen(),
+                    enumeration_index,
+                    initial_value,
+                    final_value: random_value(rng, initial_value),
+                }
+            })
+            .collect();
+        sort_state_diffs(&mut state_diffs);
+        state_diffs
+    }
+
+    #[test]
+    fn compression_round_trip() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..200 {
+            let state_diffs = random_state_diffs(&mut rng);
+            let compressed = compress_state_diffs(&state_diffs).unwrap();
+            let decompressed = decompress_state_diffs(&compressed).unwrap();
+            assert_eq!(decompressed.len(), state_diffs.len());
+
+            let (initial_writes, repeated_writes): (Vec<&StateDiff>, Vec<&StateDiff>) =
+                state_diffs.iter().partition(|el| el.is_initial_write());
+            for (state_diff, decoded) in initial_writes
+                .into_iter()
+                .chain(repeated_writes)
+                .zip(decompressed)
+            {
+                let expected_key = if state_diff.is_initial_write() {
+                    CompressedStateDiffKey::InitialWrite {
+                        derived_key: state_diff.derived_key,
+                    }
+                } else {
+                    CompressedStateDiffKey::RepeatedWrite {
+                        enumeration_index: state_diff.enumeration_index,
+                    }
+                };
+                assert_eq!(decoded.key, expected_key);
+                assert_eq!(
+                    decoded.value.apply(state_diff.initial_value),
+                    Some(state_diff.final_value)
+                );
+            }
+        }
+    }
+
+    fn state_diff(address: u64, enumeration_index: u64, initial: U256, last: U256) -> StateDiff {
+        StateDiff {
+            address: Address::from_low_u64_be(address),
+            key: U256::zero(),
+            derived_key: [0x11; 32],
+            enumeration_index,
+            initial_value: initial,
+            final_value: last,
+        }
+    }
+
+    #[test]
+    fn known_compressed_state_diffs() {
+        let state_diffs = [
+            state_diff(1, 0, U256::zero(), U256::from(0x100)),
+            state_diff(2, 5, U256::from(1000), U256::from(999)),
+            state_diff(3, 0x01020304, U256::MAX, U256::from(7)),
+            state_diff(4, 6, U256::zero(), U256::MAX),
+            state_diff(5, 7, U256::from(5), U256::from(5)),
+        ];
+        #[rustfmt::skip]
+        let expected = [
+            // version, body length, enumeration index size
+            "01", "00005b", "04",
+            // initial writes: derived key, add 0x100
+            "0001", &"11".repeat(32), "11", "0100",
+            // repeated writes: sub 1, transform to 7, no compression, add 0
+            "00000005", "0a", "01",
+            "01020304", "0b", "07",
+            "00000006", "00", &"ff".repeat(32),
+            "00000007", "01",
+        ]
+        .concat();
+
+        let compressed = compress_state_diffs(&state_diffs).unwrap();
+        let compressed_hex: String = compressed.iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(compressed_hex, expected);
+
+        let decompressed = decompress_state_diffs(&compressed).unwrap();
+        assert_eq!(
+            decompressed[0],
+            CompressedStateDiff {
+                key: CompressedStateDiffKey::InitialWrite {
+                    derived_key: [0x11; 32]
+                },
+                value: CompressedValue {
+                    operation: CompressionOperation::Add,
+                    value: U256::from(0x100)
+                }
+            }
+        );
+        assert_eq!(
+            decompressed[2],
+            CompressedStateDiff {
+                key: CompressedStateDiffKey::RepeatedWrite {
+                    enumeration_index: 0x01020304
+                },
+                value: CompressedValue {
+                    operation: CompressionOperation::Transform,
+                    value: U256::from(7)
+                }
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_state_diffs_are_not_compressed() {
+        let mut state_diffs = vec![
+            state_diff(2, 0, U256::zero(), U256::one()),
+            state_diff(1, 0, U256::zero(), U256::one()),
+        ];
+        assert_eq!(
+            compress_state_diffs(&state_diffs),
+            Err(StateDiffCompressionError::NotSorted { index: 1 })
+        );
+        state_diffs[1] = state_diffs[0];
+        assert_eq!(
+            compress_state_diffs(&state_diffs),
+            Err(StateDiffCompressionError::NotSorted { index: 1 })
+        );
+
+        assert_eq!(
+            compress_state_diffs(&[state_diff(1, 1 << 32, U256::zero(), U256::one())]),
+            Err(StateDiffCompressionError::EnumerationIndexTooLarge(1 << 32))
+        );
+
+        let initial_writes: Vec<StateDiff> = (0..=u16::MAX as u64)
+            .map(|i| state_diff(i, 0, U256::zero(), U256::one()))
+            .collect();
+        assert_eq!(
+            compress_state_diffs(&initial_writes),
+            Err(StateDiffCompressionError::TooManyInitialWrites(1 << 16))
+        );
+
+        // every uncompressed repeated write takes 4 + 1 + 32 bytes
+        let repeated_writes: Vec<StateDiff> = (0..(1u64 << 24) / 37 + 1)
+            .map(|i| state_diff(i, 1, U256::zero(), U256::MAX))
+            .collect();
+        assert!(matches!(
+            compress_state_diffs(&repeated_writes),
+            Err(StateDiffCompressionError::TooLong(_))
+        ));
+    }
+
+    #[test]
+    fn best_compression_is_chosen() {
+        let value = CompressedValue::compress(U256::from(1000), U256::from(1001));
+        assert_eq!(value.operation, CompressionOperation::Add);
+        assert_eq!(value.value, U256::one());
+
+        let value = CompressedValue::compress(U256::from(1001), U256::from(1000));
+        assert_eq!(value.operation, CompressionOperation::Sub);
+
+        let value = CompressedValue::compress(U256::MAX, U256::from(7));
+        assert_eq!(value.operation, CompressionOperation::Transform);
+
+        let value = CompressedValue::compress(U256::zero(), U256::MAX);
+        assert_eq!(value.operation, CompressionOperation::Nothing);
+        let mut encoded = vec![];
+        value.encode(&mut encoded);
+        assert_eq!(encoded.len(), 33);
+        assert_eq!(encoded[0], 0);
+
+        // unchanged value of a repeated write
+        let value = CompressedValue::compress(U256::from(5), U256::from(5));
+        assert_eq!(value.encoded_len(), 0);
+    }
+
+    #[test]
+    fn invalid_compressed_state_diffs() {
+        let mut rng = StdRng::seed_from_u64(1337);
+        let mut state_diffs = random_state_diffs(&mut rng);
+        while state_diffs.is_empty() {
+            state_diffs = random_state_diffs(&mut rng);
+        }
+        let compressed = compress_state_diffs(&state_diffs).unwrap();
+
+        let mut wrong_version = compressed.clone();
+        wrong_version[0] = 2;
+        assert_eq!(
+            decompress_state_diffs(&wrong_version),
+            Err(StateDiffDecodingError::UnsupportedVersion(2))
+        );
+
+        assert!(matches!(
+            decompress_state_diffs(&compressed[..compressed.len() - 1]),
+            Err(StateDiffDecodingError::LengthMismatch { .. })
+        ));
+        assert!(matches!(
+            decompress_state_diffs(&compressed[..3]),
+            Err(StateDiffDecodingError::UnexpectedEnd { .. })
+        ));
+
+        let mut wrong_index_size = compressed.clone();
+        wrong_index_size[4] = 9;
+        assert_eq!(
+            decompress_state_diffs(&wrong_index_size),
+            Err(StateDiffDecodingError::InvalidEnumerationIndexSize(9))
+        );
+
+        // metadata of the first write
+        let metadata_offset = if state_diffs.iter().any(|el| el.is_initial_write()) {
+            HEADER_LEN + 2 + 32
+        } else {
+            HEADER_LEN + 2 + DEFAULT_ENUMERATION_INDEX_SIZE as usize
+        };
+        let mut wrong_operation = compressed;
+        wrong_operation[metadata_offset] = 0x07;
+        assert_eq!(
+            decompress_state_diffs(&wrong_operation),
+            Err(StateDiffDecodingError::InvalidOperation {
+                offset: metadata_offset,
+                metadata: 0x07
+            })
+        );
+    }
+
+    #[test]
+    fn state_diff_matches_encodings_record() {
+        use circuit_encodings::state_diff_record::StateDiffRecord;
+
+        // Exhaustive destructuring, so a field added to or changed in either struct breaks
+        // the build until the two are brought back in sync.
+        let to_record = |state_diff: StateDiff| {
+            let StateDiff {
+                address,
+                key,
+                derived_key,
+                enumeration_index,
+                initial_value,
+                final_value,
+            } = state_diff;
+            StateDiffRecord {
+                address,
+                key,
+                derived_key,
+                enumeration_index,
+                initial_value,
+                final_value,
+            }
+        };
+        let from_record = |record: StateDiffRecord| {
+            let StateDiffRecord {
+                address,
+                key,
+                derived_key,
+                enumeration_index,
+                initial_value,
+                final_value,
+            } = record;
+            StateDiff {
+                address,
+                key,
+                derived_key,
+                enumeration_index,
+                initial_value,
+                final_value,
+            }
+        };
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for state_diff in random_state_diffs(&mut rng) {
+            let record = to_record(state_diff);
+            assert_eq!(from_record(record), state_diff);
+
+            // the circuit encoding carries the same enumeration index, with zero for initial writes
+            let encoding = record.encode();
+            let enumeration_index = u64::from_be_bytes(encoding[84..92].try_into().unwrap());
+            assert_eq!(enumeration_index == 0, state_diff.is_initial_write());
+            assert_eq!(&encoding[52..84], &state_diff.derived_key);
+        }
+    }
+}
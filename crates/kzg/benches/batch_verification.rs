@@ -0,0 +1,69 @@
+//! Compares verifying the blob proofs of a batch one by one and at once.
+//! Run with `cargo bench -p zksync_kzg --bench batch_verification`.
+#![feature(test)]
+
+extern crate test;
+
+use boojum::pairing::{
+    bls12_381::{Fr, G1Affine},
+    ff::Rand,
+};
+use test::Bencher;
+use zksync_kzg::{
+    compute_commitment, compute_proof_poly, verify_proof_poly, verify_proof_poly_batch, KzgSettings,
+};
+
+const SETUP_JSON: &str = "src/trusted_setup.json";
+
+/// Maximal number of blobs in a batch.
+const BLOBS: usize = 6;
+
+fn random_blobs_with_proofs(
+    settings: &KzgSettings,
+) -> (Vec<Vec<Fr>>, Vec<G1Affine>, Vec<G1Affine>) {
+    let mut rng = rand::thread_rng();
+    let blobs: Vec<Vec<Fr>> = (0..BLOBS)
+        .map(|_| (0..4096).map(|_| Fr::rand(&mut rng)).collect())
+        .collect();
+    let commitments: Vec<_> = blobs
+        .iter()
+        .map(|blob| compute_commitment(settings, blob))
+        .collect();
+    let proofs = blobs
+        .iter()
+        .zip(commitments.iter())
+        .map(|(blob, commitment)| compute_proof_poly(settings, blob, commitment))
+        .collect();
+    (blobs, commitments, proofs)
+}
+
+#[bench]
+fn bench_verify_blob_proofs_one_by_one(b: &mut Bencher) {
+    let settings = KzgSettings::new(SETUP_JSON);
+    let (blobs, commitments, proofs) = random_blobs_with_proofs(&settings);
+    b.iter(|| {
+        for i in 0..BLOBS {
+            assert!(verify_proof_poly(
+                &settings,
+                &blobs[i],
+                &commitments[i],
+                &proofs[i]
+            ));
+        }
+    });
+}
+
+#[bench]
+fn bench_verify_blob_proofs_batch(b: &mut Bencher) {
+    let settings = KzgSettings::new(SETUP_JSON);
+    let (blobs, commitments, proofs) = random_blobs_with_proofs(&settings);
+    let blobs: Vec<&[Fr]> = blobs.iter().map(|blob| blob.as_slice()).collect();
+    b.iter(|| {
+        assert!(verify_proof_poly_batch(
+            &settings,
+            &blobs,
+            &commitments,
+            &proofs
+        ))
+    });
+}
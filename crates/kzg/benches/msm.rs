@@ -0,0 +1,44 @@
+//! Compares the MSM implementations on a blob-sized input.
+//! Run with `cargo bench -p zksync_kzg --bench msm`.
+#![feature(test)]
+
+extern crate test;
+
+use boojum::pairing::{bls12_381::Fr, ff::Rand};
+use test::Bencher;
+use zksync_kzg::{
+    multiscalar_mul, naive_multiscalar_mul, KzgSettings, PrecomputedBases,
+    DEFAULT_PRECOMPUTATION_WINDOW_BITS,
+};
+
+const SETUP_JSON: &str = "src/trusted_setup.json";
+
+fn random_blob() -> Vec<Fr> {
+    let mut rng = rand::thread_rng();
+    (0..4096).map(|_| Fr::rand(&mut rng)).collect()
+}
+
+#[bench]
+fn bench_naive_msm(b: &mut Bencher) {
+    let settings = KzgSettings::new(SETUP_JSON);
+    let blob = random_blob();
+    b.iter(|| naive_multiscalar_mul(settings.lagrange_setup_brp.as_slice(), &blob));
+}
+
+#[bench]
+fn bench_pippenger_msm(b: &mut Bencher) {
+    let settings = KzgSettings::new(SETUP_JSON);
+    let blob = random_blob();
+    b.iter(|| multiscalar_mul(settings.lagrange_setup_brp.as_slice(), &blob));
+}
+
+#[bench]
+fn bench_precomputed_msm(b: &mut Bencher) {
+    let settings = KzgSettings::new(SETUP_JSON);
+    let precomputed = PrecomputedBases::new(
+        settings.lagrange_setup_brp.as_slice(),
+        DEFAULT_PRECOMPUTATION_WINDOW_BITS,
+    );
+    let blob = random_blob();
+    b.iter(|| precomputed.multiscalar_mul(&blob));
+}
@@ -0,0 +1,365 @@
+//! Reverse path of [`KzgInfo::new`](crate::KzgInfo::new): recovers ZKsync pubdata from the
+//! EIP-4844 blobs of a batch.
+
+use crate::kzg_info::{commitment_to_versioned_hash, KzgInfo, EIP_4844_BYTES_PER_BLOB};
+use crate::trusted_setup::KZG_SETTINGS;
+use crate::{verify_proof_poly, verify_proof_poly_batch};
+
+use boojum::pairing::{
+    bls12_381::{Fr, FrRepr, G1Affine, G1Compressed},
+    ff::{PrimeField, PrimeFieldRepr},
+    EncodedPoint,
+};
+use zkevm_circuits::eip_4844::{
+    bitreverse, ifft,
+    input::{BLOB_CHUNK_SIZE, ELEMENTS_PER_4844_BLOCK},
+};
+
+/// Errors that can occur while recovering pubdata from blobs. Indices refer to the position
+/// of the blob in the batch.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BlobDecodingError {
+    #[error("got {blobs} blobs, but {versioned_hashes} versioned hashes")]
+    BlobCountMismatch {
+        blobs: usize,
+        versioned_hashes: usize,
+    },
+    #[error("blob {0} has invalid length {1}, expected {EIP_4844_BYTES_PER_BLOB}")]
+    InvalidBlobLength(usize, usize),
+    #[error("blob {0} has invalid KZG commitment encoding")]
+    InvalidCommitmentEncoding(usize),
+    #[error("blob {0} has invalid blob proof encoding")]
+    InvalidProofEncoding(usize),
+    #[error(
+        "versioned hash of blob {index} does not match its commitment: expected 0x{}, got 0x{}",
+        hex::encode(expected),
+        hex::encode(actual)
+    )]
+    VersionedHashMismatch {
+        index: usize,
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+    #[error("element {element} of blob {blob} is not a canonical field element")]
+    NonCanonicalFieldElement { blob: usize, element: usize },
+    #[error("blob {0} does not match its KZG commitment and blob proof")]
+    InvalidBlobProof(usize),
+    #[error("element {element} of blob {blob} does not fit into {BLOB_CHUNK_SIZE} bytes of ZKsync pubdata")]
+    InvalidZksyncEncoding { blob: usize, element: usize },
+    #[error("pubdata length {pubdata_len} exceeds the {capacity} bytes the blobs can hold")]
+    PubdataTooLong { pubdata_len: usize, capacity: usize },
+    #[error("blob {0} has non-zero bytes past the end of the pubdata")]
+    NonZeroPadding(usize),
+}
+
+/// Blob together with the data that is published alongside it in the EIP-4844 sidecar.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlobSidecar {
+    /// 4844 blob, i.e. `4096` big endian encoded field elements
+    pub blob: Vec<u8>,
+    /// KZG commitment to the blob
+    pub kzg_commitment: [u8; 48],
+    /// Proof that the blob and `kzg` commitment represent the same data.
+    pub blob_proof: [u8; 48],
+}
+
+impl From<&KzgInfo> for BlobSidecar {
+    fn from(kzg_info: &KzgInfo) -> Self {
+        Self {
+            blob: kzg_info.blob.to_vec(),
+            kzg_commitment: kzg_info.kzg_commitment,
+            blob_proof: kzg_info.blob_proof,
+        }
+    }
+}
+
+fn decode_g1(bytes: &[u8; 48]) -> Option<G1Affine> {
+    let mut compressed = G1Compressed::empty();
+    compressed.as_mut().copy_from_slice(bytes);
+    compressed.into_affine().ok()
+}
+
+/// Parses the blob into bitreversed Lagrange form, rejecting non-canonical elements as
+/// the point evaluation precompile does.
+fn blob_to_poly(index: usize, blob: &[u8]) -> Result<Vec<Fr>, BlobDecodingError> {
+    if blob.len() != EIP_4844_BYTES_PER_BLOB {
+        return Err(BlobDecodingError::InvalidBlobLength(index, blob.len()));
+    }
+
+    blob.chunks(32)
+        .enumerate()
+        .map(|(element, bytes)| {
+            let mut repr = FrRepr::default();
+            repr.read_be(bytes).expect("32 bytes chunk");
+            Fr::from_repr(repr).map_err(|_| BlobDecodingError::NonCanonicalFieldElement {
+                blob: index,
+                element,
+            })
+        })
+        .collect()
+}
+
+/// Converts the blob polynomial back into ZKsync pubdata of `ZK_SYNC_BYTES_PER_BLOB` bytes.
+fn poly_to_zksync_pubdata(index: usize, mut poly: Vec<Fr>) -> Result<Vec<u8>, BlobDecodingError> {
+    bitreverse(&mut poly);
+    ifft(&mut poly);
+
+    let mut result = Vec::with_capacity(BLOB_CHUNK_SIZE * ELEMENTS_PER_4844_BLOCK);
+    // highest monomial goes first in the byte array
+    for (element, el) in poly.into_iter().rev().enumerate() {
+        let mut buffer = [0u8; 32];
+        el.into_repr().write_le(&mut buffer[..]).unwrap();
+        if buffer[BLOB_CHUNK_SIZE..].iter().any(|b| *b != 0) {
+            return Err(BlobDecodingError::InvalidZksyncEncoding {
+                blob: index,
+                element,
+            });
+        }
+        result.extend_from_slice(&buffer[..BLOB_CHUNK_SIZE]);
+    }
+
+    Ok(result)
+}
+
+/// Recovers the first `pubdata_len` bytes of pubdata of a batch from its blobs. Every blob is
+/// checked against its versioned hash, KZG commitment and blob proof before decoding.
+///
+/// Pubdata is padded with zeroes up to the blob size, and trailing zero bytes of the pubdata
+/// itself are indistinguishable from the padding, so the length has to be known by the caller,
+/// e.g. from the batch commitment. The padding past it must be zero.
+pub fn recover_pubdata(
+    sidecars: &[BlobSidecar],
+    versioned_hashes: &[[u8; 32]],
+    pubdata_len: usize,
+) -> Result<Vec<u8>, BlobDecodingError> {
+    if sidecars.len() != versioned_hashes.len() {
+        return Err(BlobDecodingError::BlobCountMismatch {
+            blobs: sidecars.len(),
+            versioned_hashes: versioned_hashes.len(),
+        });
+    }
+    let capacity = sidecars.len() * BLOB_CHUNK_SIZE * ELEMENTS_PER_4844_BLOCK;
+    if pubdata_len > capacity {
+        return Err(BlobDecodingError::PubdataTooLong {
+            pubdata_len,
+            capacity,
+        });
+    }
+
+    let mut commitments = Vec::with_capacity(sidecars.len());
+    let mut proofs = Vec::with_capacity(sidecars.len());
+    let mut polys = Vec::with_capacity(sidecars.len());
+    for (index, (sidecar, versioned_hash)) in sidecars.iter().zip(versioned_hashes).enumerate() {
+        let commitment = decode_g1(&sidecar.kzg_commitment)
+            .ok_or(BlobDecodingError::InvalidCommitmentEncoding(index))?;
+        let proof =
+            decode_g1(&sidecar.blob_proof).ok_or(BlobDecodingError::InvalidProofEncoding(index))?;
+
+        let actual = commitment_to_versioned_hash(commitment);
+        if actual != *versioned_hash {
+            return Err(BlobDecodingError::VersionedHashMismatch {
+                index,
+                expected: *versioned_hash,
+                actual,
+            });
+        }
+
+        polys.push(blob_to_poly(index, &sidecar.blob)?);
+        commitments.push(commitment);
+        proofs.push(proof);
+    }
+
+    let poly_refs: Vec<&[Fr]> = polys.iter().map(|el| el.as_slice()).collect();
+    if !verify_proof_poly_batch(&KZG_SETTINGS, &poly_refs, &commitments, &proofs) {
+        // find the offending blob to report it
+        let index = (0..polys.len())
+            .find(|&i| !verify_proof_poly(&KZG_SETTINGS, &polys[i], &commitments[i], &proofs[i]))
+            .unwrap_or_default();
+        return Err(BlobDecodingError::InvalidBlobProof(index));
+    }
+
+    let mut pubdata = Vec::with_capacity(capacity);
+    for (index, poly) in polys.into_iter().enumerate() {
+        pubdata.extend(poly_to_zksync_pubdata(index, poly)?);
+    }
+
+    if let Some(position) = pubdata[pubdata_len..].iter().position(|b| *b != 0) {
+        let index = (pubdata_len + position) / (BLOB_CHUNK_SIZE * ELEMENTS_PER_4844_BLOCK);
+        return Err(BlobDecodingError::NonZeroPadding(index));
+    }
+    pubdata.truncate(pubdata_len);
+
+    Ok(pubdata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compute_commitment, compute_proof_poly, pubdata_to_blob_commitments, ZK_SYNC_BYTES_PER_BLOB,
+    };
+    use boojum::pairing::{ff::Rand, CurveAffine};
+    use rand::Rng;
+
+    fn random_pubdata(len: usize) -> Vec<u8> {
+        let mut rng = rand::thread_rng();
+        (0..len).map(|_| rng.gen()).collect()
+    }
+
+    fn encode(pubdata: &[u8]) -> (Vec<BlobSidecar>, Vec<[u8; 32]>) {
+        pubdata
+            .chunks(ZK_SYNC_BYTES_PER_BLOB)
+            .map(|chunk| {
+                let kzg_info = KzgInfo::new(chunk);
+                (BlobSidecar::from(&kzg_info), kzg_info.versioned_hash)
+            })
+            .unzip()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = rand::thread_rng();
+        let lengths = [
+            1,
+            rng.gen_range(1..ZK_SYNC_BYTES_PER_BLOB),
+            ZK_SYNC_BYTES_PER_BLOB,
+            ZK_SYNC_BYTES_PER_BLOB + rng.gen_range(1..ZK_SYNC_BYTES_PER_BLOB),
+        ];
+        for len in lengths {
+            let pubdata = random_pubdata(len);
+            let (sidecars, versioned_hashes) = encode(&pubdata);
+            let recovered = recover_pubdata(&sidecars, &versioned_hashes, len).unwrap();
+            assert_eq!(recovered, pubdata);
+
+            let num_blobs = sidecars.len() + 1;
+            assert_eq!(
+                pubdata_to_blob_commitments(num_blobs, &recovered),
+                pubdata_to_blob_commitments(num_blobs, &pubdata)
+            );
+        }
+
+        assert_eq!(recover_pubdata(&[], &[], 0).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_trailing_zeroes_are_kept() {
+        let mut pubdata = random_pubdata(1000);
+        pubdata[899] = 1;
+        pubdata[900..].fill(0);
+        let (sidecars, versioned_hashes) = encode(&pubdata);
+
+        assert_eq!(
+            recover_pubdata(&sidecars, &versioned_hashes, 1000).unwrap(),
+            pubdata
+        );
+        // shorter lengths are fine as long as only zeroes are cut off
+        assert_eq!(
+            recover_pubdata(&sidecars, &versioned_hashes, 900).unwrap(),
+            pubdata[..900]
+        );
+        assert_eq!(
+            recover_pubdata(&sidecars, &versioned_hashes, 899),
+            Err(BlobDecodingError::NonZeroPadding(0))
+        );
+        assert_eq!(
+            recover_pubdata(&sidecars, &versioned_hashes, ZK_SYNC_BYTES_PER_BLOB + 1),
+            Err(BlobDecodingError::PubdataTooLong {
+                pubdata_len: ZK_SYNC_BYTES_PER_BLOB + 1,
+                capacity: ZK_SYNC_BYTES_PER_BLOB
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_blobs() {
+        let pubdata = random_pubdata(ZK_SYNC_BYTES_PER_BLOB + 1000);
+        let (sidecars, versioned_hashes) = encode(&pubdata);
+
+        assert_eq!(
+            recover_pubdata(&sidecars, &versioned_hashes[..1], pubdata.len()),
+            Err(BlobDecodingError::BlobCountMismatch {
+                blobs: 2,
+                versioned_hashes: 1
+            })
+        );
+
+        let mut swapped_hashes = versioned_hashes.clone();
+        swapped_hashes.swap(0, 1);
+        assert!(matches!(
+            recover_pubdata(&sidecars, &swapped_hashes, pubdata.len()),
+            Err(BlobDecodingError::VersionedHashMismatch { index: 0, .. })
+        ));
+
+        let mut tampered = sidecars.clone();
+        tampered[1].blob[32 * 5 + 31] ^= 1;
+        assert_eq!(
+            recover_pubdata(&tampered, &versioned_hashes, pubdata.len()),
+            Err(BlobDecodingError::InvalidBlobProof(1))
+        );
+
+        let mut non_canonical = sidecars.clone();
+        non_canonical[1].blob[32 * 7..32 * 8].fill(0xff);
+        assert_eq!(
+            recover_pubdata(&non_canonical, &versioned_hashes, pubdata.len()),
+            Err(BlobDecodingError::NonCanonicalFieldElement {
+                blob: 1,
+                element: 7
+            })
+        );
+
+        let mut truncated = sidecars.clone();
+        truncated[0].blob.pop();
+        assert_eq!(
+            recover_pubdata(&truncated, &versioned_hashes, pubdata.len()),
+            Err(BlobDecodingError::InvalidBlobLength(
+                0,
+                EIP_4844_BYTES_PER_BLOB - 1
+            ))
+        );
+
+        let mut bad_commitment = sidecars.clone();
+        bad_commitment[0].kzg_commitment = [0xff; 48];
+        assert_eq!(
+            recover_pubdata(&bad_commitment, &versioned_hashes, pubdata.len()),
+            Err(BlobDecodingError::InvalidCommitmentEncoding(0))
+        );
+    }
+
+    #[test]
+    fn test_non_zksync_blob() {
+        // a valid 4844 blob, which is not an encoding of ZKsync pubdata
+        let mut rng = rand::thread_rng();
+        let poly: Vec<Fr> = (0..ELEMENTS_PER_4844_BLOCK)
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+        let commitment = compute_commitment(&KZG_SETTINGS, &poly);
+        let proof = compute_proof_poly(&KZG_SETTINGS, &poly, &commitment);
+
+        let mut blob = Vec::with_capacity(EIP_4844_BYTES_PER_BLOB);
+        for el in poly.iter() {
+            let mut buffer = [0u8; 32];
+            el.into_repr().write_be(&mut buffer[..]).unwrap();
+            blob.extend(buffer);
+        }
+        let mut sidecar = BlobSidecar {
+            blob,
+            kzg_commitment: [0u8; 48],
+            blob_proof: [0u8; 48],
+        };
+        sidecar
+            .kzg_commitment
+            .copy_from_slice(commitment.into_compressed().as_ref());
+        sidecar
+            .blob_proof
+            .copy_from_slice(proof.into_compressed().as_ref());
+
+        assert!(matches!(
+            recover_pubdata(
+                &[sidecar],
+                &[commitment_to_versioned_hash(commitment)],
+                ZK_SYNC_BYTES_PER_BLOB
+            ),
+            Err(BlobDecodingError::InvalidZksyncEncoding { blob: 0, .. })
+        ));
+    }
+}
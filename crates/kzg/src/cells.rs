@@ -0,0 +1,581 @@
+//! EIP-7594 (PeerDAS) cells and cell proofs.
+//! Implementation based on https://github.com/ethereum/consensus-specs/blob/dev/specs/fulu/polynomial-commitments-sampling.md
+//!
+//! The blob polynomial is extended to twice the blob size by evaluating it on the roots of
+//! unity of order `FIELD_ELEMENTS_PER_EXT_BLOB`, and the evaluations (in bit-reversed order)
+//! are split into `CELLS_PER_EXT_BLOB` cells. Every cell comes with a KZG multi-proof for the
+//! coset of the evaluation domain it covers.
+
+use crate::{hash_to_bls_field, multiscalar_mul, KzgError, KzgSettings, FIELD_ELEMENTS_PER_BLOB};
+
+use boojum::pairing::{
+    bls12_381::{fq12::Fq12, Bls12, Fr, FrRepr, G1Affine, G2Affine},
+    ff::{Field, PrimeField, PrimeFieldRepr},
+    CurveAffine, CurveProjective, Engine,
+};
+use rayon::prelude::*;
+use zkevm_circuits::eip_4844::bitreverse;
+
+pub const FIELD_ELEMENTS_PER_EXT_BLOB: usize = 2 * FIELD_ELEMENTS_PER_BLOB;
+pub const FIELD_ELEMENTS_PER_CELL: usize = 64;
+pub const CELLS_PER_EXT_BLOB: usize = FIELD_ELEMENTS_PER_EXT_BLOB / FIELD_ELEMENTS_PER_CELL;
+
+/// Evaluations of the extended blob polynomial over the coset of a cell, in bit-reversed order.
+pub type Cell = [Fr; FIELD_ELEMENTS_PER_CELL];
+
+/// Primitive root of unity of order `size`, compatible with the roots used for blobs.
+fn root_of_unity(size: usize) -> Fr {
+    assert!(size.is_power_of_two() && size.trailing_zeros() <= Fr::S);
+    let mut root = Fr::root_of_unity();
+    for _ in size.trailing_zeros()..Fr::S {
+        root.square();
+    }
+    root
+}
+
+fn serial_fft(a: &mut [Fr], omega: &Fr) {
+    let n = a.len();
+    assert!(n.is_power_of_two());
+
+    bitreverse(a);
+
+    let mut m = 1;
+    while m < n {
+        let w_m = omega.pow([(n / (2 * m)) as u64]);
+
+        let mut k = 0;
+        while k < n {
+            let mut w = Fr::one();
+            for j in 0..m {
+                let mut t = a[k + j + m];
+                t.mul_assign(&w);
+                let mut tmp = a[k + j];
+                tmp.sub_assign(&t);
+                a[k + j + m] = tmp;
+                a[k + j].add_assign(&t);
+                w.mul_assign(&w_m);
+            }
+
+            k += 2 * m;
+        }
+
+        m *= 2;
+    }
+}
+
+fn ifft(a: &mut [Fr]) {
+    let omega_inv = root_of_unity(a.len()).inverse().unwrap();
+    serial_fft(a, &omega_inv);
+    let n_inv = Fr::from_repr(FrRepr::from(a.len() as u64))
+        .unwrap()
+        .inverse()
+        .unwrap();
+    a.iter_mut().for_each(|el| el.mul_assign(&n_inv));
+}
+
+/// Root of unity of order `FIELD_ELEMENTS_PER_EXT_BLOB`, by which the cell coset is shifted.
+fn coset_shift_for_cell(cell_index: usize) -> Fr {
+    let brp_index = cell_index.reverse_bits() >> (usize::BITS - CELLS_PER_EXT_BLOB.ilog2());
+    root_of_unity(FIELD_ELEMENTS_PER_EXT_BLOB).pow([brp_index as u64])
+}
+
+/// Converts the blob from bitreversed Lagrange form into monomial form.
+fn blob_to_coefficients(blob: &[Fr]) -> Vec<Fr> {
+    assert_eq!(blob.len(), FIELD_ELEMENTS_PER_BLOB);
+    let mut coeffs = blob.to_vec();
+    bitreverse(&mut coeffs);
+    ifft(&mut coeffs);
+    coeffs
+}
+
+/// Interpolates the polynomial of degree less than `FIELD_ELEMENTS_PER_CELL`, which takes the
+/// cell values on the cell coset.
+fn cell_to_coefficients(cell: &Cell, coset_shift: &Fr) -> Vec<Fr> {
+    // cell values are `p(h * w^brp(k))`, so after the bitreverse we get the evaluations of
+    // `p(h * X)` on the natural domain of the cell size
+    let mut coeffs = cell.to_vec();
+    bitreverse(&mut coeffs);
+    ifft(&mut coeffs);
+
+    let shift_inv = coset_shift.inverse().unwrap();
+    let mut power = Fr::one();
+    for coeff in coeffs.iter_mut() {
+        coeff.mul_assign(&power);
+        power.mul_assign(&shift_inv);
+    }
+    coeffs
+}
+
+/// Computes the multi-proof for the cell coset, i.e. the commitment to the quotient of
+/// `p(X) - I(X)` by the vanishing polynomial `X^FIELD_ELEMENTS_PER_CELL - h^FIELD_ELEMENTS_PER_CELL`.
+fn compute_cell_proof(settings: &KzgSettings, coeffs: &[Fr], cell_index: usize) -> G1Affine {
+    let shift_pow = coset_shift_for_cell(cell_index).pow([FIELD_ELEMENTS_PER_CELL as u64]);
+
+    // p(X) = q(X) * (X^n - c) + I(X), so q_{i - n} = p_i + c * q_i
+    let mut quotient = vec![Fr::zero(); coeffs.len() - FIELD_ELEMENTS_PER_CELL];
+    for i in (FIELD_ELEMENTS_PER_CELL..coeffs.len()).rev() {
+        let mut q = coeffs[i];
+        if i < quotient.len() {
+            let mut t = quotient[i];
+            t.mul_assign(&shift_pow);
+            q.add_assign(&t);
+        }
+        quotient[i - FIELD_ELEMENTS_PER_CELL] = q;
+    }
+
+    multiscalar_mul(settings.setup_g1_monomial.as_slice(), &quotient)
+}
+
+fn compute_cells_from_coefficients(coeffs: &[Fr]) -> Vec<Cell> {
+    let mut extended = coeffs.to_vec();
+    extended.resize(FIELD_ELEMENTS_PER_EXT_BLOB, Fr::zero());
+    serial_fft(&mut extended, &root_of_unity(FIELD_ELEMENTS_PER_EXT_BLOB));
+    bitreverse(&mut extended);
+
+    extended
+        .chunks(FIELD_ELEMENTS_PER_CELL)
+        .map(|chunk| {
+            let mut cell = [Fr::zero(); FIELD_ELEMENTS_PER_CELL];
+            cell.copy_from_slice(chunk);
+            cell
+        })
+        .collect()
+}
+
+/// Extends the blob and splits it into `CELLS_PER_EXT_BLOB` cells. The first half of the
+/// cells contains the blob itself.
+pub fn compute_cells(blob: &[Fr]) -> Vec<Cell> {
+    compute_cells_from_coefficients(&blob_to_coefficients(blob))
+}
+
+/// Computes all the cells of the extended blob together with their proofs.
+pub fn compute_cells_and_kzg_proofs(
+    settings: &KzgSettings,
+    blob: &[Fr],
+) -> (Vec<Cell>, Vec<G1Affine>) {
+    let coeffs = blob_to_coefficients(blob);
+    let cells = compute_cells_from_coefficients(&coeffs);
+    let proofs = (0..CELLS_PER_EXT_BLOB)
+        .into_par_iter()
+        .map(|cell_index| compute_cell_proof(settings, &coeffs, cell_index))
+        .collect();
+
+    (cells, proofs)
+}
+
+/// Vanishing polynomial of the cosets of the missing cells, i.e. the product of
+/// `X^FIELD_ELEMENTS_PER_CELL - h^FIELD_ELEMENTS_PER_CELL` over their coset shifts `h`.
+fn vanishing_polynomial_for_missing_cells(missing_cell_indices: &[usize]) -> Vec<Fr> {
+    let mut short_poly = vec![Fr::one()];
+    for &cell_index in missing_cell_indices {
+        let mut neg_root = coset_shift_for_cell(cell_index).pow([FIELD_ELEMENTS_PER_CELL as u64]);
+        neg_root.negate();
+        // multiply by (X - root)
+        short_poly.push(Fr::zero());
+        for i in (0..short_poly.len()).rev() {
+            let mut term = if i > 0 { short_poly[i - 1] } else { Fr::zero() };
+            let mut shifted = short_poly[i];
+            shifted.mul_assign(&neg_root);
+            term.add_assign(&shifted);
+            short_poly[i] = term;
+        }
+    }
+
+    let mut poly = vec![Fr::zero(); FIELD_ELEMENTS_PER_EXT_BLOB];
+    for (i, coeff) in short_poly.into_iter().enumerate() {
+        poly[i * FIELD_ELEMENTS_PER_CELL] = coeff;
+    }
+    poly
+}
+
+/// Multiplies the coefficients by the powers of `shift`, which turns the evaluation on the
+/// domain into the evaluation on the coset of the domain shifted by `shift`.
+fn shift_coefficients(coeffs: &mut [Fr], shift: &Fr) {
+    let mut power = Fr::one();
+    for coeff in coeffs.iter_mut() {
+        coeff.mul_assign(&power);
+        power.mul_assign(shift);
+    }
+}
+
+/// Recovers the blob polynomial in monomial form from at least half of the cells.
+/// The known evaluations are multiplied by the vanishing polynomial `Z` of the missing cells,
+/// which gives the evaluations of `p * Z` over the whole domain, and `p` is then recovered by
+/// the division on a coset, where `Z` has no roots.
+fn recover_coefficients(cell_indices: &[usize], cells: &[Cell]) -> Vec<Fr> {
+    let missing_cell_indices: Vec<usize> = (0..CELLS_PER_EXT_BLOB)
+        .filter(|i| !cell_indices.contains(i))
+        .collect();
+    let mut zero_poly = vanishing_polynomial_for_missing_cells(&missing_cell_indices);
+
+    let mut extended = vec![Fr::zero(); FIELD_ELEMENTS_PER_EXT_BLOB];
+    for (&cell_index, cell) in cell_indices.iter().zip(cells) {
+        extended[cell_index * FIELD_ELEMENTS_PER_CELL..][..FIELD_ELEMENTS_PER_CELL]
+            .copy_from_slice(cell);
+    }
+    bitreverse(&mut extended);
+
+    let omega = root_of_unity(FIELD_ELEMENTS_PER_EXT_BLOB);
+    let mut zero_poly_evals = zero_poly.clone();
+    serial_fft(&mut zero_poly_evals, &omega);
+    for (el, zero_poly_eval) in extended.iter_mut().zip(zero_poly_evals) {
+        el.mul_assign(&zero_poly_eval);
+    }
+    ifft(&mut extended);
+
+    let shift = Fr::multiplicative_generator();
+    shift_coefficients(&mut extended, &shift);
+    shift_coefficients(&mut zero_poly, &shift);
+    serial_fft(&mut extended, &omega);
+    serial_fft(&mut zero_poly, &omega);
+    for (el, zero_poly_eval) in extended.iter_mut().zip(zero_poly) {
+        el.mul_assign(
+            &zero_poly_eval
+                .inverse()
+                .expect("Z has no roots on the coset"),
+        );
+    }
+    ifft(&mut extended);
+    shift_coefficients(&mut extended, &shift.inverse().unwrap());
+
+    extended.truncate(FIELD_ELEMENTS_PER_BLOB);
+    extended
+}
+
+/// Recovers all the cells of the extended blob and their proofs from at least half of the
+/// cells. The cell indices must be unique, but don't have to be sorted.
+pub fn recover_cells_and_kzg_proofs(
+    settings: &KzgSettings,
+    cell_indices: &[usize],
+    cells: &[Cell],
+) -> Result<(Vec<Cell>, Vec<G1Affine>), KzgError> {
+    if cells.len() != cell_indices.len() {
+        return Err(KzgError::InvalidLength {
+            expected: cell_indices.len(),
+            actual: cells.len(),
+        });
+    }
+    let mut seen = [false; CELLS_PER_EXT_BLOB];
+    for &cell_index in cell_indices {
+        if cell_index >= CELLS_PER_EXT_BLOB {
+            return Err(KzgError::InvalidCellIndex(cell_index));
+        }
+        if std::mem::replace(&mut seen[cell_index], true) {
+            return Err(KzgError::DuplicateCellIndex(cell_index));
+        }
+    }
+    if cell_indices.len() < CELLS_PER_EXT_BLOB / 2 {
+        return Err(KzgError::NotEnoughCells {
+            required: CELLS_PER_EXT_BLOB / 2,
+            available: cell_indices.len(),
+        });
+    }
+
+    let coeffs = recover_coefficients(cell_indices, cells);
+    let cells = compute_cells_from_coefficients(&coeffs);
+    let proofs = (0..CELLS_PER_EXT_BLOB)
+        .into_par_iter()
+        .map(|cell_index| compute_cell_proof(settings, &coeffs, cell_index))
+        .collect();
+
+    Ok((cells, proofs))
+}
+
+fn setup_g2_for_cells(settings: &KzgSettings) -> Result<G2Affine, KzgError> {
+    settings
+        .setup_g2_monomial
+        .get(FIELD_ELEMENTS_PER_CELL)
+        .copied()
+        .ok_or_else(|| {
+            KzgError::InvalidTrustedSetup(format!(
+                "cell proofs verification requires at least {} G2 points",
+                FIELD_ELEMENTS_PER_CELL + 1
+            ))
+        })
+}
+
+/// Verifies the proof of a single cell of the extended blob with the given commitment.
+pub fn verify_cell_kzg_proof(
+    settings: &KzgSettings,
+    commitment: &G1Affine,
+    cell_index: usize,
+    cell: &Cell,
+    proof: &G1Affine,
+) -> Result<bool, KzgError> {
+    verify_cell_kzg_proof_batch(
+        settings,
+        &[*commitment],
+        &[cell_index],
+        std::slice::from_ref(cell),
+        &[*proof],
+    )
+}
+
+/// Verifies proofs of multiple cells at once, possibly belonging to different blobs.
+/// The checks are combined with powers of a Fiat-Shamir challenge into a single pairing check:
+/// `e(sum r^k * proof_k, [s^n]_2) == e(sum r^k * (C_k - [I_k(s)]_1 + h_k^n * proof_k), [1]_2)`
+pub fn verify_cell_kzg_proof_batch(
+    settings: &KzgSettings,
+    commitments: &[G1Affine],
+    cell_indices: &[usize],
+    cells: &[Cell],
+    proofs: &[G1Affine],
+) -> Result<bool, KzgError> {
+    let n = commitments.len();
+    if cell_indices.len() != n || cells.len() != n || proofs.len() != n {
+        return Err(KzgError::InvalidLength {
+            expected: n,
+            actual: [cell_indices.len(), cells.len(), proofs.len()]
+                .into_iter()
+                .find(|len| *len != n)
+                .unwrap(),
+        });
+    }
+    if let Some(cell_index) = cell_indices.iter().find(|&&i| i >= CELLS_PER_EXT_BLOB) {
+        return Err(KzgError::InvalidCellIndex(*cell_index));
+    }
+    let setup_g2 = setup_g2_for_cells(settings)?;
+    if n == 0 {
+        return Ok(true);
+    }
+
+    let r = compute_cell_batch_challenge(commitments, cell_indices, cells, proofs);
+    let mut r_power = Fr::one();
+
+    // scalars for the commitments, followed by the scalars for the proofs
+    let mut rhs_scalars = vec![Fr::zero(); 2 * n];
+    let mut interpolation_lincomb = vec![Fr::zero(); FIELD_ELEMENTS_PER_CELL];
+    for k in 0..n {
+        let coset_shift = coset_shift_for_cell(cell_indices[k]);
+
+        let mut shifted_r_power = coset_shift.pow([FIELD_ELEMENTS_PER_CELL as u64]);
+        shifted_r_power.mul_assign(&r_power);
+        rhs_scalars[k] = r_power;
+        rhs_scalars[n + k] = shifted_r_power;
+
+        for (acc, mut coeff) in interpolation_lincomb
+            .iter_mut()
+            .zip(cell_to_coefficients(&cells[k], &coset_shift))
+        {
+            coeff.mul_assign(&r_power);
+            acc.add_assign(&coeff);
+        }
+
+        r_power.mul_assign(&r);
+    }
+
+    let proof_lincomb = multiscalar_mul(proofs, &rhs_scalars[..n]);
+    let rhs_points: Vec<G1Affine> = commitments.iter().chain(proofs).copied().collect();
+    let mut rhs = multiscalar_mul(&rhs_points, &rhs_scalars).into_projective();
+    let mut interpolation_commitment = multiscalar_mul(
+        &settings.setup_g1_monomial[..FIELD_ELEMENTS_PER_CELL],
+        &interpolation_lincomb,
+    )
+    .into_projective();
+    interpolation_commitment.negate();
+    rhs.add_assign(&interpolation_commitment);
+
+    let mut neg_g2 = G2Affine::one();
+    neg_g2.negate();
+
+    let proof_lincomb = proof_lincomb.prepare();
+    let setup_g2 = setup_g2.prepare();
+    let rhs = rhs.into_affine().prepare();
+    let neg_g2 = neg_g2.prepare();
+
+    let result = Bls12::final_exponentiation(&Bls12::miller_loop(&[
+        (&proof_lincomb, &setup_g2),
+        (&rhs, &neg_g2),
+    ]));
+    Ok(result == Some(Fq12::one()))
+}
+
+fn compute_cell_batch_challenge(
+    commitments: &[G1Affine],
+    cell_indices: &[usize],
+    cells: &[Cell],
+    proofs: &[G1Affine],
+) -> Fr {
+    let mut data = String::from("RCKZGCBATCH__V1_").into_bytes();
+    data.extend((FIELD_ELEMENTS_PER_BLOB as u64).to_be_bytes());
+    data.extend((FIELD_ELEMENTS_PER_CELL as u64).to_be_bytes());
+    data.extend((commitments.len() as u64).to_be_bytes());
+    for k in 0..commitments.len() {
+        data.extend(commitments[k].into_compressed().as_ref());
+        data.extend((cell_indices[k] as u64).to_be_bytes());
+        for el in cells[k].iter() {
+            el.into_repr()
+                .write_be(&mut data)
+                .expect("should be able to write to data vector");
+        }
+        data.extend(proofs[k].into_compressed().as_ref());
+    }
+
+    hash_to_bls_field(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compute_commitment, eval_poly, TrustedSetup};
+    use boojum::pairing::ff::Rand;
+    use rand::Rng;
+
+    fn testing_settings() -> KzgSettings {
+        let setup = TrustedSetup::insecure_for_testing(Fr::rand(&mut rand::thread_rng()));
+        KzgSettings::try_new_from_trusted_setup(setup).unwrap()
+    }
+
+    /// Blob of a low degree polynomial, which keeps the proofs cheap to compute in tests.
+    fn random_blob(degree: usize) -> Vec<Fr> {
+        let mut rng = rand::thread_rng();
+        let mut blob: Vec<Fr> = (0..FIELD_ELEMENTS_PER_BLOB)
+            .map(|i| {
+                if i < degree {
+                    Fr::rand(&mut rng)
+                } else {
+                    Fr::zero()
+                }
+            })
+            .collect();
+        serial_fft(&mut blob, &root_of_unity(FIELD_ELEMENTS_PER_BLOB));
+        bitreverse(&mut blob);
+        blob
+    }
+
+    #[test]
+    fn test_roots_of_unity_are_compatible() {
+        let settings = KzgSettings::new("src/trusted_setup.json");
+        // the generator of the blob domain is at position 1 in natural order
+        assert_eq!(
+            settings.roots_of_unity_brp[FIELD_ELEMENTS_PER_BLOB / 2],
+            root_of_unity(FIELD_ELEMENTS_PER_BLOB)
+        );
+        let mut root = root_of_unity(FIELD_ELEMENTS_PER_EXT_BLOB);
+        root.square();
+        assert_eq!(root, root_of_unity(FIELD_ELEMENTS_PER_BLOB));
+    }
+
+    #[test]
+    fn test_cells_extend_blob() {
+        let settings = KzgSettings::new("src/trusted_setup.json");
+        let mut rng = rand::thread_rng();
+        let blob: Vec<Fr> = (0..FIELD_ELEMENTS_PER_BLOB)
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+
+        let cells = compute_cells(&blob);
+        assert_eq!(cells.len(), CELLS_PER_EXT_BLOB);
+        // the original blob is the first half of the extended blob
+        assert_eq!(cells[..CELLS_PER_EXT_BLOB / 2].concat(), blob);
+
+        for _ in 0..4 {
+            let cell_index = rng.gen_range(CELLS_PER_EXT_BLOB / 2..CELLS_PER_EXT_BLOB);
+            let element = rng.gen_range(0..FIELD_ELEMENTS_PER_CELL);
+            let brp_element =
+                element.reverse_bits() >> (usize::BITS - FIELD_ELEMENTS_PER_CELL.ilog2());
+            let mut z = root_of_unity(FIELD_ELEMENTS_PER_CELL).pow([brp_element as u64]);
+            z.mul_assign(&coset_shift_for_cell(cell_index));
+            assert_eq!(cells[cell_index][element], eval_poly(&settings, &blob, &z));
+        }
+    }
+
+    #[test]
+    fn test_cell_proofs() {
+        let settings = testing_settings();
+        let blob = random_blob(200);
+        let commitment = compute_commitment(&settings, &blob);
+        let (cells, proofs) = compute_cells_and_kzg_proofs(&settings, &blob);
+        assert_eq!(proofs.len(), CELLS_PER_EXT_BLOB);
+
+        for cell_index in [0, 1, 63, 64, 127] {
+            assert!(verify_cell_kzg_proof(
+                &settings,
+                &commitment,
+                cell_index,
+                &cells[cell_index],
+                &proofs[cell_index]
+            )
+            .unwrap());
+        }
+
+        let mut tampered = cells[5];
+        tampered[3].add_assign(&Fr::one());
+        assert!(!verify_cell_kzg_proof(&settings, &commitment, 5, &tampered, &proofs[5]).unwrap());
+        assert!(!verify_cell_kzg_proof(&settings, &commitment, 6, &cells[5], &proofs[5]).unwrap());
+        assert_eq!(
+            verify_cell_kzg_proof(&settings, &commitment, 128, &cells[5], &proofs[5]),
+            Err(KzgError::InvalidCellIndex(128))
+        );
+
+        let other_blob = random_blob(100);
+        let other_commitment = compute_commitment(&settings, &other_blob);
+        let (other_cells, other_proofs) = compute_cells_and_kzg_proofs(&settings, &other_blob);
+
+        let commitments = [commitment, other_commitment, commitment];
+        let cell_indices = [3, 100, 70];
+        let batch_cells = [cells[3], other_cells[100], cells[70]];
+        let mut batch_proofs = [proofs[3], other_proofs[100], proofs[70]];
+        assert!(verify_cell_kzg_proof_batch(
+            &settings,
+            &commitments,
+            &cell_indices,
+            &batch_cells,
+            &batch_proofs
+        )
+        .unwrap());
+        assert!(verify_cell_kzg_proof_batch(&settings, &[], &[], &[], &[]).unwrap());
+
+        batch_proofs.swap(0, 2);
+        assert!(!verify_cell_kzg_proof_batch(
+            &settings,
+            &commitments,
+            &cell_indices,
+            &batch_cells,
+            &batch_proofs
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_recover_cells() {
+        let settings = testing_settings();
+        let blob = random_blob(200);
+        let (cells, proofs) = compute_cells_and_kzg_proofs(&settings, &blob);
+
+        // the second half in reverse order and a few cells of the first half
+        let cell_indices: Vec<usize> = (64..CELLS_PER_EXT_BLOB).rev().chain([0, 17]).collect();
+        let known_cells: Vec<Cell> = cell_indices.iter().map(|&i| cells[i]).collect();
+        let (recovered_cells, recovered_proofs) =
+            recover_cells_and_kzg_proofs(&settings, &cell_indices, &known_cells).unwrap();
+        assert_eq!(recovered_cells, cells);
+        assert_eq!(recovered_proofs, proofs);
+
+        assert_eq!(
+            recover_cells_and_kzg_proofs(&settings, &cell_indices[..63], &known_cells[..63]),
+            Err(KzgError::NotEnoughCells {
+                required: 64,
+                available: 63
+            })
+        );
+        let mut duplicate_indices = cell_indices.clone();
+        duplicate_indices[1] = duplicate_indices[0];
+        assert_eq!(
+            recover_cells_and_kzg_proofs(&settings, &duplicate_indices, &known_cells),
+            Err(KzgError::DuplicateCellIndex(127))
+        );
+    }
+
+    #[test]
+    fn test_missing_g2_setup() {
+        let mut setup: TrustedSetup =
+            serde_json::from_str(include_str!("trusted_setup.json")).unwrap();
+        setup.g2_monomial.truncate(FIELD_ELEMENTS_PER_CELL);
+        let settings = KzgSettings::try_new_from_trusted_setup(setup).unwrap();
+        let cell = [Fr::zero(); FIELD_ELEMENTS_PER_CELL];
+        assert!(matches!(
+            verify_cell_kzg_proof(&settings, &G1Affine::zero(), 0, &cell, &G1Affine::zero()),
+            Err(KzgError::InvalidTrustedSetup(_))
+        ));
+    }
+}
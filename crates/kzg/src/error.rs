@@ -0,0 +1,20 @@
+/// Errors returned by the fallible KZG API.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum KzgError {
+    #[error("invalid input length: expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("invalid encoding of point {index} of the trusted setup")]
+    InvalidPointEncoding { index: usize },
+    #[error("invalid encoding of G2 point {index} of the trusted setup")]
+    InvalidG2PointEncoding { index: usize },
+    #[error("pubdata requires {required} blobs, but only {available} are available")]
+    TooManyBlobs { required: usize, available: usize },
+    #[error("cell index {0} is out of range")]
+    InvalidCellIndex(usize),
+    #[error("cell index {0} is used more than once")]
+    DuplicateCellIndex(usize),
+    #[error("recovery requires {required} cells, but only {available} are available")]
+    NotEnoughCells { required: usize, available: usize },
+    #[error("invalid trusted setup: {0}")]
+    InvalidTrustedSetup(String),
+}
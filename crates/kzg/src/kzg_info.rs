@@ -1,6 +1,7 @@
 use std::convert::TryInto;
 
 use crate::trusted_setup::KZG_SETTINGS;
+use crate::KzgError;
 
 use super::{compute_commitment, compute_proof, compute_proof_poly};
 use zkevm_circuits::{
@@ -23,7 +24,7 @@ use boojum::{
 };
 
 pub const ZK_SYNC_BYTES_PER_BLOB: usize = BLOB_CHUNK_SIZE * ELEMENTS_PER_4844_BLOCK;
-const EIP_4844_BYTES_PER_BLOB: usize = 32 * ELEMENTS_PER_4844_BLOCK;
+pub(crate) const EIP_4844_BYTES_PER_BLOB: usize = 32 * ELEMENTS_PER_4844_BLOCK;
 
 /// Packed pubdata commitments.
 /// Format: opening point (16 bytes) || claimed value (32 bytes) || commitment (48 bytes)
@@ -55,7 +56,7 @@ pub struct KzgInfo {
 }
 
 /// Given a KZG commitment, calculate the versioned hash.
-fn commitment_to_versioned_hash(kzg_commitment: G1Affine) -> [u8; 32] {
+pub(crate) fn commitment_to_versioned_hash(kzg_commitment: G1Affine) -> [u8; 32] {
     let mut versioned_hash = [0u8; 32];
 
     let mut versioned_hash_bytes = Sha256::digest(kzg_commitment.into_compressed());
@@ -121,7 +122,18 @@ impl KzgInfo {
 
     /// Deserializes `Self::SERIALIZED_SIZE` bytes into `KzgInfo` struct
     pub fn from_slice(data: &[u8]) -> Self {
-        assert_eq!(data.len(), Self::SERIALIZED_SIZE);
+        Self::try_from_slice(data).unwrap()
+    }
+
+    /// Deserializes `Self::SERIALIZED_SIZE` bytes into `KzgInfo` struct, returns an error on
+    /// length mismatch.
+    pub fn try_from_slice(data: &[u8]) -> Result<Self, KzgError> {
+        if data.len() != Self::SERIALIZED_SIZE {
+            return Err(KzgError::InvalidLength {
+                expected: Self::SERIALIZED_SIZE,
+                actual: data.len(),
+            });
+        }
 
         let mut blob = [0u8; EIP_4844_BYTES_PER_BLOB];
         let data = copy_n_bytes_return_rest(&mut blob, data, EIP_4844_BYTES_PER_BLOB);
@@ -146,7 +158,7 @@ impl KzgInfo {
 
         assert_eq!(data.len(), 0);
 
-        Self {
+        Ok(Self {
             blob,
             kzg_commitment,
             opening_point,
@@ -154,7 +166,7 @@ impl KzgInfo {
             opening_proof,
             versioned_hash,
             blob_proof,
-        }
+        })
     }
 
     /// Converts `KzgInfo` struct into a byte array
@@ -201,7 +213,17 @@ impl KzgInfo {
     ///     8. opening value, opening proof <- `compute_kzg_proof`(4844)
     ///     9. blob proof <- `compute_proof_poly`(blob, 4844 `kzg` commitment)
     pub fn new(pubdata: &[u8]) -> Self {
-        assert!(pubdata.len() <= ZK_SYNC_BYTES_PER_BLOB);
+        Self::try_new(pubdata).unwrap()
+    }
+
+    /// Same as [`KzgInfo::new`], but returns an error if the pubdata doesn't fit into a single blob.
+    pub fn try_new(pubdata: &[u8]) -> Result<Self, KzgError> {
+        if pubdata.len() > ZK_SYNC_BYTES_PER_BLOB {
+            return Err(KzgError::TooManyBlobs {
+                required: pubdata.len().div_ceil(ZK_SYNC_BYTES_PER_BLOB),
+                available: 1,
+            });
+        }
 
         let mut zksync_blob = [0u8; ZK_SYNC_BYTES_PER_BLOB];
         zksync_blob[0..pubdata.len()].copy_from_slice(pubdata);
@@ -251,7 +273,7 @@ impl KzgInfo {
         let mut commitment_proof = [0u8; 48];
         commitment_proof.copy_from_slice(blob_proof.into_compressed().as_ref());
 
-        Self {
+        Ok(Self {
             blob,
             kzg_commitment: commitment,
             opening_point: challenge_point,
@@ -259,26 +281,39 @@ impl KzgInfo {
             opening_proof: challenge_proof,
             versioned_hash,
             blob_proof: commitment_proof,
-        }
+        })
     }
 }
 
 pub fn pubdata_to_blob_commitments(num_blobs: usize, pubdata_input: &[u8]) -> Vec<H256> {
-    assert!(
-        pubdata_input.len() <= num_blobs * ZK_SYNC_BYTES_PER_BLOB,
-        "Pubdata length exceeds size of blobs"
-    );
+    try_pubdata_to_blob_commitments(num_blobs, pubdata_input)
+        .expect("Pubdata length exceeds size of blobs")
+}
+
+/// Same as [`pubdata_to_blob_commitments`], but returns an error if the pubdata doesn't fit
+/// into `num_blobs` blobs.
+pub fn try_pubdata_to_blob_commitments(
+    num_blobs: usize,
+    pubdata_input: &[u8],
+) -> Result<Vec<H256>, KzgError> {
+    let required = pubdata_input.len().div_ceil(ZK_SYNC_BYTES_PER_BLOB);
+    if required > num_blobs {
+        return Err(KzgError::TooManyBlobs {
+            required,
+            available: num_blobs,
+        });
+    }
 
     let mut blob_commitments = pubdata_input
         .chunks(ZK_SYNC_BYTES_PER_BLOB)
         .map(|blob| {
-            let kzg_info = KzgInfo::new(blob);
-            H256(kzg_info.to_blob_commitment())
+            let kzg_info = KzgInfo::try_new(blob)?;
+            Ok(H256(kzg_info.to_blob_commitment()))
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, KzgError>>()?;
 
     // Depending on the length of `pubdata_input`, we will sending the ceiling of
     // `pubdata_input / ZK_SYNC_BYTES_PER_BLOB (126976)` blobs. The rest of the blob commitments will be 32 zero bytes.
     blob_commitments.resize(num_blobs, H256::zero());
-    blob_commitments
+    Ok(blob_commitments)
 }
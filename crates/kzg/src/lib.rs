@@ -15,14 +15,34 @@ use boojum::{
     sha2::Sha256,
 };
 
-use rayon::prelude::*;
+use std::sync::Arc;
 
 // These are the 3 things that are exposed to the public and used by sequencer.
 pub use kzg_info::pubdata_to_blob_commitments;
+pub use kzg_info::try_pubdata_to_blob_commitments;
 pub use kzg_info::KzgInfo;
 pub use kzg_info::ZK_SYNC_BYTES_PER_BLOB;
 
+pub use error::KzgError;
+pub use trusted_setup::init_kzg_settings;
+
+pub use cells::{
+    compute_cells, compute_cells_and_kzg_proofs, recover_cells_and_kzg_proofs,
+    verify_cell_kzg_proof, verify_cell_kzg_proof_batch, Cell, CELLS_PER_EXT_BLOB,
+    FIELD_ELEMENTS_PER_CELL, FIELD_ELEMENTS_PER_EXT_BLOB,
+};
+
+pub use blob_decoding::{recover_pubdata, BlobDecodingError, BlobSidecar};
+
+pub use msm::{
+    multiscalar_mul, naive_multiscalar_mul, PrecomputedBases, DEFAULT_PRECOMPUTATION_WINDOW_BITS,
+};
+
+mod blob_decoding;
+mod cells;
+mod error;
 mod kzg_info;
+mod msm;
 #[cfg(test)]
 mod tests;
 mod trusted_setup;
@@ -30,6 +50,9 @@ mod trusted_setup;
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct TrustedSetup {
     g1_lagrange: Vec<String>,
+    /// Powers of tau in G2, only needed for cell proofs verification.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    g2_monomial: Vec<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -37,16 +60,70 @@ pub struct KzgSettings {
     pub roots_of_unity_brp: Box<[Fr; FIELD_ELEMENTS_PER_BLOB]>,
     pub setup_g2_1: G2,
     pub lagrange_setup_brp: Box<[G1Affine; FIELD_ELEMENTS_PER_BLOB]>,
+    /// Powers of tau in G1, used for cell proofs.
+    pub setup_g1_monomial: Box<[G1Affine; FIELD_ELEMENTS_PER_BLOB]>,
+    /// Powers of tau in G2, empty if the trusted setup doesn't provide them.
+    pub setup_g2_monomial: Vec<G2Affine>,
+    /// Optional fixed-base precomputation over `lagrange_setup_brp`, speeds up commitments
+    /// and opening proofs at the cost of memory. Only built on [`KzgSettings::with_precomputation`].
+    lagrange_setup_precomputed: Option<Arc<PrecomputedBases>>,
 }
 
 impl KzgSettings {
     pub fn new(settings_file: &str) -> Self {
-        let setup: TrustedSetup =
-            serde_json::from_slice(&std::fs::read(settings_file).unwrap()).unwrap();
+        Self::try_new(settings_file).unwrap()
+    }
+
+    /// Loads the trusted setup from a JSON file, see [`KzgSettings::try_new_from_trusted_setup`].
+    pub fn try_new(settings_file: &str) -> Result<Self, KzgError> {
+        let data = std::fs::read(settings_file).map_err(|err| {
+            KzgError::InvalidTrustedSetup(format!("failed to read {settings_file}: {err}"))
+        })?;
+        let setup: TrustedSetup = serde_json::from_slice(&data).map_err(|err| {
+            KzgError::InvalidTrustedSetup(format!("failed to parse {settings_file}: {err}"))
+        })?;
 
-        KzgSettings::new_from_trusted_setup(setup)
+        Self::try_new_from_trusted_setup(setup)
     }
+
     pub fn new_from_trusted_setup(setup: TrustedSetup) -> Self {
+        Self::try_new_from_trusted_setup(setup).unwrap()
+    }
+
+    /// Same as [`KzgSettings::new_from_trusted_setup`], but returns an error if the setup has
+    /// wrong size or contains invalid points.
+    pub fn try_new_from_trusted_setup(setup: TrustedSetup) -> Result<Self, KzgError> {
+        if setup.g1_lagrange.len() != FIELD_ELEMENTS_PER_BLOB {
+            return Err(KzgError::InvalidTrustedSetup(format!(
+                "expected {} G1 points, got {}",
+                FIELD_ELEMENTS_PER_BLOB,
+                setup.g1_lagrange.len()
+            )));
+        }
+        let setup_g1_monomial = setup
+            .g1_lagrange
+            .iter()
+            .enumerate()
+            .map(|(index, hex)| decode_g1_hex(hex).ok_or(KzgError::InvalidPointEncoding { index }))
+            .collect::<Result<Vec<G1Affine>, KzgError>>()?;
+        let mut base_setup = setup_g1_monomial
+            .iter()
+            .map(|point| point.into_projective())
+            .collect::<Vec<G1>>();
+        let setup_g1_monomial = {
+            let mut points = Box::new([G1Affine::zero(); FIELD_ELEMENTS_PER_BLOB]);
+            points.copy_from_slice(&setup_g1_monomial);
+            points
+        };
+        let setup_g2_monomial = setup
+            .g2_monomial
+            .iter()
+            .enumerate()
+            .map(|(index, hex)| {
+                decode_g2_hex(hex).ok_or(KzgError::InvalidG2PointEncoding { index })
+            })
+            .collect::<Result<Vec<G2Affine>, KzgError>>()?;
+
         let roots_of_unity = {
             // 39033254847818212395286706435128746857159659164139250548781411570340225835782
             // 2^12 root of unity for BLS12-381
@@ -77,26 +154,14 @@ impl KzgSettings {
 
         let setup_g2_1 = {
             let point = "b5bfd7dd8cdeb128843bc287230af38926187075cbfbefa81009a2ce615ac53d2914e5870cb452d2afaaab24f3499f72185cbfee53492714734429b7b38608e23926c911cceceac9a36851477ba4c60b087041de621000edc98edada20c1def2";
-            let bytes = hex_to_bytes(point);
-            let mut point = G2Compressed::empty();
-            let v = point.as_mut();
-            v.copy_from_slice(bytes.as_slice());
-            point.into_affine().unwrap().into_projective()
+            let point = decode_g2_hex(point).ok_or_else(|| {
+                KzgError::InvalidTrustedSetup("invalid encoding of the G2 generator".to_owned())
+            })?;
+            // prefer the setup's own value if it's provided
+            setup_g2_monomial.get(1).unwrap_or(&point).into_projective()
         };
 
         let lagrange_setup_brp = {
-            let mut base_setup: Vec<G1> = setup
-                .g1_lagrange
-                .iter()
-                .map(|hex| {
-                    let bytes = hex_to_bytes(&hex[2..]);
-                    let mut point = G1Compressed::empty();
-                    let v = point.as_mut();
-                    v.copy_from_slice(bytes.as_slice());
-                    point.into_affine().unwrap().into_projective()
-                })
-                .collect::<Vec<G1>>();
-
             // radix-2 ifft
             // we break up the powers into smallest chunks and then compose them together with the
             // cooley-tukey algorithm. the roots need to be inverted, and all results need to be
@@ -156,10 +221,37 @@ impl KzgSettings {
             Box::new(lagrange_setup_brp)
         };
 
-        Self {
+        Ok(Self {
             roots_of_unity_brp,
             setup_g2_1,
             lagrange_setup_brp,
+            setup_g1_monomial,
+            setup_g2_monomial,
+            lagrange_setup_precomputed: None,
+        })
+    }
+
+    /// Precomputes the Lagrange setup with the given window size, see [`PrecomputedBases`].
+    /// With [`DEFAULT_PRECOMPUTATION_WINDOW_BITS`] the tables take around 13 MB.
+    pub fn with_precomputation(mut self, window_bits: usize) -> Self {
+        self.lagrange_setup_precomputed = Some(Arc::new(PrecomputedBases::new(
+            self.lagrange_setup_brp.as_slice(),
+            window_bits,
+        )));
+        self
+    }
+
+    /// Fixed-base precomputation over the Lagrange setup, if it was requested.
+    pub fn lagrange_setup_precomputed(&self) -> Option<&PrecomputedBases> {
+        self.lagrange_setup_precomputed.as_deref()
+    }
+
+    /// Computes the MSM of the Lagrange setup with the given scalars, using the
+    /// precomputed tables if they are available.
+    pub fn lagrange_multiscalar_mul(&self, scalars: &[Fr]) -> G1Affine {
+        match &self.lagrange_setup_precomputed {
+            Some(precomputed) => precomputed.multiscalar_mul(scalars),
+            None => multiscalar_mul(self.lagrange_setup_brp.as_slice(), scalars),
         }
     }
 }
@@ -186,41 +278,30 @@ fn bit_reverse_array<T: Clone>(input: &mut [T]) {
     });
 }
 
-fn hex_to_bytes(hex_string: &str) -> Vec<u8> {
-    (0..hex_string.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&hex_string[i..i + 2], 16).unwrap())
-        .collect::<Vec<u8>>()
+fn decode_g1_hex(hex_string: &str) -> Option<G1Affine> {
+    let bytes = hex::decode(hex_string.strip_prefix("0x").unwrap_or(hex_string)).ok()?;
+    let mut point = G1Compressed::empty();
+    if bytes.len() != point.as_ref().len() {
+        return None;
+    }
+    point.as_mut().copy_from_slice(&bytes);
+    point.into_affine().ok()
+}
+
+fn decode_g2_hex(hex_string: &str) -> Option<G2Affine> {
+    let bytes = hex::decode(hex_string.strip_prefix("0x").unwrap_or(hex_string)).ok()?;
+    let mut point = G2Compressed::empty();
+    if bytes.len() != point.as_ref().len() {
+        return None;
+    }
+    point.as_mut().copy_from_slice(&bytes);
+    point.into_affine().ok()
 }
 
 /// Computes a KZG commitment to a EIP4844 blob.
 pub fn compute_commitment(settings: &KzgSettings, blob: &[Fr]) -> G1Affine {
     assert!(blob.len() <= FIELD_ELEMENTS_PER_BLOB);
-    multiscalar_mul(&settings.lagrange_setup_brp.as_slice(), blob)
-}
-
-// XXX: this could be sped up but im not sure if its necessary due to always having 4096 elements
-/// Performs a naive MSM and compute a polynomial commitment.
-pub fn multiscalar_mul(points: &[G1Affine], scalars: &[Fr]) -> G1Affine {
-    assert!(scalars.len() <= points.len());
-    scalars
-        .par_iter()
-        .zip(points)
-        .fold(
-            || G1::zero(),
-            |mut acc, (scalar, point)| {
-                acc.add_assign(&point.mul(*scalar));
-                acc
-            },
-        )
-        .reduce(
-            || G1::zero(),
-            |mut a: G1, b: G1| {
-                a.add_assign(&b);
-                a
-            },
-        )
-        .into_affine()
+    settings.lagrange_multiscalar_mul(blob)
 }
 
 /// Computes a KZG opening proof for the given blob and evaluation point.
@@ -258,10 +339,7 @@ pub fn compute_proof(settings: &KzgSettings, blob: &[Fr], z: &Fr) -> (G1Affine,
         })
         .collect::<Vec<Fr>>();
 
-    (
-        multiscalar_mul(&settings.lagrange_setup_brp.to_vec(), &quotient_poly),
-        y,
-    )
+    (settings.lagrange_multiscalar_mul(&quotient_poly), y)
 }
 
 /// Verifies a KZG commitment and proof for a given evaluation point and evaluation result.
@@ -309,6 +387,82 @@ pub fn verify_proof_poly(
     verify_kzg_proof(settings, commitment, &challenge, &y, proof)
 }
 
+/// Verifies multiple KZG openings at once. The openings are combined with powers of a
+/// Fiat-Shamir challenge into a single pairing check.
+pub fn verify_kzg_proof_batch(
+    settings: &KzgSettings,
+    commitments: &[G1Affine],
+    zs: &[Fr],
+    ys: &[Fr],
+    proofs: &[G1Affine],
+) -> bool {
+    let n = commitments.len();
+    assert!(zs.len() == n && ys.len() == n && proofs.len() == n);
+    if n == 0 {
+        return true;
+    }
+
+    let r = compute_batch_challenge(commitments, zs, ys, proofs);
+    let mut r_power = Fr::one();
+
+    let mut proof_lincomb = G1::zero();
+    let mut proof_z_lincomb = G1::zero();
+    let mut c_minus_y_lincomb = G1::zero();
+    for i in 0..n {
+        let mut t = proofs[i].mul(r_power);
+        proof_lincomb.add_assign(&t);
+
+        t.mul_assign(zs[i]);
+        proof_z_lincomb.add_assign(&t);
+
+        let mut c_minus_y = commitments[i].into_projective();
+        c_minus_y.sub_assign(&G1Affine::one().mul(ys[i]));
+        c_minus_y.mul_assign(r_power);
+        c_minus_y_lincomb.add_assign(&c_minus_y);
+
+        r_power.mul_assign(&r);
+    }
+
+    // e(sum r^i * proof_i, [-s]_2) * e(sum r^i * (C_i - [y_i]_1 + z_i * proof_i), [1]_2) == 1
+    let mut lhs = c_minus_y_lincomb;
+    lhs.add_assign(&proof_z_lincomb);
+    let mut neg_setup_g2 = settings.setup_g2_1;
+    neg_setup_g2.negate();
+
+    let proof_lincomb = proof_lincomb.into_affine().prepare();
+    let neg_setup_g2 = neg_setup_g2.into_affine().prepare();
+    let lhs = lhs.into_affine().prepare();
+    let g2 = G2Affine::one().prepare();
+
+    let result = Bls12::final_exponentiation(&Bls12::miller_loop(&[
+        (&proof_lincomb, &neg_setup_g2),
+        (&lhs, &g2),
+    ]));
+    result == Some(Fq12::one())
+}
+
+/// Verifies commitments and opening proofs for multiple blobs at once, the challenge points
+/// are derived deterministically as in [`verify_proof_poly`].
+pub fn verify_proof_poly_batch(
+    settings: &KzgSettings,
+    blobs: &[&[Fr]],
+    commitments: &[G1Affine],
+    proofs: &[G1Affine],
+) -> bool {
+    assert!(blobs.len() == commitments.len() && blobs.len() == proofs.len());
+    let (zs, ys): (Vec<Fr>, Vec<Fr>) = blobs
+        .iter()
+        .zip(commitments.iter())
+        .map(|(blob, commitment)| {
+            let challenge = compute_challenge(blob, commitment);
+            let y = eval_poly(settings, blob, &challenge);
+            (challenge, y)
+        })
+        .unzip();
+
+    verify_kzg_proof_batch(settings, commitments, &zs, &ys, proofs)
+}
+
 fn compute_quotient_eval(settings: &KzgSettings, z: &Fr, poly: &[Fr], y: &Fr) -> Fr {
     settings
         .roots_of_unity_brp
@@ -339,24 +493,22 @@ fn eval_poly(settings: &KzgSettings, blob: &[Fr], z: &Fr) -> Fr {
         .unwrap()
         .inverse()
         .unwrap();
-    let mut res = {
-        if let Some(idx) = settings.roots_of_unity_brp.iter().position(|r| r == z) {
-            blob[idx]
-        } else {
-            blob.iter().zip(settings.roots_of_unity_brp.iter()).fold(
-                Fr::zero(),
-                |mut acc, (el, root)| {
-                    let mut el = el.clone();
-                    el.mul_assign(&root);
-                    let mut z_1 = z.clone();
-                    z_1.sub_assign(&root);
-                    el.mul_assign(&z_1.inverse().unwrap());
-                    acc.add_assign(&el);
-                    acc
-                },
-            )
-        }
-    };
+    // the formula doesn't work on the domain itself, but there the value is known
+    if let Some(idx) = settings.roots_of_unity_brp.iter().position(|r| r == z) {
+        return blob.get(idx).copied().unwrap_or(Fr::zero());
+    }
+    let mut res = blob.iter().zip(settings.roots_of_unity_brp.iter()).fold(
+        Fr::zero(),
+        |mut acc, (el, root)| {
+            let mut el = el.clone();
+            el.mul_assign(&root);
+            let mut z_1 = z.clone();
+            z_1.sub_assign(&root);
+            el.mul_assign(&z_1.inverse().unwrap());
+            acc.add_assign(&el);
+            acc
+        },
+    );
 
     let mut z_1 = z.clone();
     z_1 = z_1.pow([blob.len() as u64]);
@@ -378,6 +530,10 @@ fn compute_challenge(blob: &[Fr], commitment: &G1Affine) -> Fr {
     });
     data.extend(commitment.into_compressed().as_ref());
 
+    hash_to_bls_field(&data)
+}
+
+fn hash_to_bls_field(data: &[u8]) -> Fr {
     let mut result = [0u8; 32];
     let digest = Sha256::digest(data);
     result.copy_from_slice(&digest);
@@ -391,6 +547,31 @@ fn compute_challenge(blob: &[Fr], commitment: &G1Affine) -> Fr {
     Fr::from_repr(FrRepr(repr)).unwrap()
 }
 
+fn compute_batch_challenge(
+    commitments: &[G1Affine],
+    zs: &[Fr],
+    ys: &[Fr],
+    proofs: &[G1Affine],
+) -> Fr {
+    let mut data = String::from("RCKZGBATCH___V1_").into_bytes();
+    data.extend((FIELD_ELEMENTS_PER_BLOB as u64).to_be_bytes());
+    data.extend((commitments.len() as u64).to_be_bytes());
+    for i in 0..commitments.len() {
+        data.extend(commitments[i].into_compressed().as_ref());
+        zs[i]
+            .into_repr()
+            .write_be(&mut data)
+            .expect("should be able to write to data vector");
+        ys[i]
+            .into_repr()
+            .write_be(&mut data)
+            .expect("should be able to write to data vector");
+        data.extend(proofs[i].into_compressed().as_ref());
+    }
+
+    hash_to_bls_field(&data)
+}
+
 fn u8_repr_to_u64_repr_be(bytes: [u8; 32]) -> [u64; 4] {
     bytes
         .array_chunks::<8>()
@@ -461,4 +642,106 @@ mod local_tests {
         let proof = compute_proof_poly(&settings, &blob, &commitment);
         assert!(verify_proof_poly(&settings, &blob, &commitment, &proof));
     }
+
+    #[test]
+    fn test_precomputed_commitment() {
+        let mut rng = rand::thread_rng();
+        let mut blob = [Fr::zero(); FIELD_ELEMENTS_PER_BLOB];
+        blob.iter_mut().for_each(|v| *v = Fr::rand(&mut rng));
+
+        let settings = KzgSettings::new(SETUP_JSON);
+        assert!(settings.lagrange_setup_precomputed().is_none());
+        let precomputed_settings = settings
+            .clone()
+            .with_precomputation(DEFAULT_PRECOMPUTATION_WINDOW_BITS);
+        assert_eq!(
+            precomputed_settings
+                .lagrange_setup_precomputed()
+                .map(|el| el.num_points()),
+            Some(FIELD_ELEMENTS_PER_BLOB)
+        );
+
+        let commitment = compute_commitment(&precomputed_settings, &blob);
+        assert_eq!(commitment, compute_commitment(&settings, &blob));
+        assert_eq!(
+            commitment,
+            naive_multiscalar_mul(settings.lagrange_setup_brp.as_slice(), &blob)
+        );
+
+        let z = Fr::rand(&mut rng);
+        let (proof, y) = compute_proof(&precomputed_settings, &blob, &z);
+        assert!(verify_kzg_proof(&settings, &commitment, &z, &y, &proof));
+    }
+
+    #[test]
+    fn test_batch_verify() {
+        let mut rng = rand::thread_rng();
+        let settings = KzgSettings::new(SETUP_JSON);
+
+        let blobs: Vec<Vec<Fr>> = (0..3)
+            .map(|_| {
+                (0..FIELD_ELEMENTS_PER_BLOB)
+                    .map(|_| Fr::rand(&mut rng))
+                    .collect()
+            })
+            .collect();
+        let commitments: Vec<G1Affine> = blobs
+            .iter()
+            .map(|blob| compute_commitment(&settings, blob))
+            .collect();
+        let zs: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let (proofs, ys): (Vec<G1Affine>, Vec<Fr>) = blobs
+            .iter()
+            .zip(zs.iter())
+            .map(|(blob, z)| compute_proof(&settings, blob, z))
+            .unzip();
+
+        assert!(verify_kzg_proof_batch(&settings, &[], &[], &[], &[]));
+        assert!(verify_kzg_proof_batch(
+            &settings,
+            &commitments,
+            &zs,
+            &ys,
+            &proofs
+        ));
+
+        let mut wrong_ys = ys.clone();
+        wrong_ys[1].add_assign(&Fr::one());
+        assert!(!verify_kzg_proof_batch(
+            &settings,
+            &commitments,
+            &zs,
+            &wrong_ys,
+            &proofs
+        ));
+
+        let mut swapped_proofs = proofs.clone();
+        swapped_proofs.swap(0, 2);
+        assert!(!verify_kzg_proof_batch(
+            &settings,
+            &commitments,
+            &zs,
+            &ys,
+            &swapped_proofs
+        ));
+
+        let blob_refs: Vec<&[Fr]> = blobs.iter().map(|el| el.as_slice()).collect();
+        let blob_proofs: Vec<G1Affine> = blobs
+            .iter()
+            .zip(commitments.iter())
+            .map(|(blob, commitment)| compute_proof_poly(&settings, blob, commitment))
+            .collect();
+        assert!(verify_proof_poly_batch(
+            &settings,
+            &blob_refs,
+            &commitments,
+            &blob_proofs
+        ));
+        assert!(!verify_proof_poly_batch(
+            &settings,
+            &blob_refs,
+            &commitments,
+            &proofs
+        ));
+    }
 }
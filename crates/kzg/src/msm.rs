@@ -0,0 +1,239 @@
+//! Multi-scalar multiplication over G1 used for commitments and opening proofs.
+
+use boojum::pairing::{
+    bls12_381::{Fr, FrRepr, G1Affine, G1},
+    ff::PrimeField,
+    CurveAffine, CurveProjective,
+};
+use rayon::prelude::*;
+
+/// Window size used for the precomputed Lagrange setup. Every point of the setup is stored
+/// `ceil(255 / 8) = 32` times, which is around 13 MB for a blob-sized setup.
+pub const DEFAULT_PRECOMPUTATION_WINDOW_BITS: usize = 8;
+
+fn num_windows(window_bits: usize) -> usize {
+    (Fr::NUM_BITS as usize).div_ceil(window_bits)
+}
+
+// heuristic from the literature, close to optimal for the sizes we care about
+fn window_bits_for_size(size: usize) -> usize {
+    if size < 32 {
+        3
+    } else {
+        (size as f64).ln().ceil() as usize
+    }
+}
+
+/// Returns `window_bits` bits of the scalar starting from `start_bit`.
+fn window_digit(repr: &FrRepr, start_bit: usize, window_bits: usize) -> usize {
+    let limbs = repr.as_ref();
+    let limb_idx = start_bit / 64;
+    let shift = start_bit % 64;
+    if limb_idx >= limbs.len() {
+        return 0;
+    }
+
+    let mut digit = limbs[limb_idx] >> shift;
+    if shift + window_bits > 64 && limb_idx + 1 < limbs.len() {
+        digit |= limbs[limb_idx + 1] << (64 - shift);
+    }
+
+    (digit & ((1u64 << window_bits) - 1)) as usize
+}
+
+/// Sums up `bucket[i] * (i + 1)` with the running sum trick.
+fn reduce_buckets(buckets: &[G1]) -> G1 {
+    let mut running_sum = G1::zero();
+    let mut result = G1::zero();
+    for bucket in buckets.iter().rev() {
+        running_sum.add_assign(bucket);
+        result.add_assign(&running_sum);
+    }
+
+    result
+}
+
+/// Performs a naive MSM, kept as a reference implementation.
+pub fn naive_multiscalar_mul(points: &[G1Affine], scalars: &[Fr]) -> G1Affine {
+    assert!(scalars.len() <= points.len());
+    scalars
+        .par_iter()
+        .zip(points)
+        .fold(G1::zero, |mut acc, (scalar, point)| {
+            acc.add_assign(&point.mul(*scalar));
+            acc
+        })
+        .reduce(G1::zero, |mut a: G1, b: G1| {
+            a.add_assign(&b);
+            a
+        })
+        .into_affine()
+}
+
+/// Performs a windowed Pippenger (bucket) MSM, windows are processed in parallel.
+pub fn multiscalar_mul(points: &[G1Affine], scalars: &[Fr]) -> G1Affine {
+    assert!(scalars.len() <= points.len());
+    if scalars.is_empty() {
+        return G1Affine::zero();
+    }
+
+    let window_bits = window_bits_for_size(scalars.len());
+    let scalars: Vec<FrRepr> = scalars.par_iter().map(|el| el.into_repr()).collect();
+
+    let window_sums: Vec<G1> = (0..num_windows(window_bits))
+        .into_par_iter()
+        .map(|window_idx| {
+            let mut buckets = vec![G1::zero(); (1 << window_bits) - 1];
+            for (scalar, point) in scalars.iter().zip(points) {
+                let digit = window_digit(scalar, window_idx * window_bits, window_bits);
+                if digit != 0 {
+                    buckets[digit - 1].add_assign_mixed(point);
+                }
+            }
+
+            reduce_buckets(&buckets)
+        })
+        .collect();
+
+    let mut result = G1::zero();
+    for window_sum in window_sums.iter().rev() {
+        for _ in 0..window_bits {
+            result.double();
+        }
+        result.add_assign(window_sum);
+    }
+
+    result.into_affine()
+}
+
+///
+/// Fixed-base precomputation for a set of points. Every point `P` is stored as
+/// `P * 2^(window_bits * j)` for all the windows `j`, so the MSM needs just a single
+/// pass over the buckets instead of one per window.
+///
+#[derive(Clone, Debug)]
+pub struct PrecomputedBases {
+    window_bits: usize,
+    num_windows: usize,
+    num_points: usize,
+    bases: Vec<G1Affine>,
+}
+
+impl PrecomputedBases {
+    pub fn new(points: &[G1Affine], window_bits: usize) -> Self {
+        assert!(
+            window_bits > 0 && window_bits < 32,
+            "window size must be in range [1, 32)"
+        );
+        let num_windows = num_windows(window_bits);
+
+        let bases = points
+            .par_iter()
+            .flat_map_iter(|point| {
+                let mut shifted = Vec::with_capacity(num_windows);
+                let mut current = point.into_projective();
+                for _ in 0..num_windows {
+                    shifted.push(current);
+                    for _ in 0..window_bits {
+                        current.double();
+                    }
+                }
+                G1::batch_normalization(&mut shifted);
+
+                shifted.into_iter().map(|el| el.into_affine())
+            })
+            .collect();
+
+        Self {
+            window_bits,
+            num_windows,
+            num_points: points.len(),
+            bases,
+        }
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.num_points
+    }
+
+    /// Computes the MSM of the first `scalars.len()` precomputed points with the scalars.
+    pub fn multiscalar_mul(&self, scalars: &[Fr]) -> G1Affine {
+        assert!(scalars.len() <= self.num_points);
+        let chunk_size = std::cmp::max(scalars.len() / rayon::current_num_threads(), 1);
+
+        scalars
+            .par_chunks(chunk_size)
+            .enumerate()
+            .map(|(chunk_idx, chunk)| {
+                let mut buckets = vec![G1::zero(); (1 << self.window_bits) - 1];
+                for (i, scalar) in chunk.iter().enumerate() {
+                    let repr = scalar.into_repr();
+                    let offset = (chunk_idx * chunk_size + i) * self.num_windows;
+                    for window_idx in 0..self.num_windows {
+                        let digit =
+                            window_digit(&repr, window_idx * self.window_bits, self.window_bits);
+                        if digit != 0 {
+                            buckets[digit - 1].add_assign_mixed(&self.bases[offset + window_idx]);
+                        }
+                    }
+                }
+
+                reduce_buckets(&buckets)
+            })
+            .reduce(G1::zero, |mut a: G1, b: G1| {
+                a.add_assign(&b);
+                a
+            })
+            .into_affine()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boojum::pairing::ff::{Field, Rand};
+
+    fn random_inputs(size: usize) -> (Vec<G1Affine>, Vec<Fr>) {
+        let mut rng = rand::thread_rng();
+        let points = (0..size)
+            .map(|_| G1::rand(&mut rng).into_affine())
+            .collect();
+        let scalars = (0..size).map(|_| Fr::rand(&mut rng)).collect();
+
+        (points, scalars)
+    }
+
+    #[test]
+    fn pippenger_matches_naive() {
+        for size in [1, 7, 31, 32, 100, 513] {
+            let (points, scalars) = random_inputs(size);
+            assert_eq!(
+                multiscalar_mul(&points, &scalars),
+                naive_multiscalar_mul(&points, &scalars)
+            );
+        }
+    }
+
+    #[test]
+    fn precomputed_matches_naive() {
+        let (points, mut scalars) = random_inputs(300);
+        scalars[3] = Fr::zero();
+        scalars[4] = Fr::one();
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        scalars[5] = minus_one;
+
+        for window_bits in [1, 5, DEFAULT_PRECOMPUTATION_WINDOW_BITS] {
+            let precomputed = PrecomputedBases::new(&points, window_bits);
+            assert_eq!(
+                precomputed.multiscalar_mul(&scalars),
+                naive_multiscalar_mul(&points, &scalars)
+            );
+            // fewer scalars than points
+            assert_eq!(
+                precomputed.multiscalar_mul(&scalars[..17]),
+                naive_multiscalar_mul(&points, &scalars[..17])
+            );
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Runner for the consensus spec KZG test vectors, see `consensus_spec_vectors/README.md`.
+
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use super::*;
+
+const VECTORS_DIR: &str = "src/tests/consensus_spec_vectors";
+
+const BYTES_PER_BLOB: usize = 32 * FIELD_ELEMENTS_PER_BLOB;
+
+#[derive(Debug, Deserialize)]
+struct TestCase<I, O> {
+    input: I,
+    output: Option<O>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobInput {
+    blob: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobWithPointInput {
+    blob: String,
+    z: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyKzgProofInput {
+    commitment: String,
+    z: String,
+    y: String,
+    proof: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlobWithCommitmentInput {
+    blob: String,
+    commitment: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyBlobKzgProofInput {
+    blob: String,
+    commitment: String,
+    proof: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyBlobKzgProofBatchInput {
+    blobs: Vec<String>,
+    commitments: Vec<String>,
+    proofs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyCellKzgProofBatchInput {
+    commitments: Vec<String>,
+    cell_indices: Vec<usize>,
+    cells: Vec<String>,
+    proofs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecoverCellsAndKzgProofsInput {
+    cell_indices: Vec<usize>,
+    cells: Vec<String>,
+}
+
+fn bytes_from_hex(value: &str, len: usize) -> Option<Vec<u8>> {
+    let bytes = hex::decode(value.strip_prefix("0x")?).ok()?;
+    (bytes.len() == len).then_some(bytes)
+}
+
+/// Field elements must be canonical, i.e. less than the modulus.
+fn field_element_from_bytes(bytes: &[u8]) -> Option<Fr> {
+    let mut repr = FrRepr::default();
+    repr.read_be(bytes).ok()?;
+    Fr::from_repr(repr).ok()
+}
+
+fn field_element_from_hex(value: &str) -> Option<Fr> {
+    field_element_from_bytes(&bytes_from_hex(value, 32)?)
+}
+
+fn blob_from_hex(value: &str) -> Option<Vec<Fr>> {
+    bytes_from_hex(value, BYTES_PER_BLOB)?
+        .chunks(32)
+        .map(field_element_from_bytes)
+        .collect()
+}
+
+fn cell_from_hex(value: &str) -> Option<Cell> {
+    let bytes = bytes_from_hex(value, 32 * FIELD_ELEMENTS_PER_CELL)?;
+    let mut cell = [Fr::zero(); FIELD_ELEMENTS_PER_CELL];
+    for (el, chunk) in cell.iter_mut().zip(bytes.chunks(32)) {
+        *el = field_element_from_bytes(chunk)?;
+    }
+    Some(cell)
+}
+
+fn g1_from_hex(value: &str) -> Option<G1Affine> {
+    let bytes = bytes_from_hex(value, 48)?;
+    let mut compressed = G1Compressed::empty();
+    compressed.as_mut().copy_from_slice(&bytes);
+    compressed.into_affine().ok()
+}
+
+fn g1_to_hex(point: &G1Affine) -> String {
+    format!("0x{}", hex::encode(point.into_compressed()))
+}
+
+fn field_element_to_hex(value: &Fr) -> String {
+    let mut bytes = [0u8; 32];
+    value.into_repr().write_be(&mut bytes[..]).unwrap();
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn cell_to_hex(cell: &Cell) -> String {
+    let mut bytes = vec![0u8; 32 * FIELD_ELEMENTS_PER_CELL];
+    for (el, chunk) in cell.iter().zip(bytes.chunks_mut(32)) {
+        el.into_repr().write_be(chunk).unwrap();
+    }
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn collect_test_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_test_files(&path, files);
+        } else if path.file_name().is_some_and(|name| name == "data.yaml") {
+            files.push(path);
+        }
+    }
+}
+
+/// Runs all the vectors of the handler, the handler must have at least one.
+fn run_handler<I, O>(handler: &str, run: impl Fn(&I) -> Option<O>)
+where
+    I: DeserializeOwned + Debug,
+    O: DeserializeOwned + PartialEq + Debug,
+{
+    let mut files = vec![];
+    collect_test_files(&Path::new(VECTORS_DIR).join(handler), &mut files);
+    files.sort();
+    assert!(
+        !files.is_empty(),
+        "no {} vectors found in {}",
+        handler,
+        VECTORS_DIR
+    );
+
+    for file in files.iter() {
+        let data = std::fs::read_to_string(file).unwrap();
+        let test_case: TestCase<I, O> = serde_yaml::from_str(&data)
+            .unwrap_or_else(|err| panic!("failed to parse {}: {}", file.display(), err));
+        assert_eq!(
+            run(&test_case.input),
+            test_case.output,
+            "{} failed",
+            file.display()
+        );
+    }
+}
+
+/// Runs the vendored vectors of the official consensus spec tests.
+#[test]
+fn consensus_spec_vectors() {
+    let settings = &*KZG_SETTINGS;
+
+    run_handler("verify_kzg_proof", |input: &VerifyKzgProofInput| {
+        let commitment = g1_from_hex(&input.commitment)?;
+        let z = field_element_from_hex(&input.z)?;
+        let y = field_element_from_hex(&input.y)?;
+        let proof = g1_from_hex(&input.proof)?;
+        Some(verify_kzg_proof(settings, &commitment, &z, &y, &proof))
+    });
+
+    run_handler(
+        "verify_blob_kzg_proof",
+        |input: &VerifyBlobKzgProofInput| {
+            let blob = blob_from_hex(&input.blob)?;
+            let commitment = g1_from_hex(&input.commitment)?;
+            let proof = g1_from_hex(&input.proof)?;
+            let is_valid = verify_proof_poly(settings, &blob, &commitment, &proof);
+            // the valid proofs of the spec are computed from the blob
+            if is_valid {
+                assert_eq!(compute_commitment(settings, &blob), commitment);
+                assert_eq!(compute_proof_poly(settings, &blob, &commitment), proof);
+            }
+            Some(is_valid)
+        },
+    );
+
+    run_handler(
+        "verify_blob_kzg_proof_batch",
+        |input: &VerifyBlobKzgProofBatchInput| {
+            if input.blobs.len() != input.commitments.len()
+                || input.blobs.len() != input.proofs.len()
+            {
+                return None;
+            }
+            let blobs = input
+                .blobs
+                .iter()
+                .map(|el| blob_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let commitments = input
+                .commitments
+                .iter()
+                .map(|el| g1_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let proofs = input
+                .proofs
+                .iter()
+                .map(|el| g1_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let blobs: Vec<&[Fr]> = blobs.iter().map(|el| el.as_slice()).collect();
+            Some(verify_proof_poly_batch(
+                settings,
+                &blobs,
+                &commitments,
+                &proofs,
+            ))
+        },
+    );
+}
+
+/// Runs the handlers whose vectors aren't vendored yet, see `consensus_spec_vectors/README.md`.
+#[test]
+#[ignore = "requires the official vectors of the remaining handlers"]
+fn consensus_spec_vectors_remaining_handlers() {
+    let settings = &*KZG_SETTINGS;
+
+    run_handler("blob_to_kzg_commitment", |input: &BlobInput| {
+        let blob = blob_from_hex(&input.blob)?;
+        Some(g1_to_hex(&compute_commitment(settings, &blob)))
+    });
+
+    run_handler("compute_kzg_proof", |input: &BlobWithPointInput| {
+        let blob = blob_from_hex(&input.blob)?;
+        let z = field_element_from_hex(&input.z)?;
+        let (proof, y) = compute_proof(settings, &blob, &z);
+        Some(vec![g1_to_hex(&proof), field_element_to_hex(&y)])
+    });
+
+    run_handler(
+        "compute_blob_kzg_proof",
+        |input: &BlobWithCommitmentInput| {
+            let blob = blob_from_hex(&input.blob)?;
+            let commitment = g1_from_hex(&input.commitment)?;
+            Some(g1_to_hex(&compute_proof_poly(settings, &blob, &commitment)))
+        },
+    );
+
+    run_handler("compute_cells_and_kzg_proofs", |input: &BlobInput| {
+        let blob = blob_from_hex(&input.blob)?;
+        let (cells, proofs) = compute_cells_and_kzg_proofs(settings, &blob);
+        Some((
+            cells.iter().map(cell_to_hex).collect::<Vec<_>>(),
+            proofs.iter().map(g1_to_hex).collect::<Vec<_>>(),
+        ))
+    });
+
+    run_handler(
+        "verify_cell_kzg_proof_batch",
+        |input: &VerifyCellKzgProofBatchInput| {
+            let commitments = input
+                .commitments
+                .iter()
+                .map(|el| g1_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let cells = input
+                .cells
+                .iter()
+                .map(|el| cell_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let proofs = input
+                .proofs
+                .iter()
+                .map(|el| g1_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            verify_cell_kzg_proof_batch(
+                settings,
+                &commitments,
+                &input.cell_indices,
+                &cells,
+                &proofs,
+            )
+            .ok()
+        },
+    );
+
+    run_handler(
+        "recover_cells_and_kzg_proofs",
+        |input: &RecoverCellsAndKzgProofsInput| {
+            let cells = input
+                .cells
+                .iter()
+                .map(|el| cell_from_hex(el))
+                .collect::<Option<Vec<_>>>()?;
+            let (cells, proofs) =
+                recover_cells_and_kzg_proofs(settings, &input.cell_indices, &cells).ok()?;
+            Some((
+                cells.iter().map(cell_to_hex).collect::<Vec<_>>(),
+                proofs.iter().map(g1_to_hex).collect::<Vec<_>>(),
+            ))
+        },
+    );
+}
@@ -11,6 +11,8 @@ use zkevm_circuits::eip_4844::{
 
 use super::*;
 
+mod consensus_spec;
+
 const KZG_TEST_JSON: &str = include_str!("kzg_test_0.json");
 
 #[serde_with::serde_as]
@@ -135,3 +137,77 @@ fn bytes_test() {
     let decoded_kzg_info = KzgInfo::from_slice(&encoded_info);
     assert_eq!(kzg_info, decoded_kzg_info);
 }
+
+#[test]
+fn fallible_api_test() {
+    let kzg_test: KzgTest = serde_json::from_str(KZG_TEST_JSON).unwrap();
+    let kzg_info = KzgInfo::try_new(&kzg_test.pubdata).unwrap();
+    let encoded_info = kzg_info.to_bytes();
+
+    assert_eq!(
+        KzgInfo::try_from_slice(&encoded_info[1..]),
+        Err(KzgError::InvalidLength {
+            expected: KzgInfo::SERIALIZED_SIZE,
+            actual: KzgInfo::SERIALIZED_SIZE - 1
+        })
+    );
+    assert_eq!(KzgInfo::try_from_slice(&encoded_info), Ok(kzg_info));
+
+    let too_long_pubdata = vec![1u8; ZK_SYNC_BYTES_PER_BLOB + 1];
+    assert_eq!(
+        KzgInfo::try_new(&too_long_pubdata),
+        Err(KzgError::TooManyBlobs {
+            required: 2,
+            available: 1
+        })
+    );
+    assert_eq!(
+        try_pubdata_to_blob_commitments(1, &too_long_pubdata),
+        Err(KzgError::TooManyBlobs {
+            required: 2,
+            available: 1
+        })
+    );
+    assert_eq!(
+        try_pubdata_to_blob_commitments(2, &kzg_test.pubdata),
+        Ok(pubdata_to_blob_commitments(2, &kzg_test.pubdata))
+    );
+}
+
+#[test]
+fn invalid_trusted_setup_test() {
+    let setup: TrustedSetup = serde_json::from_str(include_str!("../trusted_setup.json")).unwrap();
+
+    let mut truncated = setup.clone();
+    truncated.g1_lagrange.pop();
+    assert!(matches!(
+        KzgSettings::try_new_from_trusted_setup(truncated),
+        Err(KzgError::InvalidTrustedSetup(_))
+    ));
+
+    let mut invalid_point = setup.clone();
+    invalid_point.g1_lagrange[7] = format!("0x{}", "ff".repeat(48));
+    assert_eq!(
+        KzgSettings::try_new_from_trusted_setup(invalid_point).err(),
+        Some(KzgError::InvalidPointEncoding { index: 7 })
+    );
+
+    let mut invalid_hex = setup.clone();
+    invalid_hex.g1_lagrange[9] = "0xzz".to_owned();
+    assert_eq!(
+        KzgSettings::try_new_from_trusted_setup(invalid_hex).err(),
+        Some(KzgError::InvalidPointEncoding { index: 9 })
+    );
+
+    let mut invalid_g2_point = setup;
+    invalid_g2_point.g2_monomial[3] = format!("0x{}", "ff".repeat(96));
+    assert_eq!(
+        KzgSettings::try_new_from_trusted_setup(invalid_g2_point).err(),
+        Some(KzgError::InvalidG2PointEncoding { index: 3 })
+    );
+
+    assert!(matches!(
+        KzgSettings::try_new("non_existent_setup.json"),
+        Err(KzgError::InvalidTrustedSetup(_))
+    ));
+}
@@ -1,23 +1,363 @@
-use crate::{KzgSettings, TrustedSetup};
+use std::path::Path;
 
-use once_cell::sync::Lazy;
+use crate::FIELD_ELEMENTS_PER_BLOB;
+use crate::{KzgError, KzgSettings, TrustedSetup};
 
-pub(super) static KZG_SETTINGS: Lazy<KzgSettings> = Lazy::new(|| {
-    // Taken from the C KZG library: https://github.com/ethereum/c-kzg-4844/blob/main/src/trusted_setup.txt
-    const TRUSTED_SETUP: &[u8] = include_bytes!("trusted_setup.json");
-    let setup: TrustedSetup = serde_json::from_slice(TRUSTED_SETUP).unwrap();
-    KzgSettings::new_from_trusted_setup(setup)
+use boojum::{
+    blake2::Digest,
+    pairing::{
+        bls12_381::{Fr, FrRepr, G1Affine, G1Uncompressed, G2Affine, G2Uncompressed},
+        ff::{Field, PrimeField, PrimeFieldRepr},
+        CurveAffine, CurveProjective, EncodedPoint,
+    },
+    sha2::Sha256,
+};
+use once_cell::sync::{Lazy, OnceCell};
+
+static CONFIGURED_KZG_SETTINGS: OnceCell<KzgSettings> = OnceCell::new();
+
+pub(super) static KZG_SETTINGS: Lazy<&'static KzgSettings> = Lazy::new(|| {
+    CONFIGURED_KZG_SETTINGS.get_or_init(|| {
+        // Taken from the C KZG library: https://github.com/ethereum/c-kzg-4844/blob/main/src/trusted_setup.txt
+        const TRUSTED_SETUP: &[u8] = include_bytes!("trusted_setup.json");
+        KzgSettings::from_json_bytes(TRUSTED_SETUP).unwrap()
+    })
 });
 
+/// Sets the settings used by [`KzgInfo`](crate::KzgInfo) and other functions that don't take
+/// the settings explicitly, instead of the bundled trusted setup. Must be called before the
+/// first use of such functions, otherwise the settings are returned back as an error.
+///
+/// The bundled settings have no MSM precomputation, to use it pass the settings built with
+/// [`KzgSettings::with_precomputation`].
+pub fn init_kzg_settings(settings: KzgSettings) -> Result<(), Box<KzgSettings>> {
+    CONFIGURED_KZG_SETTINGS.set(settings).map_err(Box::new)
+}
+
+/// The binary settings cache starts with the magic, the format version and the hash of the
+/// trusted setup the settings were built from, see [`TrustedSetup::hash`].
+const CACHE_MAGIC: [u8; 6] = *b"ZKKZGC";
+const CACHE_FORMAT_VERSION: u16 = 2;
+const CACHE_HEADER_LEN: usize = CACHE_MAGIC.len() + 2 + 32;
+
+impl TrustedSetup {
+    /// Setup with a known secret, must never be used outside of tests.
+    #[cfg(test)]
+    pub(crate) fn insecure_for_testing(secret: Fr) -> Self {
+        use crate::FIELD_ELEMENTS_PER_CELL;
+
+        let mut g1_monomial = Vec::with_capacity(FIELD_ELEMENTS_PER_BLOB);
+        let mut g2_monomial = Vec::with_capacity(FIELD_ELEMENTS_PER_CELL + 1);
+        let mut power = Fr::one();
+        for i in 0..FIELD_ELEMENTS_PER_BLOB {
+            g1_monomial.push(format!(
+                "0x{}",
+                hex::encode(G1Affine::one().mul(power).into_affine().into_compressed())
+            ));
+            if i <= FIELD_ELEMENTS_PER_CELL {
+                g2_monomial.push(format!(
+                    "0x{}",
+                    hex::encode(G2Affine::one().mul(power).into_affine().into_compressed())
+                ));
+            }
+            power.mul_assign(&secret);
+        }
+
+        Self {
+            g1_lagrange: g1_monomial,
+            g2_monomial,
+        }
+    }
+
+    /// Parses the text format used by c-kzg: number of G1 and G2 points, followed by
+    /// G1 points in Lagrange form, G2 points in monomial form and G1 points in monomial form.
+    pub fn from_text(text: &str) -> Result<Self, KzgError> {
+        let mut tokens = text.split_whitespace();
+        let mut next_count = |what: &str| {
+            tokens
+                .next()
+                .and_then(|el| el.parse::<usize>().ok())
+                .ok_or_else(|| KzgError::InvalidTrustedSetup(format!("missing {what} count")))
+        };
+        let num_g1 = next_count("G1 points")?;
+        let num_g2 = next_count("G2 points")?;
+
+        let mut next_points = |count: usize, what: &str| {
+            let points: Vec<String> = tokens.by_ref().take(count).map(str::to_owned).collect();
+            if points.len() != count {
+                return Err(KzgError::InvalidTrustedSetup(format!(
+                    "expected {count} {what} points, got {}",
+                    points.len()
+                )));
+            }
+            Ok(points)
+        };
+        // Lagrange form is recomputed from the monomial one
+        let _g1_lagrange = next_points(num_g1, "G1 Lagrange")?;
+        let g2_monomial = next_points(num_g2, "G2 monomial")?;
+        let g1_monomial = next_points(num_g1, "G1 monomial")?;
+        if tokens.next().is_some() {
+            return Err(KzgError::InvalidTrustedSetup(
+                "unexpected data after the G1 monomial points".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            g1_lagrange: g1_monomial,
+            g2_monomial,
+        })
+    }
+
+    /// SHA256 of the compressed G1 and G2 monomial points, identifies the setup regardless of
+    /// the format it was loaded from.
+    pub fn hash(&self) -> Result<[u8; 32], KzgError> {
+        let decode = |point: &String| hex::decode(point.strip_prefix("0x").unwrap_or(point));
+
+        let mut hasher = Sha256::new();
+        for (index, point) in self.g1_lagrange.iter().enumerate() {
+            hasher.update(decode(point).map_err(|_| KzgError::InvalidPointEncoding { index })?);
+        }
+        hasher.update((self.g2_monomial.len() as u32).to_le_bytes());
+        for (index, point) in self.g2_monomial.iter().enumerate() {
+            hasher.update(decode(point).map_err(|_| KzgError::InvalidG2PointEncoding { index })?);
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+        Ok(hash)
+    }
+}
+
+fn write_fr(out: &mut Vec<u8>, value: &Fr) {
+    value
+        .into_repr()
+        .write_be(out)
+        .expect("should be able to write to data vector");
+}
+
+fn write_g1(out: &mut Vec<u8>, point: &G1Affine) {
+    out.extend(point.into_uncompressed().as_ref());
+}
+
+fn write_g2(out: &mut Vec<u8>, point: &G2Affine) {
+    out.extend(point.into_uncompressed().as_ref());
+}
+
+/// Reader over the cache bytes. The checksum only detects corruption, so points are fully
+/// validated: on the curve and in the prime order subgroup.
+struct CacheReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> CacheReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], KzgError> {
+        if self.data.len() < len {
+            return Err(KzgError::InvalidLength {
+                expected: len,
+                actual: self.data.len(),
+            });
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<usize, KzgError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    fn read_fr(&mut self) -> Result<Fr, KzgError> {
+        let mut repr = FrRepr::default();
+        repr.read_be(self.take(32)?).unwrap();
+        Fr::from_repr(repr)
+            .map_err(|_| KzgError::InvalidTrustedSetup("invalid field element".to_owned()))
+    }
+
+    fn read_g1(&mut self, index: usize) -> Result<G1Affine, KzgError> {
+        let mut point = G1Uncompressed::empty();
+        let len = point.as_ref().len();
+        point.as_mut().copy_from_slice(self.take(len)?);
+        point
+            .into_affine()
+            .map_err(|_| KzgError::InvalidPointEncoding { index })
+    }
+
+    fn read_g2(&mut self, index: usize) -> Result<G2Affine, KzgError> {
+        let mut point = G2Uncompressed::empty();
+        let len = point.as_ref().len();
+        point.as_mut().copy_from_slice(self.take(len)?);
+        point
+            .into_affine()
+            .map_err(|_| KzgError::InvalidG2PointEncoding { index })
+    }
+}
+
+impl KzgSettings {
+    /// Builds the settings from the JSON trusted setup, same format as the bundled one.
+    pub fn from_json_bytes(bytes: &[u8]) -> Result<Self, KzgError> {
+        let setup: TrustedSetup = serde_json::from_slice(bytes).map_err(|err| {
+            KzgError::InvalidTrustedSetup(format!("failed to parse trusted setup: {err}"))
+        })?;
+        Self::try_new_from_trusted_setup(setup)
+    }
+
+    /// Builds the settings from the trusted setup in the text format used by c-kzg.
+    pub fn from_text(text: &str) -> Result<Self, KzgError> {
+        Self::try_new_from_trusted_setup(TrustedSetup::from_text(text)?)
+    }
+
+    /// Same as [`KzgSettings::from_text`], but reads the setup from a file.
+    pub fn from_text_file(path: impl AsRef<Path>) -> Result<Self, KzgError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|err| {
+            KzgError::InvalidTrustedSetup(format!("failed to read {}: {err}", path.display()))
+        })?;
+        Self::from_text(&text)
+    }
+
+    /// Hash of the trusted setup the settings were built from, see [`TrustedSetup::hash`].
+    pub fn setup_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for point in self.setup_g1_monomial.iter() {
+            hasher.update(point.into_compressed());
+        }
+        hasher.update((self.setup_g2_monomial.len() as u32).to_le_bytes());
+        for point in self.setup_g2_monomial.iter() {
+            hasher.update(point.into_compressed());
+        }
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+        hash
+    }
+
+    /// Serializes the settings, including the Lagrange form of the setup, so that they can be
+    /// loaded without redoing the FFT. Precomputed tables are not included.
+    pub fn to_cache_bytes(&self) -> Vec<u8> {
+        let mut out = CACHE_MAGIC.to_vec();
+        out.extend(CACHE_FORMAT_VERSION.to_le_bytes());
+        out.extend(self.setup_hash());
+        self.roots_of_unity_brp
+            .iter()
+            .for_each(|el| write_fr(&mut out, el));
+        write_g2(&mut out, &self.setup_g2_1.into_affine());
+        self.lagrange_setup_brp
+            .iter()
+            .for_each(|el| write_g1(&mut out, el));
+        self.setup_g1_monomial
+            .iter()
+            .for_each(|el| write_g1(&mut out, el));
+        out.extend((self.setup_g2_monomial.len() as u32).to_le_bytes());
+        self.setup_g2_monomial
+            .iter()
+            .for_each(|el| write_g2(&mut out, el));
+
+        let checksum = Sha256::digest(&out);
+        out.extend(checksum);
+        out
+    }
+
+    /// Loads the settings serialized with [`KzgSettings::to_cache_bytes`], the cache must be
+    /// built from the trusted setup with the hash `setup_hash`.
+    pub fn from_cache_bytes(bytes: &[u8], setup_hash: &[u8; 32]) -> Result<Self, KzgError> {
+        if bytes.len() < CACHE_HEADER_LEN + 32 || bytes[..CACHE_MAGIC.len()] != CACHE_MAGIC {
+            return Err(KzgError::InvalidTrustedSetup(
+                "not a KZG settings cache".to_owned(),
+            ));
+        }
+        let version = u16::from_le_bytes([bytes[CACHE_MAGIC.len()], bytes[CACHE_MAGIC.len() + 1]]);
+        if version != CACHE_FORMAT_VERSION {
+            return Err(KzgError::InvalidTrustedSetup(format!(
+                "unsupported cache format version {version}"
+            )));
+        }
+        let (data, checksum) = bytes.split_at(bytes.len() - 32);
+        if Sha256::digest(data).as_slice() != checksum {
+            return Err(KzgError::InvalidTrustedSetup(
+                "cache checksum mismatch".to_owned(),
+            ));
+        }
+        if &data[CACHE_MAGIC.len() + 2..CACHE_HEADER_LEN] != setup_hash {
+            return Err(KzgError::InvalidTrustedSetup(
+                "cache is built from a different trusted setup".to_owned(),
+            ));
+        }
+
+        let mut reader = CacheReader {
+            data: &data[CACHE_HEADER_LEN..],
+        };
+        let mut roots_of_unity_brp = Box::new([Fr::zero(); FIELD_ELEMENTS_PER_BLOB]);
+        for root in roots_of_unity_brp.iter_mut() {
+            *root = reader.read_fr()?;
+        }
+        let setup_g2_1 = reader.read_g2(0)?.into_projective();
+        let mut lagrange_setup_brp = Box::new([G1Affine::zero(); FIELD_ELEMENTS_PER_BLOB]);
+        for (index, point) in lagrange_setup_brp.iter_mut().enumerate() {
+            *point = reader.read_g1(index)?;
+        }
+        let mut setup_g1_monomial = Box::new([G1Affine::zero(); FIELD_ELEMENTS_PER_BLOB]);
+        for (index, point) in setup_g1_monomial.iter_mut().enumerate() {
+            *point = reader.read_g1(index)?;
+        }
+        let num_g2 = reader.read_u32()?;
+        let setup_g2_monomial = (0..num_g2)
+            .map(|index| reader.read_g2(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !reader.data.is_empty() {
+            return Err(KzgError::InvalidLength {
+                expected: data.len() - reader.data.len(),
+                actual: data.len(),
+            });
+        }
+
+        let settings = Self {
+            roots_of_unity_brp,
+            setup_g2_1,
+            lagrange_setup_brp,
+            setup_g1_monomial,
+            setup_g2_monomial,
+            lagrange_setup_precomputed: None,
+        };
+        // the header is covered by the checksum, but not tied to the points
+        if &settings.setup_hash() != setup_hash {
+            return Err(KzgError::InvalidTrustedSetup(
+                "cache points don't match the trusted setup".to_owned(),
+            ));
+        }
+
+        Ok(settings)
+    }
+
+    /// Loads the settings from the cache file if it's valid and built from `setup`, otherwise
+    /// builds them from `setup` and writes the cache for the next time. Failing to write the
+    /// cache is not an error.
+    pub fn load_cached(
+        cache_path: impl AsRef<Path>,
+        setup: TrustedSetup,
+    ) -> Result<Self, KzgError> {
+        let cache_path = cache_path.as_ref();
+        let setup_hash = setup.hash()?;
+        if let Some(settings) = std::fs::read(cache_path)
+            .ok()
+            .and_then(|bytes| Self::from_cache_bytes(&bytes, &setup_hash).ok())
+        {
+            return Ok(settings);
+        }
+
+        let settings = Self::try_new_from_trusted_setup(setup)?;
+        let _ = std::fs::write(cache_path, settings.to_cache_bytes());
+        Ok(settings)
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{compute_commitment, compute_proof, verify_kzg_proof};
+    use boojum::pairing::ff::Rand;
     use zkevm_circuits::eip_4844::input::ELEMENTS_PER_4844_BLOCK;
 
-    use zkevm_circuits::boojum::pairing::{
-        bls12_381::{Fr, FrRepr},
-        ff::{Field as _, PrimeField as _},
-    };
-
     const FIRST_ROOT_OF_UNITY: FrRepr = FrRepr([
         0xe206da11a5d36306,
         0x0ad1347b378fbf96,
@@ -34,4 +374,140 @@ mod tests {
         }
         assert_eq!(value, Fr::one());
     }
+
+    fn to_text(setup: &TrustedSetup, settings: &KzgSettings) -> String {
+        let strip = |el: &String| el.trim_start_matches("0x").to_owned();
+        let mut lines = vec![
+            setup.g1_lagrange.len().to_string(),
+            setup.g2_monomial.len().to_string(),
+        ];
+        // c-kzg stores Lagrange points in natural order
+        let mut lagrange = settings.lagrange_setup_brp.to_vec();
+        crate::bit_reverse_array(&mut lagrange);
+        lines.extend(lagrange.iter().map(|el| hex::encode(el.into_compressed())));
+        lines.extend(setup.g2_monomial.iter().map(strip));
+        lines.extend(setup.g1_lagrange.iter().map(strip));
+        lines.join("\n")
+    }
+
+    #[test]
+    fn trusted_setup_loaders() {
+        let mut rng = rand::thread_rng();
+        let setup = TrustedSetup::insecure_for_testing(Fr::rand(&mut rng));
+        let settings = KzgSettings::try_new_from_trusted_setup(setup.clone()).unwrap();
+
+        let from_json = KzgSettings::from_json_bytes(&serde_json::to_vec(&setup).unwrap()).unwrap();
+        let from_text = KzgSettings::from_text(&to_text(&setup, &settings)).unwrap();
+        let from_cache =
+            KzgSettings::from_cache_bytes(&settings.to_cache_bytes(), &setup.hash().unwrap())
+                .unwrap();
+        assert_eq!(settings.setup_hash(), setup.hash().unwrap());
+
+        for loaded in [from_json, from_text, from_cache] {
+            assert_eq!(loaded.roots_of_unity_brp, settings.roots_of_unity_brp);
+            assert_eq!(loaded.setup_g2_1, settings.setup_g2_1);
+            assert_eq!(loaded.lagrange_setup_brp, settings.lagrange_setup_brp);
+            assert_eq!(loaded.setup_g1_monomial, settings.setup_g1_monomial);
+            assert_eq!(loaded.setup_g2_monomial, settings.setup_g2_monomial);
+        }
+
+        // the insecure setup is a valid one
+        let blob: Vec<Fr> = (0..FIELD_ELEMENTS_PER_BLOB)
+            .map(|_| Fr::rand(&mut rng))
+            .collect();
+        let commitment = compute_commitment(&settings, &blob);
+        let z = Fr::rand(&mut rng);
+        let (proof, y) = compute_proof(&settings, &blob, &z);
+        assert!(verify_kzg_proof(&settings, &commitment, &z, &y, &proof));
+    }
+
+    fn bundled_setup() -> TrustedSetup {
+        serde_json::from_slice(include_bytes!("trusted_setup.json")).unwrap()
+    }
+
+    /// Replaces the checksum after the cache is modified.
+    fn reseal(cache: &mut Vec<u8>) {
+        cache.truncate(cache.len() - 32);
+        let checksum = Sha256::digest(&cache);
+        cache.extend(checksum);
+    }
+
+    #[test]
+    fn invalid_cache_and_text() {
+        let setup = bundled_setup();
+        let setup_hash = setup.hash().unwrap();
+        let settings = KzgSettings::try_new_from_trusted_setup(setup).unwrap();
+        let mut cache = settings.to_cache_bytes();
+
+        assert!(KzgSettings::from_cache_bytes(&cache[..100], &setup_hash).is_err());
+        let len = cache.len();
+        cache[len / 2] ^= 1;
+        assert_eq!(
+            KzgSettings::from_cache_bytes(&cache, &setup_hash).err(),
+            Some(KzgError::InvalidTrustedSetup(
+                "cache checksum mismatch".to_owned()
+            ))
+        );
+
+        // the first Lagrange point is moved off the curve
+        let mut cache = settings.to_cache_bytes();
+        let first_g1 = CACHE_HEADER_LEN + 32 * FIELD_ELEMENTS_PER_BLOB + 192;
+        cache[first_g1 + 95] ^= 1;
+        reseal(&mut cache);
+        assert_eq!(
+            KzgSettings::from_cache_bytes(&cache, &setup_hash).err(),
+            Some(KzgError::InvalidPointEncoding { index: 0 })
+        );
+
+        let mut cache = settings.to_cache_bytes();
+        cache[CACHE_MAGIC.len()] += 1;
+        reseal(&mut cache);
+        assert!(matches!(
+            KzgSettings::from_cache_bytes(&cache, &setup_hash),
+            Err(KzgError::InvalidTrustedSetup(_))
+        ));
+
+        // the old c-kzg format without the G1 monomial points
+        assert!(matches!(
+            KzgSettings::from_text("2\n1\naa\nbb\ncc"),
+            Err(KzgError::InvalidTrustedSetup(_))
+        ));
+        assert!(matches!(
+            KzgSettings::from_text(""),
+            Err(KzgError::InvalidTrustedSetup(_))
+        ));
+    }
+
+    #[test]
+    fn cached_settings_are_reused() {
+        let path = std::env::temp_dir().join(format!("kzg_settings_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let built = KzgSettings::load_cached(&path, bundled_setup()).unwrap();
+        let cache = std::fs::read(&path).unwrap();
+        let loaded = KzgSettings::load_cached(&path, bundled_setup()).unwrap();
+        assert_eq!(loaded.lagrange_setup_brp, built.lagrange_setup_brp);
+        assert_eq!(std::fs::read(&path).unwrap(), cache);
+
+        // the cache of another setup is rebuilt
+        let other_setup = TrustedSetup::insecure_for_testing(Fr::from_str("42").unwrap());
+        let other_hash = other_setup.hash().unwrap();
+        assert_eq!(
+            KzgSettings::from_cache_bytes(&cache, &other_hash).err(),
+            Some(KzgError::InvalidTrustedSetup(
+                "cache is built from a different trusted setup".to_owned()
+            ))
+        );
+        let rebuilt = KzgSettings::load_cached(&path, other_setup).unwrap();
+        assert_eq!(rebuilt.setup_hash(), other_hash);
+        assert_ne!(rebuilt.lagrange_setup_brp, built.lagrange_setup_brp);
+        assert_eq!(
+            KzgSettings::from_cache_bytes(&std::fs::read(&path).unwrap(), &other_hash)
+                .unwrap()
+                .setup_hash(),
+            other_hash
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use circuit_definitions::circuit_definitions::recursion_layer::ZkSyncRecursionLayerStorageType;
 use circuit_definitions::zkevm_circuits::scheduler::aux::BaseLayerCircuitType;
 
@@ -10,89 +12,248 @@ pub enum FileKind {
     Binary,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLayer {
+    Basic,
+    Leaf,
+    Node,
+    RecursionTip,
+    Scheduler,
+    Compression,
+    CompressionWrapper,
+}
+
+impl KeyLayer {
+    pub const ALL: [KeyLayer; 7] = [
+        KeyLayer::Basic,
+        KeyLayer::Leaf,
+        KeyLayer::Node,
+        KeyLayer::RecursionTip,
+        KeyLayer::Scheduler,
+        KeyLayer::Compression,
+        KeyLayer::CompressionWrapper,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyLayer::Basic => "basic",
+            KeyLayer::Leaf => "leaf",
+            KeyLayer::Node => "node",
+            KeyLayer::RecursionTip => "recursion_tip",
+            KeyLayer::Scheduler => "scheduler",
+            KeyLayer::Compression => "compression",
+            KeyLayer::CompressionWrapper => "compression_wrapper",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KeyArtifact {
     pub file_name: String,
     pub kind: FileKind,
+    pub layer: KeyLayer,
+    pub circuit_type: Option<u8>,
 }
 
-pub fn planned_key_artifacts() -> Vec<KeyArtifact> {
-    let mut artifacts = Vec::new();
-
-    for basic_circuit_type in BaseLayerCircuitType::as_iter_u8() {
-        artifacts.push(KeyArtifact {
-            file_name: format!("verification_basic_{}_key.json", basic_circuit_type),
-            kind: FileKind::Json,
-        });
-        artifacts.push(KeyArtifact {
-            file_name: format!("finalization_hints_basic_{}.bin", basic_circuit_type),
-            kind: FileKind::Binary,
-        });
+impl KeyArtifact {
+    /// Verification key and finalization hints of a circuit, named as in zksync-era.
+    fn for_circuit(layer: KeyLayer, circuit_type: Option<u8>) -> [Self; 2] {
+        let circuit_name = circuit_name(layer, circuit_type);
+        // zksync-era stores compression finalization hints as JSON despite the .bin extension.
+        let finalization_hints_kind = match layer {
+            KeyLayer::Compression | KeyLayer::CompressionWrapper => FileKind::Json,
+            _ => FileKind::Binary,
+        };
+
+        [
+            Self {
+                file_name: format!("verification_{circuit_name}_key.json"),
+                kind: FileKind::Json,
+                layer,
+                circuit_type,
+            },
+            Self {
+                file_name: format!("finalization_hints_{circuit_name}.bin"),
+                kind: finalization_hints_kind,
+                layer,
+                circuit_type,
+            },
+        ]
     }
 
-    for leaf_circuit_type in ZkSyncRecursionLayerStorageType::leafs_as_iter_u8() {
-        artifacts.push(KeyArtifact {
-            file_name: format!("verification_leaf_{}_key.json", leaf_circuit_type),
-            kind: FileKind::Json,
-        });
-        artifacts.push(KeyArtifact {
-            file_name: format!("finalization_hints_leaf_{}.bin", leaf_circuit_type),
-            kind: FileKind::Binary,
-        });
+    /// Circuit name in the `--only` filter format, e.g. `basic_5` or `node`.
+    pub fn circuit_name(&self) -> String {
+        circuit_name(self.layer, self.circuit_type)
     }
+}
 
-    artifacts.extend([
-        KeyArtifact {
-            file_name: "verification_node_key.json".to_owned(),
-            kind: FileKind::Json,
-        },
-        KeyArtifact {
-            file_name: "finalization_hints_node.bin".to_owned(),
-            kind: FileKind::Binary,
-        },
-        KeyArtifact {
-            file_name: "verification_recursion_tip_key.json".to_owned(),
-            kind: FileKind::Json,
-        },
-        KeyArtifact {
-            file_name: "finalization_hints_recursion_tip.bin".to_owned(),
-            kind: FileKind::Binary,
-        },
-        KeyArtifact {
-            file_name: "verification_scheduler_key.json".to_owned(),
-            kind: FileKind::Json,
-        },
-        KeyArtifact {
-            file_name: "finalization_hints_scheduler.bin".to_owned(),
-            kind: FileKind::Binary,
-        },
-    ]);
-
-    for circuit_type in COMPRESSION_CIRCUIT_TYPES {
-        artifacts.push(KeyArtifact {
-            file_name: format!("verification_compression_{}_key.json", circuit_type),
-            kind: FileKind::Json,
-        });
-        artifacts.push(KeyArtifact {
-            file_name: format!("finalization_hints_compression_{}.bin", circuit_type),
-            kind: FileKind::Json,
-        });
+fn circuit_name(layer: KeyLayer, circuit_type: Option<u8>) -> String {
+    match circuit_type {
+        Some(circuit_type) => format!("{}_{}", layer.name(), circuit_type),
+        None => layer.name().to_owned(),
     }
+}
 
-    for circuit_type in COMPRESSION_FOR_WRAPPER_CIRCUIT_TYPES {
-        artifacts.push(KeyArtifact {
-            file_name: format!("verification_compression_wrapper_{}_key.json", circuit_type),
-            kind: FileKind::Json,
-        });
-        artifacts.push(KeyArtifact {
-            file_name: format!(
-                "finalization_hints_compression_wrapper_{}.bin",
-                circuit_type
-            ),
-            kind: FileKind::Json,
-        });
+/// Selects artifacts by layer (`leaf`) or by layer and circuit type (`leaf_3`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitFilter {
+    pub layer: KeyLayer,
+    pub circuit_type: Option<u8>,
+}
+
+impl CircuitFilter {
+    pub fn matches(&self, artifact: &KeyArtifact) -> bool {
+        self.layer == artifact.layer
+            && (self.circuit_type.is_none() || self.circuit_type == artifact.circuit_type)
+    }
+}
+
+impl FromStr for CircuitFilter {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parse_layer = |name: &str| KeyLayer::ALL.into_iter().find(|layer| layer.name() == name);
+
+        if let Some(layer) = parse_layer(value) {
+            return Ok(Self {
+                layer,
+                circuit_type: None,
+            });
+        }
+
+        value
+            .rsplit_once('_')
+            .and_then(|(layer, circuit_type)| {
+                Some(Self {
+                    layer: parse_layer(layer)?,
+                    circuit_type: Some(circuit_type.parse().ok()?),
+                })
+            })
+            .ok_or_else(|| {
+                let layers: Vec<_> = KeyLayer::ALL.iter().map(|layer| layer.name()).collect();
+                format!(
+                    "invalid circuit filter `{value}`, expected `<layer>` or `<layer>_<circuit type>` with layer one of: {}",
+                    layers.join(", ")
+                )
+            })
     }
+}
+
+pub fn planned_key_artifacts() -> Vec<KeyArtifact> {
+    let mut circuits = Vec::new();
+    circuits.extend(
+        BaseLayerCircuitType::as_iter_u8()
+            .map(|circuit_type| (KeyLayer::Basic, Some(circuit_type))),
+    );
+    circuits.extend(
+        ZkSyncRecursionLayerStorageType::leafs_as_iter_u8()
+            .map(|circuit_type| (KeyLayer::Leaf, Some(circuit_type))),
+    );
+    circuits.extend(
+        [KeyLayer::Node, KeyLayer::RecursionTip, KeyLayer::Scheduler].map(|layer| (layer, None)),
+    );
+    circuits.extend(
+        COMPRESSION_CIRCUIT_TYPES.map(|circuit_type| (KeyLayer::Compression, Some(circuit_type))),
+    );
+    circuits.extend(
+        COMPRESSION_FOR_WRAPPER_CIRCUIT_TYPES
+            .map(|circuit_type| (KeyLayer::CompressionWrapper, Some(circuit_type))),
+    );
 
     // TODO: Extend this list with snark and commitment artifacts.
-    artifacts
+    circuits
+        .into_iter()
+        .flat_map(|(layer, circuit_type)| KeyArtifact::for_circuit(layer, circuit_type))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circuit_filter_from_str() {
+        for (value, layer, circuit_type) in [
+            ("basic", KeyLayer::Basic, None),
+            ("leaf_3", KeyLayer::Leaf, Some(3)),
+            ("recursion_tip", KeyLayer::RecursionTip, None),
+            ("compression_wrapper", KeyLayer::CompressionWrapper, None),
+            (
+                "compression_wrapper_5",
+                KeyLayer::CompressionWrapper,
+                Some(5),
+            ),
+            ("basic_255", KeyLayer::Basic, Some(255)),
+        ] {
+            assert_eq!(
+                value.parse(),
+                Ok(CircuitFilter {
+                    layer,
+                    circuit_type
+                }),
+                "{value}"
+            );
+        }
+
+        for value in ["", "foo", "leaf_", "leaf_x", "basic_256", "_3", "recursion"] {
+            let err = value.parse::<CircuitFilter>().unwrap_err();
+            assert!(err.contains(&format!("`{value}`")), "{err}");
+        }
+    }
+
+    #[test]
+    fn circuit_filter_matches_planned_artifacts() {
+        let artifacts = planned_key_artifacts();
+        let count = |filter: &str| {
+            let filter: CircuitFilter = filter.parse().unwrap();
+            artifacts
+                .iter()
+                .filter(|artifact| filter.matches(artifact))
+                .count()
+        };
+
+        // verification key and finalization hints for each circuit
+        assert_eq!(count("scheduler"), 2);
+        assert_eq!(count("leaf_3"), 2);
+        assert_eq!(count("compression_wrapper_1"), 2);
+        assert_eq!(count("compression"), 2 * COMPRESSION_CIRCUIT_TYPES.len());
+        assert_eq!(count("leaf_2"), 0);
+
+        for artifact in &artifacts {
+            let filter: CircuitFilter = artifact.circuit_name().parse().unwrap();
+            assert!(filter.matches(artifact), "{}", artifact.file_name);
+        }
+    }
+
+    #[test]
+    fn planned_artifacts_use_era_file_names() {
+        let artifacts = planned_key_artifacts();
+        let find = |file_name: &str| {
+            artifacts
+                .iter()
+                .find(|artifact| artifact.file_name == file_name)
+                .unwrap_or_else(|| panic!("{file_name} is not planned"))
+        };
+
+        assert!(matches!(
+            find("verification_basic_1_key.json").kind,
+            FileKind::Json
+        ));
+        assert!(matches!(
+            find("finalization_hints_recursion_tip.bin").kind,
+            FileKind::Binary
+        ));
+        assert!(matches!(
+            find("finalization_hints_compression_wrapper_5.bin").kind,
+            FileKind::Json
+        ));
+        assert_eq!(find("verification_node_key.json").circuit_name(), "node");
+        assert_eq!(
+            artifacts.len(),
+            2 * (2 * BaseLayerCircuitType::as_iter_u8().count()
+                + 3
+                + COMPRESSION_CIRCUIT_TYPES.len()
+                + COMPRESSION_FOR_WRAPPER_CIRCUIT_TYPES.len())
+        );
+    }
 }
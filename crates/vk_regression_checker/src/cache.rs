@@ -0,0 +1,397 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use zkevm_test_harness::boojum::cs::implementations::polynomial_storage::SetupBaseStorage;
+use zkevm_test_harness::boojum::cs::CSGeometry;
+use zkevm_test_harness::boojum::field::goldilocks::GoldilocksField;
+use zkevm_test_harness::boojum::field::U64Representable;
+use zkevm_test_harness::sha2::{Digest, Sha256};
+
+/// Bump when the layout of cache entries or the fingerprint inputs change.
+const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// Content-addressed storage of generated keys: every entry is stored under the fingerprint of
+/// everything the key depends on, so stale entries are never reused, only left behind.
+pub struct KeyCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedKey<V, H> {
+    vk: V,
+    finalization_hint: H,
+}
+
+impl KeyCache {
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| {
+            format!(
+                "while attempting to create key cache directory {}",
+                dir.display()
+            )
+        })?;
+
+        Ok(Self {
+            dir: dir.to_owned(),
+        })
+    }
+
+    fn entry_path(&self, fingerprint: &str) -> PathBuf {
+        self.dir.join(format!("{fingerprint}.json"))
+    }
+
+    /// Unreadable entries are treated as missing, they are overwritten after regeneration.
+    fn load<V: DeserializeOwned, H: DeserializeOwned>(&self, fingerprint: &str) -> Option<(V, H)> {
+        let path = self.entry_path(fingerprint);
+        let payload = fs::read(&path).ok()?;
+        match serde_json::from_slice::<CachedKey<V, H>>(&payload) {
+            Ok(entry) => Some((entry.vk, entry.finalization_hint)),
+            Err(err) => {
+                warn!("ignoring corrupted cache entry {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    fn store<V: Serialize, H: Serialize>(
+        &self,
+        fingerprint: &str,
+        vk: &V,
+        finalization_hint: &H,
+    ) -> Result<()> {
+        let path = self.entry_path(fingerprint);
+        let payload = serde_json::to_vec(&CachedKey {
+            vk,
+            finalization_hint,
+        })
+        .context("while attempting to serialize cache entry")?;
+        // Write to a temporary file first, so interrupted runs don't leave truncated entries.
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, payload)
+            .with_context(|| format!("while attempting to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("while attempting to write {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Hashes everything that determines the key of a circuit: its description and dependencies,
+/// completed with the synthesized setup by [`generate_with_cache`].
+pub struct Fingerprint {
+    hasher: Sha256,
+}
+
+impl Fingerprint {
+    pub fn new(layer: &str, circuit_type: u8, description: &str) -> Self {
+        let mut fingerprint = Self {
+            hasher: Sha256::new(),
+        };
+        fingerprint.update(&CACHE_FORMAT_VERSION.to_be_bytes());
+        fingerprint.update(env!("CARGO_PKG_VERSION").as_bytes());
+        fingerprint.update(layer.as_bytes());
+        fingerprint.update(&[circuit_type]);
+        fingerprint.update(description.as_bytes());
+        fingerprint
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        // Length prefix keeps the encoding of consecutive parts unambiguous.
+        self.hasher.update((bytes.len() as u64).to_be_bytes());
+        self.hasher.update(bytes);
+    }
+
+    pub fn with_structure(
+        self,
+        geometry: CSGeometry,
+        size_hint: (Option<usize>, Option<usize>),
+    ) -> Result<Self> {
+        self.with_json(&geometry)?.with_json(&size_hint)
+    }
+
+    /// Adds the LDE factor and the setup tree cap size, which are part of the verification key.
+    pub fn with_proof_config(self, fri_lde_factor: usize, cap_size: usize) -> Result<Self> {
+        self.with_json(&(fri_lde_factor, cap_size))
+    }
+
+    /// Adds a dependency, e.g. the verification key the circuit verifies.
+    pub fn with_json(mut self, value: &impl Serialize) -> Result<Self> {
+        let payload =
+            serde_json::to_vec(value).context("while attempting to serialize fingerprint input")?;
+        self.update(&payload);
+        Ok(self)
+    }
+
+    /// Adds the setup of the circuit synthesized in setup mode: the gate set with the selectors
+    /// placement, lookup tables, and the constant and copy permutation columns. These change
+    /// with the circuit logic even if the geometry stays the same.
+    pub fn with_setup(self, setup_base: &SetupBaseStorage<GoldilocksField>) -> Result<Self> {
+        let columns = [
+            &setup_base.copy_permutation_polys,
+            &setup_base.constant_columns,
+            &setup_base.lookup_tables_columns,
+        ];
+        let mut fingerprint = self
+            .with_json(&setup_base.selectors_placement)?
+            .with_json(&setup_base.table_ids_column_idxes)?
+            .with_json(&columns.map(|columns| columns.len()))?;
+        for column in columns.into_iter().flatten() {
+            let values: Vec<u8> = column
+                .storage
+                .iter()
+                .flat_map(|el| el.as_u64_reduced().to_le_bytes())
+                .collect();
+            fingerprint.update(&values);
+        }
+        Ok(fingerprint)
+    }
+
+    pub fn finish(self) -> String {
+        self.hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+pub struct PlannedKey<C> {
+    pub name: String,
+    pub fingerprint: Fingerprint,
+    pub circuit: C,
+}
+
+#[derive(Default, Debug)]
+pub struct GenerationReport {
+    pub regenerated: Vec<String>,
+    pub reused: Vec<String>,
+}
+
+impl GenerationReport {
+    pub fn log(&self) {
+        info!(
+            "Regenerated {} keys, reused {} cached keys",
+            self.regenerated.len(),
+            self.reused.len()
+        );
+        for name in self.regenerated.iter() {
+            info!("regenerated: {name}");
+        }
+    }
+}
+
+/// Generates keys for the planned circuits in parallel. Every circuit is synthesized once with
+/// `synthesize`. With a cache, its `setup_base` completes the fingerprint and keys with matching
+/// fingerprints are taken from the cache, otherwise `generate` builds the key from the same
+/// synthesized circuit. Results are in the order of `planned`.
+pub fn generate_with_cache<C, S, V, H>(
+    cache: Option<&KeyCache>,
+    report: &mut GenerationReport,
+    jobs: usize,
+    planned: Vec<PlannedKey<C>>,
+    synthesize: impl Fn(C) -> S + Sync,
+    setup_base: impl Fn(&S) -> SetupBaseStorage<GoldilocksField> + Sync,
+    generate: impl Fn(S) -> (V, H) + Sync,
+) -> Result<Vec<(V, H)>>
+where
+    C: Send,
+    V: Serialize + DeserializeOwned + Send,
+    H: Serialize + DeserializeOwned + Send,
+{
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .context("while attempting to create verification key generation thread pool")?;
+    let results: Vec<Result<_>> = pool.install(|| {
+        planned
+            .into_par_iter()
+            .map(|key| {
+                let synthesized = synthesize(key.circuit);
+                let fingerprint = match cache {
+                    Some(cache) => {
+                        let fingerprint = key
+                            .fingerprint
+                            .with_setup(&setup_base(&synthesized))?
+                            .finish();
+                        if let Some((vk, hint)) = cache.load::<V, H>(&fingerprint) {
+                            return Ok((key.name, false, vk, hint));
+                        }
+                        Some((cache, fingerprint))
+                    }
+                    None => None,
+                };
+
+                info!("Generating {}", key.name);
+                let (vk, hint) = generate(synthesized);
+                if let Some((cache, fingerprint)) = fingerprint {
+                    cache
+                        .store(&fingerprint, &vk, &hint)
+                        .with_context(|| format!("while attempting to cache {}", key.name))?;
+                }
+                Ok((key.name, true, vk, hint))
+            })
+            .collect()
+    });
+
+    let mut generated = Vec::with_capacity(results.len());
+    for result in results {
+        let (name, regenerated, vk, hint) = result?;
+        if regenerated {
+            report.regenerated.push(name);
+        } else {
+            report.reused.push(name);
+        }
+        generated.push((vk, hint));
+    }
+
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use zkevm_test_harness::boojum::config::SetupCSConfig;
+    use zkevm_test_harness::boojum::cs::cs_builder::new_builder;
+    use zkevm_test_harness::boojum::cs::cs_builder_reference::CsReferenceImplementationBuilder;
+    use zkevm_test_harness::boojum::cs::gates::{
+        ConstantAllocatableCS, ConstantsAllocatorGate, NopGate,
+    };
+    use zkevm_test_harness::boojum::cs::traits::gate::GatePlacementStrategy;
+    use zkevm_test_harness::boojum::worker::Worker;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    type F = GoldilocksField;
+
+    const GEOMETRY: CSGeometry = CSGeometry {
+        num_columns_under_copy_permutation: 8,
+        num_witness_columns: 0,
+        num_constant_columns: 2,
+        max_allowed_constraint_degree: 4,
+    };
+
+    /// Setup of a toy circuit that allocates the given constants.
+    fn toy_setup(constants: &[u64]) -> SetupBaseStorage<F> {
+        let builder_impl =
+            CsReferenceImplementationBuilder::<F, F, SetupCSConfig>::new(GEOMETRY, 1 << 4);
+        let builder = new_builder::<_, F>(builder_impl);
+        let builder = ConstantsAllocatorGate::configure_builder(
+            builder,
+            GatePlacementStrategy::UseGeneralPurposeColumns,
+        );
+        let builder =
+            NopGate::configure_builder(builder, GatePlacementStrategy::UseGeneralPurposeColumns);
+        let mut cs = builder.build(1 << 8);
+        for constant in constants {
+            cs.allocate_constant(F::from_u64_unchecked(*constant));
+        }
+        cs.pad_and_shrink();
+
+        cs.into_assembly::<std::alloc::Global>()
+            .create_base_setup(&Worker::new_with_num_threads(1), &mut ())
+    }
+
+    fn fingerprint(setup_base: &SetupBaseStorage<F>) -> String {
+        Fingerprint::new("basic", 1, "toy")
+            .with_structure(GEOMETRY, (Some(1 << 4), Some(1 << 8)))
+            .unwrap()
+            .with_setup(setup_base)
+            .unwrap()
+            .finish()
+    }
+
+    #[test]
+    fn setup_changes_invalidate_fingerprint() {
+        let setup_base = toy_setup(&[1, 2, 3]);
+        assert_eq!(
+            fingerprint(&setup_base),
+            fingerprint(&toy_setup(&[1, 2, 3]))
+        );
+
+        // same geometry and size hint, different circuit logic
+        let changed_constant = toy_setup(&[1, 2, 4]);
+        assert_eq!(
+            setup_base.constant_columns.len(),
+            changed_constant.constant_columns.len()
+        );
+        assert_ne!(fingerprint(&setup_base), fingerprint(&changed_constant));
+        let added_constant = toy_setup(&[1, 2, 3, 4]);
+        assert_ne!(fingerprint(&setup_base), fingerprint(&added_constant));
+    }
+
+    #[test]
+    fn changed_setup_is_regenerated() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = KeyCache::open(dir.path()).unwrap();
+        let syntheses = AtomicUsize::new(0);
+        let run = |constants: Vec<u64>, report: &mut GenerationReport| {
+            let planned = vec![PlannedKey {
+                name: "toy".to_owned(),
+                fingerprint: Fingerprint::new("basic", 1, "toy"),
+                circuit: constants,
+            }];
+            generate_with_cache(
+                Some(&cache),
+                report,
+                1,
+                planned,
+                |constants| {
+                    syntheses.fetch_add(1, Ordering::Relaxed);
+                    constants
+                },
+                |constants| toy_setup(constants),
+                |constants| (constants.clone(), constants.len()),
+            )
+            .unwrap()
+        };
+
+        let mut report = GenerationReport::default();
+        assert_eq!(run(vec![1, 2, 3], &mut report), [(vec![1, 2, 3], 3)]);
+        assert_eq!(run(vec![1, 2, 3], &mut report), [(vec![1, 2, 3], 3)]);
+        assert_eq!(run(vec![1, 2, 5], &mut report), [(vec![1, 2, 5], 3)]);
+        assert_eq!(report.regenerated, ["toy", "toy"]);
+        assert_eq!(report.reused, ["toy"]);
+        // regenerated keys reuse the circuit synthesized for the fingerprint
+        assert_eq!(syntheses.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn proof_config_changes_invalidate_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = KeyCache::open(dir.path()).unwrap();
+        let run = |fri_lde_factor: usize, cap_size: usize, report: &mut GenerationReport| {
+            let planned = vec![PlannedKey {
+                name: format!("toy {fri_lde_factor} {cap_size}"),
+                fingerprint: Fingerprint::new("basic", 1, "toy")
+                    .with_proof_config(fri_lde_factor, cap_size)
+                    .unwrap(),
+                circuit: vec![1, 2, 3],
+            }];
+            generate_with_cache(
+                Some(&cache),
+                report,
+                1,
+                planned,
+                |constants: Vec<u64>| constants,
+                |constants| toy_setup(constants),
+                |constants| (constants, (fri_lde_factor, cap_size)),
+            )
+            .unwrap()
+        };
+
+        let mut report = GenerationReport::default();
+        assert_eq!(run(2, 16, &mut report), [(vec![1, 2, 3], (2, 16))]);
+        assert_eq!(run(2, 16, &mut report), [(vec![1, 2, 3], (2, 16))]);
+        assert_eq!(run(4, 16, &mut report), [(vec![1, 2, 3], (4, 16))]);
+        assert_eq!(run(2, 32, &mut report), [(vec![1, 2, 3], (2, 32))]);
+        assert_eq!(report.regenerated, ["toy 2 16", "toy 4 16", "toy 2 32"]);
+        assert_eq!(report.reused, ["toy 2 16"]);
+    }
+}
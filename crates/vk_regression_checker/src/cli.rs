@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::artifacts::CircuitFilter;
 
 #[derive(Debug, Parser)]
 #[command(about = "Verification key regression checker")]
@@ -15,6 +17,10 @@ pub enum Command {
     Generate(GenerateArgs),
     /// Generate keys and compare them with a reference key folder.
     Compare(CompareArgs),
+    /// Compare two existing key folders field by field, without generating keys.
+    Diff(DiffArgs),
+    /// Compute the commitments of a key folder that go into the scheduler and on-chain.
+    Commitments(CommitmentsArgs),
 }
 
 #[derive(Debug, Args)]
@@ -25,6 +31,9 @@ pub struct GenerateArgs {
     /// Number of verification key generation jobs.
     #[arg(long, default_value_t = 1)]
     pub jobs: usize,
+    /// Directory for caching generated keys between runs.
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -38,4 +47,50 @@ pub struct CompareArgs {
     /// Number of verification key generation jobs.
     #[arg(long, default_value_t = 1)]
     pub jobs: usize,
+    /// Directory for caching generated keys between runs.
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Directory with reference keys.
+    #[arg(long)]
+    pub keys_dir: PathBuf,
+    /// Directory with keys to compare against the reference.
+    #[arg(long)]
+    pub generated_dir: PathBuf,
+    #[command(flatten)]
+    pub report: ReportArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct CommitmentsArgs {
+    /// Directory with keys.
+    #[arg(long)]
+    pub keys_dir: PathBuf,
+    /// Output file for the JSON manifest, printed to stdout if not set.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Don't require `verification_snark_key.json` and leave the SNARK wrapper hash out.
+    #[arg(long)]
+    pub skip_snark_wrapper: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// Report format.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Only compare these circuits, e.g. `basic`, `leaf_3` or `scheduler`.
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<CircuitFilter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
 }
@@ -0,0 +1,296 @@
+use std::path::Path;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use circuit_definitions::circuit_definitions::aux_layer::ZkSyncSnarkWrapperCircuit;
+use circuit_definitions::circuit_definitions::base_layer::ZkSyncBaseLayerVerificationKey;
+use circuit_definitions::circuit_definitions::recursion_layer::{
+    base_circuit_type_into_recursive_leaf_circuit_type, ZkSyncRecursionLayerVerificationKey,
+    ZkSyncRecursionVerificationKey,
+};
+use circuit_definitions::zkevm_circuits::scheduler::aux::BaseLayerCircuitType;
+use serde::Serialize;
+use tracing::info;
+use zkevm_test_harness::boojum::field::goldilocks::GoldilocksField;
+use zkevm_test_harness::boojum::field::U64Representable;
+use zkevm_test_harness::franklin_crypto::bellman::pairing::bn256::{Bn256, Fq};
+use zkevm_test_harness::franklin_crypto::bellman::pairing::ff::{
+    Field, PrimeField, PrimeFieldRepr,
+};
+use zkevm_test_harness::franklin_crypto::bellman::pairing::CurveAffine;
+use zkevm_test_harness::franklin_crypto::bellman::plonk::better_better_cs::setup::VerificationKey as SnarkVK;
+use zkevm_test_harness::sha3::{Digest, Keccak256};
+use zkevm_test_harness::witness::recursive_aggregation::{
+    compute_leaf_params, compute_leaf_vks_and_params_commitment, compute_node_vk_commitment,
+};
+
+use crate::file_io::read_json_as;
+
+/// zksync-era keeps the SNARK wrapper key next to the others, but `generate` doesn't produce it.
+const SNARK_WRAPPER_VK_FILE_NAME: &str = "verification_snark_key.json";
+
+#[derive(Debug, Serialize)]
+pub struct LeafLayerParameters {
+    pub circuit_type: u8,
+    pub basic_circuit_vk_commitment: String,
+    pub leaf_layer_vk_commitment: String,
+}
+
+/// Everything that goes into the scheduler or on-chain, hex-encoded.
+#[derive(Debug, Serialize)]
+pub struct CommitmentsManifest {
+    pub leaf_layer_parameters: Vec<LeafLayerParameters>,
+    /// `compute_leaf_vks_and_params_commitment` over all the leaf layer parameters.
+    pub leaf: String,
+    pub node: String,
+    pub recursion_tip: String,
+    /// Scheduler key commitment, computed the same way as the node one.
+    pub scheduler: String,
+    /// Keccak256 of the SNARK wrapper key, as checked by the L1 verifier. `None` only if
+    /// the SNARK wrapper was explicitly skipped.
+    pub snark_wrapper: Option<String>,
+}
+
+pub fn run_commitments(
+    keys_dir: &Path,
+    output: Option<&Path>,
+    skip_snark_wrapper: bool,
+) -> Result<()> {
+    info!("Computing commitments");
+    info!("keys={}", keys_dir.display());
+
+    let manifest = compute_commitments(keys_dir, skip_snark_wrapper)?;
+    let payload = serde_json::to_string_pretty(&manifest)
+        .context("while attempting to serialize commitments manifest")?;
+
+    match output {
+        Some(path) => std::fs::write(path, payload).with_context(|| {
+            format!(
+                "while attempting to write commitments manifest {}",
+                path.display()
+            )
+        })?,
+        None => println!("{payload}"),
+    }
+
+    Ok(())
+}
+
+pub fn compute_commitments(
+    keys_dir: &Path,
+    skip_snark_wrapper: bool,
+) -> Result<CommitmentsManifest> {
+    let mut leaf_params = vec![];
+    let mut leaf_layer_parameters = vec![];
+    for basic_circuit_type in BaseLayerCircuitType::as_iter_u8() {
+        let leaf_circuit_type = base_circuit_type_into_recursive_leaf_circuit_type(
+            BaseLayerCircuitType::from_numeric_value(basic_circuit_type),
+        ) as u8;
+
+        let base_vk: ZkSyncBaseLayerVerificationKey = read_json_as(&keys_dir.join(format!(
+            "verification_basic_{}_key.json",
+            basic_circuit_type
+        )))
+        .with_context(|| {
+            format!("while attempting to load base verification key {basic_circuit_type}")
+        })?;
+        let leaf_vk: ZkSyncRecursionLayerVerificationKey = read_json_as(
+            &keys_dir.join(format!("verification_leaf_{}_key.json", leaf_circuit_type)),
+        )
+        .with_context(|| {
+            format!("while attempting to load leaf verification key {leaf_circuit_type}")
+        })?;
+        ensure!(
+            base_vk.numeric_circuit_type() == basic_circuit_type
+                && leaf_vk.numeric_circuit_type() == leaf_circuit_type,
+            "verification keys for base circuit {basic_circuit_type} have unexpected circuit types"
+        );
+
+        let params = compute_leaf_params(basic_circuit_type, base_vk, leaf_vk);
+        leaf_layer_parameters.push(LeafLayerParameters {
+            circuit_type: basic_circuit_type,
+            basic_circuit_vk_commitment: field_elements_to_hex(&params.basic_circuit_vk_commitment),
+            leaf_layer_vk_commitment: field_elements_to_hex(&params.leaf_layer_vk_commitment),
+        });
+        leaf_params.push(params);
+    }
+
+    let num_leaf_params = leaf_params.len();
+    let leaf = compute_leaf_vks_and_params_commitment(
+        leaf_params
+            .try_into()
+            .map_err(|_| anyhow!("unexpected number of base circuit types: {num_leaf_params}"))?,
+    );
+
+    let node_vk: ZkSyncRecursionLayerVerificationKey =
+        read_json_as(&keys_dir.join("verification_node_key.json"))
+            .context("while attempting to load node verification key")?;
+    let node = compute_node_vk_commitment(node_vk);
+
+    // Recursion tip key is committed to the same way as the node one.
+    let recursion_tip_vk: ZkSyncRecursionLayerVerificationKey =
+        read_json_as(&keys_dir.join("verification_recursion_tip_key.json"))
+            .context("while attempting to load recursion tip verification key")?;
+    let recursion_tip = compute_node_vk_commitment(recursion_tip_vk);
+
+    // Scheduler key is stored unwrapped.
+    let scheduler_vk: ZkSyncRecursionVerificationKey =
+        read_json_as(&keys_dir.join("verification_scheduler_key.json"))
+            .context("while attempting to load scheduler verification key")?;
+    let scheduler = compute_node_vk_commitment(
+        ZkSyncRecursionLayerVerificationKey::SchedulerCircuit(scheduler_vk),
+    );
+
+    let snark_wrapper = if skip_snark_wrapper {
+        info!("Skipping SNARK wrapper hash");
+        None
+    } else {
+        let snark_wrapper_path = keys_dir.join(SNARK_WRAPPER_VK_FILE_NAME);
+        ensure!(
+            snark_wrapper_path.exists(),
+            "SNARK wrapper verification key {} is missing, use --skip-snark-wrapper to compute the other commitments",
+            snark_wrapper_path.display()
+        );
+        let vk: SnarkVK<Bn256, ZkSyncSnarkWrapperCircuit> = read_json_as(&snark_wrapper_path)
+            .context("while attempting to load SNARK wrapper verification key")?;
+        Some(format!("0x{}", hex_encode(&snark_vk_hash(&vk)?)))
+    };
+
+    Ok(CommitmentsManifest {
+        leaf_layer_parameters,
+        leaf: field_elements_to_hex(&leaf),
+        node: field_elements_to_hex(&node),
+        recursion_tip: field_elements_to_hex(&recursion_tip),
+        scheduler: field_elements_to_hex(&scheduler),
+        snark_wrapper,
+    })
+}
+
+/// Big-endian concatenation of the reduced field elements, the way zksync-era encodes them.
+fn field_elements_to_hex(elements: &[GoldilocksField]) -> String {
+    let bytes: Vec<u8> = elements
+        .iter()
+        .flat_map(|el| el.as_u64_reduced().to_be_bytes())
+        .collect();
+    format!("0x{}", hex_encode(&bytes))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Same hash as the one hardcoded into the L1 verifier.
+fn snark_vk_hash(vk: &SnarkVK<Bn256, ZkSyncSnarkWrapperCircuit>) -> Result<[u8; 32]> {
+    fn write_point<G: CurveAffine<Base = Fq>>(point: &G, out: &mut Vec<u8>) -> Result<()> {
+        let (x, y) = point.as_xy();
+        x.into_repr().write_be(&mut *out)?;
+        y.into_repr().write_be(&mut *out)?;
+        Ok(())
+    }
+
+    ensure!(
+        vk.gate_setup_commitments.len() == 8
+            && vk.gate_selectors_commitments.len() == 2
+            && vk.permutation_commitments.len() == 4
+            && vk.lookup_tables_commitments.len() == 4,
+        "unexpected SNARK wrapper verification key shape"
+    );
+    let lookup_selector = vk
+        .lookup_selector_commitment
+        .context("SNARK wrapper verification key has no lookup selector commitment")?;
+    let lookup_table_type = vk
+        .lookup_table_type_commitment
+        .context("SNARK wrapper verification key has no lookup table type commitment")?;
+
+    let mut payload = vec![];
+    for point in vk
+        .gate_setup_commitments
+        .iter()
+        .chain(vk.gate_selectors_commitments.iter())
+        .chain(vk.permutation_commitments.iter())
+        .chain(std::iter::once(&lookup_selector))
+        .chain(vk.lookup_tables_commitments.iter())
+        .chain(std::iter::once(&lookup_table_type))
+    {
+        write_point(point, &mut payload)?;
+    }
+    // flag for the recursive part, always unused
+    Fq::zero().into_repr().write_be(&mut payload)?;
+
+    Ok(Keccak256::digest(&payload).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use zkevm_test_harness::franklin_crypto::bellman::pairing::bn256::{Fr, G1Affine};
+    use zkevm_test_harness::franklin_crypto::bellman::pairing::CurveProjective;
+
+    use super::*;
+
+    fn reference_dir() -> &'static Path {
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/reference"))
+    }
+
+    #[test]
+    fn reference_commitments() {
+        let manifest = compute_commitments(reference_dir(), true).unwrap();
+
+        assert_eq!(
+            manifest.leaf_layer_parameters.len(),
+            BaseLayerCircuitType::as_iter_u8().count()
+        );
+        assert_eq!(
+            manifest.leaf,
+            "0xfa89b8cf2ea163502c74b570348414307e220e876f470aa8b363c636629468c9"
+        );
+        assert_eq!(
+            manifest.node,
+            "0x96803f6a9b4a92f38284b59c2feb1ce1c1f6335e65ef1f48ee57e14b30adce9d"
+        );
+        assert_eq!(
+            manifest.recursion_tip,
+            "0x10d1e4dccb838af8f6e2c42882e2d11c8ef4b627671c62b6a796c2d037e2eea2"
+        );
+        assert_eq!(
+            manifest.scheduler,
+            "0xd2c2b3c78206f079c4ba01a18d63e348442461b6a66443c03040a030cd12030c"
+        );
+        assert_eq!(manifest.snark_wrapper, None);
+    }
+
+    #[test]
+    fn missing_snark_wrapper_key_is_reported() {
+        let err = compute_commitments(reference_dir(), false).unwrap_err();
+        assert!(
+            err.to_string().contains(SNARK_WRAPPER_VK_FILE_NAME),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn snark_vk_hash_of_known_key() {
+        let point = |scalar: u64| {
+            G1Affine::one()
+                .mul(Fr::from_str(&scalar.to_string()).unwrap())
+                .into_affine()
+        };
+        let mut vk = SnarkVK::<Bn256, ZkSyncSnarkWrapperCircuit>::empty();
+        vk.gate_setup_commitments = (1..=8).map(point).collect();
+        vk.gate_selectors_commitments = (9..=10).map(point).collect();
+        vk.permutation_commitments = (11..=14).map(point).collect();
+        vk.lookup_selector_commitment = Some(point(15));
+        vk.lookup_tables_commitments = (16..=19).map(point).collect();
+        vk.lookup_table_type_commitment = Some(point(20));
+
+        // keccak256 over the coordinates of 1*G..20*G and a zero word, computed independently
+        assert_eq!(
+            hex_encode(&snark_vk_hash(&vk).unwrap()),
+            "91e14e99b13e22642c4d6131f2a625b7517f4d913df4926655a43f835f7b55a8"
+        );
+
+        vk.lookup_selector_commitment = None;
+        assert!(snark_vk_hash(&vk).is_err());
+        vk.lookup_selector_commitment = Some(point(15));
+        vk.gate_setup_commitments.pop();
+        assert!(snark_vk_hash(&vk).is_err());
+    }
+}
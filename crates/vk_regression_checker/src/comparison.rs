@@ -1,20 +1,29 @@
-use std::fs;
 use std::path::Path;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use serde::Serialize;
 use tracing::{error, info};
 
-use crate::artifacts::{planned_key_artifacts, FileKind};
-use crate::file_io::read_json;
+use crate::artifacts::{planned_key_artifacts, CircuitFilter};
+use crate::cli::{OutputFormat, ReportArgs};
+use crate::diff::{diff_artifact, format_field_diff, ArtifactDiff, ArtifactStatus};
 use crate::generation::{generate_data_source, validate_jobs, write_era_compatible_layout};
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize)]
 pub struct ComparisonSummary {
     pub checked: usize,
     pub different: usize,
+    /// Only the artifacts that differ.
+    pub artifacts: Vec<ArtifactDiff>,
 }
 
-pub fn run_compare(reference_keys_dir: &Path, generated_dir: &Path, jobs: usize) -> Result<()> {
+pub fn run_compare(
+    reference_keys_dir: &Path,
+    generated_dir: &Path,
+    jobs: usize,
+    cache_dir: Option<&Path>,
+    report: &ReportArgs,
+) -> Result<()> {
     validate_jobs(jobs)?;
 
     info!("Generating keys for comparison");
@@ -22,10 +31,27 @@ pub fn run_compare(reference_keys_dir: &Path, generated_dir: &Path, jobs: usize)
     info!("reference={}", reference_keys_dir.display());
     info!("generated={}", generated_dir.display());
 
-    let source = generate_data_source(jobs)?;
+    let source = generate_data_source(jobs, cache_dir)?;
     write_era_compatible_layout(&source, generated_dir)?;
 
-    let summary = compare_key_folders(reference_keys_dir, generated_dir)?;
+    run_diff(reference_keys_dir, generated_dir, report)
+}
+
+pub fn run_diff(
+    reference_keys_dir: &Path,
+    generated_dir: &Path,
+    report: &ReportArgs,
+) -> Result<()> {
+    let summary = compare_key_folders(reference_keys_dir, generated_dir, &report.only)?;
+
+    match report.format {
+        OutputFormat::Text => log_summary(&summary),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&summary)
+                .context("while attempting to serialize comparison report")?
+        ),
+    }
 
     if summary.different > 0 {
         bail!(
@@ -39,70 +65,82 @@ pub fn run_compare(reference_keys_dir: &Path, generated_dir: &Path, jobs: usize)
     Ok(())
 }
 
+/// Compares all planned artifacts matching `only` (all of them if it's empty). Fails if `only`
+/// doesn't match any artifact, so a mistyped filter can't pass as a successful check.
 pub fn compare_key_folders(
     reference_keys_dir: &Path,
     generated_dir: &Path,
+    only: &[CircuitFilter],
 ) -> Result<ComparisonSummary> {
     let mut summary = ComparisonSummary::default();
 
     for artifact in planned_key_artifacts() {
-        summary.checked += 1;
-
-        let reference_path = reference_keys_dir.join(&artifact.file_name);
-        let generated_path = generated_dir.join(&artifact.file_name);
-
-        if !reference_path.exists() {
-            summary.different += 1;
-            error!("Key <{}> is different", artifact.file_name);
-            error!("missing reference file {}", reference_path.display());
+        if !only.is_empty() && !only.iter().any(|filter| filter.matches(&artifact)) {
             continue;
         }
+        summary.checked += 1;
 
-        if !generated_path.exists() {
+        let diff = diff_artifact(
+            &artifact,
+            &reference_keys_dir.join(&artifact.file_name),
+            &generated_dir.join(&artifact.file_name),
+        )?;
+        if !diff.is_same() {
             summary.different += 1;
-            error!("Key <{}> is different", artifact.file_name);
-            error!("missing generated file {}", generated_path.display());
-            continue;
+            summary.artifacts.push(diff);
         }
+    }
+    ensure!(
+        summary.checked > 0,
+        "--only filters don't match any key files"
+    );
 
-        let is_same = match artifact.kind {
-            FileKind::Json => {
-                let reference = read_json(&reference_path).with_context(|| {
-                    format!(
-                        "while attempting to read reference JSON key {}",
-                        reference_path.display()
-                    )
-                })?;
-                let generated = read_json(&generated_path).with_context(|| {
-                    format!(
-                        "while attempting to read generated JSON key {}",
-                        generated_path.display()
-                    )
-                })?;
-                reference == generated
-            }
-            FileKind::Binary => {
-                let reference = fs::read(&reference_path).with_context(|| {
-                    format!(
-                        "while attempting to read reference binary key {}",
-                        reference_path.display()
-                    )
-                })?;
-                let generated = fs::read(&generated_path).with_context(|| {
-                    format!(
-                        "while attempting to read generated binary key {}",
-                        generated_path.display()
-                    )
-                })?;
-                reference == generated
+    Ok(summary)
+}
+
+fn log_summary(summary: &ComparisonSummary) {
+    for diff in summary.artifacts.iter() {
+        error!("Key <{}> is different", diff.file_name);
+        match &diff.status {
+            ArtifactStatus::Same => {}
+            ArtifactStatus::MissingReference => error!("  missing reference file"),
+            ArtifactStatus::MissingGenerated => error!("  missing generated file"),
+            ArtifactStatus::Different { fields } => {
+                for field in fields {
+                    error!("  {}", format_field_diff(field));
+                }
             }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_matching_nothing_fails() {
+        let reference_dir = tempfile::tempdir().unwrap();
+        let generated_dir = tempfile::tempdir().unwrap();
+        let compare = |only: &str| {
+            compare_key_folders(
+                reference_dir.path(),
+                generated_dir.path(),
+                &[only.parse().unwrap()],
+            )
         };
 
-        if !is_same {
-            summary.different += 1;
-            error!("Key <{}> is different", artifact.file_name);
+        let summary = compare("node").unwrap();
+        assert_eq!(summary.checked, 2);
+        assert_eq!(summary.different, 2);
+
+        // leaf circuit types skip 2, and there's no basic circuit 200
+        for only in ["leaf_2", "basic_200"] {
+            let err = compare(only).unwrap_err();
+            assert!(
+                err.to_string().contains("don't match any key files"),
+                "{err}"
+            );
         }
     }
-
-    Ok(summary)
 }
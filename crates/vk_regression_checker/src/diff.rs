@@ -0,0 +1,372 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use circuit_definitions::boojum::cs::implementations::setup::FinalizationHintsForProver;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::artifacts::{FileKind, KeyArtifact};
+use crate::file_io::read_json;
+
+const MAX_TEXT_VALUE_LEN: usize = 120;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDiff {
+    /// Dotted path of the field, e.g. `MainVM.fixed_parameters.domain_size`.
+    pub path: String,
+    pub category: &'static str,
+    pub reference: Value,
+    pub generated: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    Same,
+    MissingReference,
+    MissingGenerated,
+    Different { fields: Vec<FieldDiff> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactDiff {
+    pub file_name: String,
+    pub circuit: String,
+    #[serde(flatten)]
+    pub status: ArtifactStatus,
+}
+
+impl ArtifactDiff {
+    pub fn is_same(&self) -> bool {
+        matches!(self.status, ArtifactStatus::Same)
+    }
+}
+
+pub fn diff_artifact(
+    artifact: &KeyArtifact,
+    reference_path: &Path,
+    generated_path: &Path,
+) -> Result<ArtifactDiff> {
+    let status = if !reference_path.exists() {
+        ArtifactStatus::MissingReference
+    } else if !generated_path.exists() {
+        ArtifactStatus::MissingGenerated
+    } else {
+        let reference = read_artifact(artifact.kind, reference_path).with_context(|| {
+            format!(
+                "while attempting to read reference key {}",
+                reference_path.display()
+            )
+        })?;
+        let generated = read_artifact(artifact.kind, generated_path).with_context(|| {
+            format!(
+                "while attempting to read generated key {}",
+                generated_path.display()
+            )
+        })?;
+
+        let mut fields = vec![];
+        diff_values("", &reference, &generated, &mut fields);
+        if fields.is_empty() {
+            ArtifactStatus::Same
+        } else {
+            ArtifactStatus::Different { fields }
+        }
+    };
+
+    Ok(ArtifactDiff {
+        file_name: artifact.file_name.clone(),
+        circuit: artifact.circuit_name(),
+        status,
+    })
+}
+
+/// Reads an artifact as JSON. Binary finalization hints are decoded, so they can be diffed
+/// field by field; if decoding fails, the raw bytes are compared instead.
+fn read_artifact(kind: FileKind, path: &Path) -> Result<Value> {
+    match kind {
+        FileKind::Json => read_json(path),
+        FileKind::Binary => {
+            let payload = fs::read(path).with_context(|| {
+                format!("while attempting to read binary file {}", path.display())
+            })?;
+            match bincode::deserialize::<FinalizationHintsForProver>(&payload) {
+                Ok(hints) => serde_json::to_value(hints)
+                    .context("while attempting to convert finalization hints to JSON"),
+                Err(_) => Ok(Value::from(payload)),
+            }
+        }
+    }
+}
+
+fn diff_values(path: &str, reference: &Value, generated: &Value, out: &mut Vec<FieldDiff>) {
+    if reference == generated {
+        return;
+    }
+
+    match (reference, generated) {
+        (Value::Object(reference), Value::Object(generated)) => {
+            let keys: BTreeSet<_> = reference.keys().chain(generated.keys()).collect();
+            for key in keys {
+                diff_values(
+                    &join_path(path, key),
+                    reference.get(key).unwrap_or(&Value::Null),
+                    generated.get(key).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        // Arrays of scalars (e.g. Merkle caps) are reported as a whole.
+        (Value::Array(reference), Value::Array(generated))
+            if reference.len() == generated.len()
+                && reference.iter().any(|el| el.is_object() || el.is_array()) =>
+        {
+            for (idx, (reference, generated)) in reference.iter().zip(generated).enumerate() {
+                diff_values(&format!("{path}[{idx}]"), reference, generated, out);
+            }
+        }
+        _ => out.push(FieldDiff {
+            path: path.to_owned(),
+            category: categorize(path),
+            reference: reference.clone(),
+            generated: generated.clone(),
+        }),
+    }
+}
+
+fn join_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn categorize(path: &str) -> &'static str {
+    const CATEGORIES: [(&str, &str); 12] = [
+        ("setup_merkle_tree_cap", "setup Merkle cap"),
+        ("domain_size", "domain size"),
+        ("lookup_parameters", "lookup parameters"),
+        ("total_tables_len", "lookup parameters"),
+        ("table_ids_column_idxes", "lookup parameters"),
+        ("selectors_placement", "gate set"),
+        ("fixed_parameters.parameters", "columns and constants"),
+        (
+            "extra_constant_polys_for_selectors",
+            "columns and constants",
+        ),
+        ("public_inputs", "public inputs"),
+        ("row_finalization_hints", "row count"),
+        ("column_finalization_hints", "column count"),
+        ("final_trace_len", "row count"),
+    ];
+
+    CATEGORIES
+        .iter()
+        .find(|(pattern, _)| path.contains(pattern))
+        .map(|(_, category)| *category)
+        .unwrap_or(if path.contains("fixed_parameters") {
+            "fixed parameters"
+        } else {
+            "other"
+        })
+}
+
+/// Human-readable description of a single field change.
+pub fn format_field_diff(diff: &FieldDiff) -> String {
+    format!(
+        "[{}] {}: {} -> {}",
+        diff.category,
+        if diff.path.is_empty() {
+            "<root>"
+        } else {
+            &diff.path
+        },
+        format_value(&diff.reference),
+        format_value(&diff.generated)
+    )
+}
+
+fn format_value(value: &Value) -> String {
+    let formatted = value.to_string();
+    if formatted.len() <= MAX_TEXT_VALUE_LEN {
+        return formatted;
+    }
+
+    let cut = (0..=MAX_TEXT_VALUE_LEN)
+        .rev()
+        .find(|idx| formatted.is_char_boundary(*idx))
+        .unwrap_or(0);
+    format!("{}... ({} bytes)", &formatted[..cut], formatted.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::artifacts::planned_key_artifacts;
+
+    fn reference_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("reference")
+    }
+
+    fn reference_key(file_name: &str) -> Value {
+        read_json(&reference_dir().join(file_name)).unwrap()
+    }
+
+    #[test]
+    fn reference_keys_match_themselves() {
+        for artifact in planned_key_artifacts() {
+            let path = reference_dir().join(&artifact.file_name);
+            let diff = diff_artifact(&artifact, &path, &path).unwrap();
+            assert!(diff.is_same(), "{} differs from itself", artifact.file_name);
+        }
+    }
+
+    #[test]
+    fn missing_keys_are_reported() {
+        let artifact = &planned_key_artifacts()[0];
+        let existing = reference_dir().join(&artifact.file_name);
+        let missing = reference_dir().join("non_existent_key.json");
+
+        let diff = diff_artifact(artifact, &missing, &existing).unwrap();
+        assert!(matches!(diff.status, ArtifactStatus::MissingReference));
+        let diff = diff_artifact(artifact, &existing, &missing).unwrap();
+        assert!(matches!(diff.status, ArtifactStatus::MissingGenerated));
+    }
+
+    #[test]
+    fn finalization_hints_are_decoded() {
+        let path = reference_dir().join("finalization_hints_basic_1.bin");
+        let hints = read_artifact(FileKind::Binary, &path).unwrap();
+        assert!(hints["final_trace_len"].is_u64());
+        assert!(hints["row_finalization_hints"].is_array());
+    }
+
+    #[test]
+    fn mutated_key_diff() {
+        let reference = reference_key("verification_basic_1_key.json");
+        let mut generated = reference.clone();
+        let fixed_parameters = &mut generated["MainVM"]["fixed_parameters"];
+        fixed_parameters["domain_size"] = json!(1 << 21);
+        fixed_parameters["parameters"]["num_constant_columns"] = json!(5);
+        fixed_parameters["new_parameter"] = json!(true);
+        generated["MainVM"]["setup_merkle_tree_cap"][3][1] = json!(42);
+
+        let mut fields = vec![];
+        diff_values("", &reference, &generated, &mut fields);
+        let fields: Vec<_> = fields
+            .iter()
+            .map(|diff| (diff.path.as_str(), diff.category))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("MainVM.fixed_parameters.domain_size", "domain size"),
+                ("MainVM.fixed_parameters.new_parameter", "fixed parameters"),
+                (
+                    "MainVM.fixed_parameters.parameters.num_constant_columns",
+                    "columns and constants"
+                ),
+                ("MainVM.setup_merkle_tree_cap[3]", "setup Merkle cap"),
+            ]
+        );
+    }
+
+    #[test]
+    fn different_keys_diff() {
+        let basic = reference_key("verification_basic_1_key.json");
+        let leaf = reference_key("verification_leaf_3_key.json");
+
+        let mut fields = vec![];
+        diff_values("", &basic, &leaf, &mut fields);
+        assert!(!fields.is_empty());
+        assert!(fields.iter().all(|diff| diff.reference != diff.generated));
+        // leaf keys are stored under a different variant, so nothing is shared
+        assert!(fields.iter().any(|diff| diff.generated.is_null()));
+        assert!(fields.iter().any(|diff| diff.reference.is_null()));
+    }
+
+    #[test]
+    fn scalar_arrays_are_reported_as_a_whole() {
+        let mut fields = vec![];
+        diff_values(
+            "table_ids_column_idxes",
+            &json!([1, 2, 3]),
+            &json!([1, 2, 4]),
+            &mut fields,
+        );
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].path, "table_ids_column_idxes");
+        assert_eq!(fields[0].category, "lookup parameters");
+
+        let mut fields = vec![];
+        diff_values(
+            "caps",
+            &json!([[1], [2]]),
+            &json!([[1], [2], [3]]),
+            &mut fields,
+        );
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].path, "caps");
+    }
+
+    #[test]
+    fn categories() {
+        for (path, category) in [
+            ("MainVM.setup_merkle_tree_cap", "setup Merkle cap"),
+            ("MainVM.fixed_parameters.domain_size", "domain size"),
+            (
+                "MainVM.fixed_parameters.total_tables_len",
+                "lookup parameters",
+            ),
+            (
+                "MainVM.fixed_parameters.selectors_placement.Fork.left",
+                "gate set",
+            ),
+            (
+                "MainVM.fixed_parameters.extra_constant_polys_for_selectors",
+                "columns and constants",
+            ),
+            ("public_inputs[0]", "public inputs"),
+            ("final_trace_len", "row count"),
+            ("column_finalization_hints", "column count"),
+            ("MainVM.fixed_parameters.cap_size", "fixed parameters"),
+            ("nop_gates_to_add", "other"),
+            ("", "other"),
+        ] {
+            assert_eq!(categorize(path), category, "{path}");
+        }
+    }
+
+    #[test]
+    fn long_values_are_shortened() {
+        assert_eq!(format_value(&json!(42)), "42");
+        assert_eq!(format_value(&json!([1, 2])), "[1,2]");
+
+        let long = json!("a".repeat(200));
+        assert_eq!(
+            format_value(&long),
+            format!("\"{}... (202 bytes)", "a".repeat(MAX_TEXT_VALUE_LEN - 1))
+        );
+
+        // the cut must not split a multibyte character
+        let multibyte = json!("ä".repeat(100));
+        let formatted = format_value(&multibyte);
+        assert!(formatted.ends_with("... (202 bytes)"));
+        assert!(formatted.len() <= MAX_TEXT_VALUE_LEN + "... (202 bytes)".len());
+
+        let diff = FieldDiff {
+            path: String::new(),
+            category: "other",
+            reference: json!(1),
+            generated: json!(2),
+        };
+        assert_eq!(format_field_diff(&diff), "[other] <root>: 1 -> 2");
+    }
+}
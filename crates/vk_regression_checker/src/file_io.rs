@@ -28,3 +28,11 @@ pub fn read_json(path: &Path) -> Result<serde_json::Value> {
     serde_json::from_slice(&payload)
         .with_context(|| format!("while attempting to parse JSON file {}", path.display()))
 }
+
+pub fn read_json_as<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T> {
+    let payload = fs::read(path)
+        .with_context(|| format!("while attempting to read JSON file {}", path.display()))?;
+
+    serde_json::from_slice(&payload)
+        .with_context(|| format!("while attempting to parse JSON file {}", path.display()))
+}
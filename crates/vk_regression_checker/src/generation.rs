@@ -1,36 +1,49 @@
 use std::fs;
 use std::path::Path;
 
-use anyhow::{anyhow, ensure, Context, Result};
+use anyhow::{ensure, Context, Result};
 use circuit_definitions::circuit_definitions::aux_layer::{
     ZkSyncCompressionForWrapperCircuit, ZkSyncCompressionForWrapperFinalizationHint,
     ZkSyncCompressionForWrapperVerificationKey, ZkSyncCompressionLayerCircuit,
     ZkSyncCompressionLayerFinalizationHint, ZkSyncCompressionLayerStorage,
     ZkSyncCompressionLayerVerificationKey,
 };
-use circuit_definitions::circuit_definitions::recursion_layer::ZkSyncRecursionLayerStorageType;
+use circuit_definitions::circuit_definitions::base_layer::{
+    ZkSyncBaseLayerFinalizationHint, ZkSyncBaseLayerVerificationKey,
+};
+use circuit_definitions::circuit_definitions::recursion_layer::{
+    ZkSyncRecursionLayerFinalizationHint, ZkSyncRecursionLayerStorageType,
+    ZkSyncRecursionLayerVerificationKey, ZkSyncRecursiveLayerCircuit,
+};
 use circuit_definitions::zkevm_circuits::scheduler::aux::BaseLayerCircuitType;
+use circuit_definitions::{
+    BASE_LAYER_CAP_SIZE, BASE_LAYER_FRI_LDE_FACTOR, RECURSION_LAYER_CAP_SIZE,
+    RECURSION_LAYER_FRI_LDE_FACTOR,
+};
 use tracing::info;
 use zkevm_test_harness::boojum::worker::Worker;
-use zkevm_test_harness::compute_setups::{generate_base_layer_vks, generate_recursive_layer_vks};
+use zkevm_test_harness::compute_setups::{
+    get_all_basic_circuits, get_leaf_circuits, get_node_circuit, get_recursion_tip_circuit,
+    get_scheduler_circuit,
+};
 use zkevm_test_harness::data_source::{
     in_memory_data_source::InMemoryDataSource, SetupDataSource, SourceResult,
 };
-use zkevm_test_harness::prover_utils::{
-    create_compression_for_wrapper_setup_data, create_compression_layer_setup_data,
-};
+use zkevm_test_harness::geometry_config::ProtocolGeometry;
+use zkevm_test_harness::prover_utils::SynthesizedSetup;
 
 use crate::artifacts::{COMPRESSION_CIRCUIT_TYPES, COMPRESSION_FOR_WRAPPER_CIRCUIT_TYPES};
+use crate::cache::{generate_with_cache, Fingerprint, GenerationReport, KeyCache, PlannedKey};
 use crate::file_io::{write_bin, write_json};
 
-pub fn run_generate(keys_dir: &Path, jobs: usize) -> Result<()> {
+pub fn run_generate(keys_dir: &Path, jobs: usize, cache_dir: Option<&Path>) -> Result<()> {
     validate_jobs(jobs)?;
 
     info!("Generating keys");
     info!("jobs={jobs}");
     info!("output={}", keys_dir.display());
 
-    let source = generate_data_source(jobs)?;
+    let source = generate_data_source(jobs, cache_dir)?;
     write_era_compatible_layout(&source, keys_dir)?;
 
     info!("Done");
@@ -42,23 +55,211 @@ pub fn validate_jobs(jobs: usize) -> Result<()> {
     Ok(())
 }
 
-pub fn generate_data_source(jobs: usize) -> Result<InMemoryDataSource> {
+/// Generates all the keys. With `cache_dir`, keys of circuits whose fingerprint didn't change
+/// are taken from the cache instead.
+pub fn generate_data_source(jobs: usize, cache_dir: Option<&Path>) -> Result<InMemoryDataSource> {
+    let cache = cache_dir.map(KeyCache::open).transpose()?;
+    if let Some(cache_dir) = cache_dir {
+        info!("cache={}", cache_dir.display());
+    }
+    let cache = cache.as_ref();
+    let mut report = GenerationReport::default();
     let mut source = InMemoryDataSource::new();
 
-    generate_base_layer_vks(&mut source, Some(jobs), || {})
-        .map_err(|err| anyhow!("{err}"))
+    generate_base_layer_artifacts(&mut source, cache, &mut report, jobs)
         .context("while attempting to generate base layer verification keys")?;
-
-    generate_recursive_layer_vks(&mut source, Some(jobs), || {})
-        .map_err(|err| anyhow!("{err}"))
+    generate_recursive_layer_artifacts(&mut source, cache, &mut report, jobs)
         .context("while attempting to generate recursive layer verification keys")?;
+    generate_compression_layer_artifacts(&mut source, cache, &mut report)?;
+    generate_compression_for_wrapper_artifacts(&mut source, cache, &mut report)?;
 
-    generate_compression_layer_artifacts(&mut source)?;
-    generate_compression_for_wrapper_artifacts(&mut source)?;
-
+    report.log();
     Ok(source)
 }
 
+fn generate_base_layer_artifacts(
+    source: &mut InMemoryDataSource,
+    cache: Option<&KeyCache>,
+    report: &mut GenerationReport,
+    jobs: usize,
+) -> Result<()> {
+    let geometry = ProtocolGeometry::latest().config();
+    let worker = Worker::new();
+
+    let planned = get_all_basic_circuits(&geometry)
+        .into_iter()
+        .map(|circuit| {
+            let circuit_type = circuit.numeric_circuit_type();
+            let fingerprint = Fingerprint::new("basic", circuit_type, circuit.short_description())
+                .with_structure(circuit.geometry(), circuit.size_hint())?
+                .with_proof_config(BASE_LAYER_FRI_LDE_FACTOR, BASE_LAYER_CAP_SIZE)?
+                .with_json(&geometry)?;
+            Ok(PlannedKey {
+                name: format!("basic {circuit_type}"),
+                fingerprint,
+                circuit,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let generated = generate_with_cache(
+        cache,
+        report,
+        jobs,
+        planned,
+        |circuit| {
+            let circuit_type = circuit.numeric_circuit_type();
+            (circuit_type, SynthesizedSetup::for_base_layer(circuit))
+        },
+        |(_, synthesized)| synthesized.setup_base(&worker),
+        |(circuit_type, synthesized)| {
+            let (vk, hint) = synthesized.into_vk_and_finalization_hint(
+                &worker,
+                BASE_LAYER_FRI_LDE_FACTOR,
+                BASE_LAYER_CAP_SIZE,
+            );
+            (
+                ZkSyncBaseLayerVerificationKey::from_inner(circuit_type, vk),
+                ZkSyncBaseLayerFinalizationHint::from_inner(circuit_type, hint),
+            )
+        },
+    )?;
+    for (vk, hint) in generated {
+        source.set_base_layer_finalization_hint(hint)?;
+        source.set_base_layer_vk(vk)?;
+    }
+
+    Ok(())
+}
+
+fn plan_recursive_circuit(
+    circuit: ZkSyncRecursiveLayerCircuit,
+    name: String,
+    dependencies: &impl serde::Serialize,
+) -> Result<PlannedKey<ZkSyncRecursiveLayerCircuit>> {
+    let fingerprint = Fingerprint::new(
+        "recursive",
+        circuit.numeric_circuit_type(),
+        circuit.short_description(),
+    )
+    .with_structure(circuit.geometry(), circuit.size_hint())?
+    .with_proof_config(RECURSION_LAYER_FRI_LDE_FACTOR, RECURSION_LAYER_CAP_SIZE)?
+    .with_json(dependencies)?;
+
+    Ok(PlannedKey {
+        name,
+        fingerprint,
+        circuit,
+    })
+}
+
+/// Recursive circuits are built from the keys of the circuits they verify, so these keys are
+/// part of their fingerprints.
+fn generate_recursive_layer_artifacts(
+    source: &mut InMemoryDataSource,
+    cache: Option<&KeyCache>,
+    report: &mut GenerationReport,
+    jobs: usize,
+) -> Result<()> {
+    let worker = Worker::new();
+    let synthesize = |circuit: ZkSyncRecursiveLayerCircuit| {
+        let circuit_type = circuit.numeric_circuit_type();
+        (circuit_type, SynthesizedSetup::for_recursive_layer(circuit))
+    };
+    let setup_base = |(_, synthesized): &(u8, SynthesizedSetup)| synthesized.setup_base(&worker);
+    let generate = |(circuit_type, synthesized): (u8, SynthesizedSetup)| {
+        let (vk, hint) = synthesized.into_vk_and_finalization_hint(
+            &worker,
+            RECURSION_LAYER_FRI_LDE_FACTOR,
+            RECURSION_LAYER_CAP_SIZE,
+        );
+        (
+            ZkSyncRecursionLayerVerificationKey::from_inner(circuit_type, vk),
+            ZkSyncRecursionLayerFinalizationHint::from_inner(circuit_type, hint),
+        )
+    };
+
+    let leaf_circuits = get_leaf_circuits(source)?;
+    let planned = BaseLayerCircuitType::as_iter_u8()
+        .zip(leaf_circuits)
+        .map(|(base_circuit_type, circuit)| {
+            let base_vk = from_source(source.get_base_layer_vk(base_circuit_type), || {
+                format!("while attempting to load base verification key {base_circuit_type}")
+            })?;
+            let name = format!("leaf {}", circuit.numeric_circuit_type());
+            plan_recursive_circuit(circuit, name, &base_vk)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (vk, hint) in generate_with_cache(
+        cache, report, jobs, planned, synthesize, setup_base, generate,
+    )? {
+        source.set_recursion_layer_finalization_hint(hint)?;
+        source.set_recursion_layer_vk(vk)?;
+    }
+
+    let leaf_vks = ZkSyncRecursionLayerStorageType::leafs_as_iter_u8()
+        .map(|circuit_type| {
+            from_source(source.get_recursion_layer_vk(circuit_type), || {
+                format!("while attempting to load leaf verification key {circuit_type}")
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let node_circuit = get_node_circuit(source)?;
+    let planned = vec![plan_recursive_circuit(
+        node_circuit,
+        "node".to_owned(),
+        &leaf_vks[0],
+    )?];
+    for (vk, hint) in generate_with_cache(
+        cache, report, jobs, planned, synthesize, setup_base, generate,
+    )? {
+        source.set_recursion_layer_node_finalization_hint(hint)?;
+        source.set_recursion_layer_node_vk(vk)?;
+    }
+
+    let node_vk = from_source(source.get_recursion_layer_node_vk(), || {
+        "while attempting to load node verification key".to_owned()
+    })?;
+    let recursion_tip_circuit = get_recursion_tip_circuit(source)?;
+    let planned = vec![plan_recursive_circuit(
+        recursion_tip_circuit,
+        "recursion tip".to_owned(),
+        &node_vk,
+    )?];
+    for (vk, hint) in generate_with_cache(
+        cache, report, jobs, planned, synthesize, setup_base, generate,
+    )? {
+        source.set_recursion_tip_finalization_hint(hint)?;
+        source.set_recursion_tip_vk(vk)?;
+    }
+
+    let base_vks = BaseLayerCircuitType::as_iter_u8()
+        .map(|circuit_type| {
+            from_source(source.get_base_layer_vk(circuit_type), || {
+                format!("while attempting to load base verification key {circuit_type}")
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let recursion_tip_vk = from_source(source.get_recursion_tip_vk(), || {
+        "while attempting to load recursion tip verification key".to_owned()
+    })?;
+    let scheduler_circuit = get_scheduler_circuit(source)?;
+    let planned = vec![plan_recursive_circuit(
+        scheduler_circuit,
+        "scheduler".to_owned(),
+        &(base_vks, leaf_vks, node_vk, recursion_tip_vk),
+    )?];
+    for (vk, hint) in generate_with_cache(
+        cache, report, jobs, planned, synthesize, setup_base, generate,
+    )? {
+        source.set_recursion_layer_finalization_hint(hint)?;
+        source.set_recursion_layer_vk(vk)?;
+    }
+
+    Ok(())
+}
+
 pub fn write_era_compatible_layout(source: &InMemoryDataSource, keys_dir: &Path) -> Result<()> {
     fs::create_dir_all(keys_dir).with_context(|| {
         format!(
@@ -253,7 +454,11 @@ pub fn write_era_compatible_layout(source: &InMemoryDataSource, keys_dir: &Path)
     Ok(())
 }
 
-fn generate_compression_layer_artifacts(source: &mut InMemoryDataSource) -> Result<()> {
+fn generate_compression_layer_artifacts(
+    source: &mut InMemoryDataSource,
+    cache: Option<&KeyCache>,
+    report: &mut GenerationReport,
+) -> Result<()> {
     let worker = Worker::new();
 
     for circuit_type in COMPRESSION_CIRCUIT_TYPES {
@@ -282,40 +487,65 @@ fn generate_compression_layer_artifacts(source: &mut InMemoryDataSource) -> Resu
             _ => unreachable!("unsupported compression mode: {circuit_type}"),
         };
 
-        let circuit =
-            ZkSyncCompressionLayerCircuit::from_witness_and_vk(None, previous_vk, circuit_type);
+        let circuit = ZkSyncCompressionLayerCircuit::from_witness_and_vk(
+            None,
+            previous_vk.clone(),
+            circuit_type,
+        );
         let proof_config = circuit.proof_config_for_compression_step();
-        let (_setup_base, _setup, vk, _setup_tree, _vars_hint, _wits_hint, finalization_hint) =
-            create_compression_layer_setup_data(
-                circuit,
-                &worker,
-                proof_config.fri_lde_factor,
-                proof_config.merkle_tree_cap_size,
-            );
+        let fingerprint =
+            Fingerprint::new("compression", circuit_type, circuit.short_description())
+                .with_structure(circuit.geometry(), circuit.size_hint())?
+                .with_proof_config(
+                    proof_config.fri_lde_factor,
+                    proof_config.merkle_tree_cap_size,
+                )?
+                .with_json(&previous_vk)?;
+        let planned = vec![PlannedKey {
+            name: format!("compression {circuit_type}"),
+            fingerprint,
+            circuit,
+        }];
+
+        let generated = generate_with_cache(
+            cache,
+            report,
+            1,
+            planned,
+            SynthesizedSetup::for_compression_layer,
+            |synthesized| synthesized.setup_base(&worker),
+            |synthesized| {
+                synthesized.into_vk_and_finalization_hint(
+                    &worker,
+                    proof_config.fri_lde_factor,
+                    proof_config.merkle_tree_cap_size,
+                )
+            },
+        )?;
 
-        let vk: ZkSyncCompressionLayerVerificationKey =
-            ZkSyncCompressionLayerStorage::from_inner(circuit_type, vk);
-        source
-            .set_compression_vk(vk)
-            .map_err(|err| anyhow!("{err}"))
-            .with_context(|| {
+        for (vk, finalization_hint) in generated {
+            let vk: ZkSyncCompressionLayerVerificationKey =
+                ZkSyncCompressionLayerStorage::from_inner(circuit_type, vk);
+            source.set_compression_vk(vk).with_context(|| {
                 format!("while attempting to store compression verification key {circuit_type}")
             })?;
 
-        let hint: ZkSyncCompressionLayerFinalizationHint =
-            ZkSyncCompressionLayerStorage::from_inner(circuit_type, finalization_hint);
-        source
-            .set_compression_hint(hint)
-            .map_err(|err| anyhow!("{err}"))
-            .with_context(|| {
+            let hint: ZkSyncCompressionLayerFinalizationHint =
+                ZkSyncCompressionLayerStorage::from_inner(circuit_type, finalization_hint);
+            source.set_compression_hint(hint).with_context(|| {
                 format!("while attempting to store compression finalization hints {circuit_type}")
             })?;
+        }
     }
 
     Ok(())
 }
 
-fn generate_compression_for_wrapper_artifacts(source: &mut InMemoryDataSource) -> Result<()> {
+fn generate_compression_for_wrapper_artifacts(
+    source: &mut InMemoryDataSource,
+    cache: Option<&KeyCache>,
+    report: &mut GenerationReport,
+) -> Result<()> {
     let worker = Worker::new();
 
     for circuit_type in COMPRESSION_FOR_WRAPPER_CIRCUIT_TYPES {
@@ -340,46 +570,67 @@ fn generate_compression_for_wrapper_artifacts(source: &mut InMemoryDataSource) -
 
         let circuit = ZkSyncCompressionForWrapperCircuit::from_witness_and_vk(
             None,
-            previous_vk,
+            previous_vk.clone(),
             circuit_type,
         );
         let proof_config = circuit.proof_config_for_compression_step();
-        let (_setup_base, _setup, vk, _setup_tree, _vars_hint, _wits_hint, finalization_hint) =
-            create_compression_for_wrapper_setup_data(
-                circuit,
-                &worker,
-                proof_config.fri_lde_factor,
-                proof_config.merkle_tree_cap_size,
-            );
+        let fingerprint = Fingerprint::new(
+            "compression_wrapper",
+            circuit_type,
+            circuit.short_description(),
+        )
+        .with_structure(circuit.geometry(), circuit.size_hint())?
+        .with_proof_config(
+            proof_config.fri_lde_factor,
+            proof_config.merkle_tree_cap_size,
+        )?
+        .with_json(&previous_vk)?;
+        let planned = vec![PlannedKey {
+            name: format!("compression wrapper {circuit_type}"),
+            fingerprint,
+            circuit,
+        }];
+
+        let generated = generate_with_cache(
+            cache,
+            report,
+            1,
+            planned,
+            SynthesizedSetup::for_compression_for_wrapper,
+            |synthesized| synthesized.setup_base(&worker),
+            |synthesized| {
+                synthesized.into_vk_and_finalization_hint(
+                    &worker,
+                    proof_config.fri_lde_factor,
+                    proof_config.merkle_tree_cap_size,
+                )
+            },
+        )?;
 
-        let vk: ZkSyncCompressionForWrapperVerificationKey =
-            ZkSyncCompressionLayerStorage::from_inner(circuit_type, vk);
-        source
-            .set_compression_for_wrapper_vk(vk)
-            .map_err(|err| anyhow!("{err}"))
-            .with_context(|| {
+        for (vk, finalization_hint) in generated {
+            let vk: ZkSyncCompressionForWrapperVerificationKey =
+                ZkSyncCompressionLayerStorage::from_inner(circuit_type, vk);
+            source.set_compression_for_wrapper_vk(vk).with_context(|| {
                 format!(
                     "while attempting to store compression wrapper verification key {circuit_type}"
                 )
             })?;
 
-        let hint: ZkSyncCompressionForWrapperFinalizationHint =
-            ZkSyncCompressionLayerStorage::from_inner(circuit_type, finalization_hint);
-        source
-            .set_compression_for_wrapper_hint(hint)
-            .map_err(|err| anyhow!("{err}"))
-            .with_context(|| {
-                format!(
-                    "while attempting to store compression wrapper finalization hints {circuit_type}"
-                )
-            })?;
+            let hint: ZkSyncCompressionForWrapperFinalizationHint =
+                ZkSyncCompressionLayerStorage::from_inner(circuit_type, finalization_hint);
+            source
+                .set_compression_for_wrapper_hint(hint)
+                .with_context(|| {
+                    format!(
+                        "while attempting to store compression wrapper finalization hints {circuit_type}"
+                    )
+                })?;
+        }
     }
 
     Ok(())
 }
 
 fn from_source<T>(source_result: SourceResult<T>, context: impl FnOnce() -> String) -> Result<T> {
-    source_result
-        .map_err(|err| anyhow!("{err}"))
-        .with_context(context)
+    source_result.with_context(context)
 }
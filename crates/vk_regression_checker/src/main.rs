@@ -1,6 +1,11 @@
+#![cfg_attr(test, feature(allocator_api))]
+
 mod artifacts;
+mod cache;
 mod cli;
+mod commitments;
 mod comparison;
+mod diff;
 mod file_io;
 mod generation;
 
@@ -30,9 +35,23 @@ fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Command::Generate(args) => generation::run_generate(&args.keys_dir, args.jobs),
-        Command::Compare(args) => {
-            comparison::run_compare(&args.keys_dir, &args.generated_dir, args.jobs)
+        Command::Generate(args) => {
+            generation::run_generate(&args.keys_dir, args.jobs, args.cache_dir.as_deref())
+        }
+        Command::Compare(args) => comparison::run_compare(
+            &args.keys_dir,
+            &args.generated_dir,
+            args.jobs,
+            args.cache_dir.as_deref(),
+            &args.report,
+        ),
+        Command::Diff(args) => {
+            comparison::run_diff(&args.keys_dir, &args.generated_dir, &args.report)
         }
+        Command::Commitments(args) => commitments::run_commitments(
+            &args.keys_dir,
+            args.output.as_deref(),
+            args.skip_snark_wrapper,
+        ),
     }
 }
@@ -0,0 +1,519 @@
+//!
+//! The control flow graph of the linked code.
+//!
+
+use super::*;
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use zkevm_opcode_defs::decoding::AllowedPcOrImm;
+use zkevm_opcode_defs::{ImmMemHandlerFlags, Opcode, Operand, RegOrImmFlags, RET_TO_LABEL_BIT_IDX};
+
+pub(crate) type LinkedOpcode = DecodedOpcode<8, EncodingModeProduction>;
+
+/// The way control is transferred from an instruction to one of its successors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Flow {
+    /// Sequential execution, also taken when a conditional instruction is skipped.
+    Next(usize),
+    /// Taken static jump.
+    Jump(usize),
+    /// Entry into the callee of a near call.
+    Call(usize),
+    /// Resumption in the caller after a successful near or far call.
+    Continuation(usize),
+    /// Landing pad of a near or far call.
+    ExceptionHandler(usize),
+    /// The label of `ret.*.to_label`.
+    ReturnToLabel(usize),
+}
+
+impl Flow {
+    pub(crate) const fn target(&self) -> usize {
+        match self {
+            Flow::Next(pc)
+            | Flow::Jump(pc)
+            | Flow::Call(pc)
+            | Flow::Continuation(pc)
+            | Flow::ExceptionHandler(pc)
+            | Flow::ReturnToLabel(pc) => *pc,
+        }
+    }
+
+    pub(crate) const fn kind(&self) -> EdgeKind {
+        match self {
+            Flow::Next(_) => EdgeKind::Fallthrough,
+            Flow::Jump(_) => EdgeKind::Jump,
+            Flow::Call(_) => EdgeKind::NearCall,
+            Flow::Continuation(_) => EdgeKind::Continuation,
+            Flow::ExceptionHandler(_) => EdgeKind::ExceptionHandler,
+            Flow::ReturnToLabel(_) => EdgeKind::ReturnToLabel,
+        }
+    }
+
+    pub(crate) const fn is_static_target(&self) -> bool {
+        !matches!(self, Flow::Next(_) | Flow::Continuation(_))
+    }
+}
+
+pub(crate) fn static_jump_target<const N: usize, E: VmEncodingMode<N>>(
+    opcode: &DecodedOpcode<N, E>,
+) -> Option<usize> {
+    match opcode.variant.src0_operand_type {
+        Operand::Full(ImmMemHandlerFlags::UseImm16Only)
+        | Operand::RegOrImm(RegOrImmFlags::UseImm16Only) => Some(opcode.imm_0.as_u64() as usize),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_indirect_jump<const N: usize, E: VmEncodingMode<N>>(
+    opcode: &DecodedOpcode<N, E>,
+) -> bool {
+    matches!(opcode.variant.opcode, Opcode::Jump(_)) && static_jump_target(opcode).is_none()
+}
+
+/// Lists all the possible control transfers out of the instruction at `pc`.
+/// Indirect jumps and near returns have no statically known successors.
+pub(crate) fn control_flow<const N: usize, E: VmEncodingMode<N>>(
+    pc: usize,
+    opcode: &DecodedOpcode<N, E>,
+) -> Vec<Flow> {
+    let is_conditional = opcode.condition != Condition::Always;
+    let mut result = Vec::with_capacity(3);
+    match opcode.variant.opcode {
+        Opcode::Jump(_) => {
+            if let Some(target) = static_jump_target(opcode) {
+                result.push(Flow::Jump(target));
+            }
+            if is_conditional {
+                result.push(Flow::Next(pc + 1));
+            }
+        }
+        Opcode::NearCall(_) => {
+            result.push(Flow::Call(opcode.imm_0.as_u64() as usize));
+            result.push(Flow::ExceptionHandler(opcode.imm_1.as_u64() as usize));
+            result.push(Flow::Continuation(pc + 1));
+        }
+        Opcode::FarCall(_) => {
+            result.push(Flow::ExceptionHandler(opcode.imm_0.as_u64() as usize));
+            result.push(Flow::Continuation(pc + 1));
+        }
+        Opcode::Ret(_) => {
+            if opcode.variant.flags[RET_TO_LABEL_BIT_IDX] {
+                result.push(Flow::ReturnToLabel(opcode.imm_0.as_u64() as usize));
+            }
+            if is_conditional {
+                result.push(Flow::Next(pc + 1));
+            }
+        }
+        Opcode::Invalid(_) => {
+            if is_conditional {
+                result.push(Flow::Next(pc + 1));
+            }
+        }
+        _ => {
+            result.push(Flow::Next(pc + 1));
+        }
+    }
+
+    result
+}
+
+/// Code of the assembly as the VM sees it, together with the source mapping.
+pub(crate) struct LinkedCode {
+    pub(crate) opcodes: Vec<LinkedOpcode>,
+    pub(crate) data: Vec<[u8; 32]>,
+    pub(crate) pc_line_mapping: HashMap<usize, usize>,
+    pub(crate) function_labels: HashMap<String, usize>,
+}
+
+impl LinkedCode {
+    pub(crate) fn from_assembly(assembly: &Assembly) -> Result<Self, InstructionReadError> {
+        let mut tmp = assembly.clone();
+        let _ = tmp.compile_to_bytecode_for_mode::<8, EncodingModeProduction>()?;
+
+        let mut opcodes = Vec::with_capacity(tmp.bytecode.len() * 4);
+        let mut data = vec![];
+        for el in tmp.bytecode.into_iter() {
+            match el {
+                AlignedRawBytecode::Instructions(instructions) => {
+                    for instr in instructions.into_iter() {
+                        let opcode: LinkedOpcode =
+                            instr.into_decoded_opcode(assembly.config.isa_version)?;
+                        opcodes.push(opcode);
+                    }
+                }
+                AlignedRawBytecode::Data(value) => {
+                    data.push(value.serialize());
+                }
+            }
+        }
+
+        Ok(Self {
+            opcodes,
+            data,
+            pc_line_mapping: tmp.pc_line_mapping,
+            function_labels: tmp.function_labels,
+        })
+    }
+
+    /// Interprets every word of the bytecode as instructions, since the boundary between
+    /// code and constants is not encoded. Constants are normally not reachable from the entry point.
+    pub(crate) fn from_bytecode(bytecode: &[[u8; 32]]) -> Self {
+        let mut opcodes = Vec::with_capacity(bytecode.len() * 4);
+        for word in bytecode.iter() {
+            for chunk in word.chunks_exact(8) {
+                let raw = u64::from_be_bytes(chunk.try_into().unwrap());
+                let (opcode, _) =
+                    EncodingModeProduction::parse_preliminary_variant_and_absolute_number(raw);
+                opcodes.push(opcode);
+            }
+        }
+
+        Self {
+            opcodes,
+            data: vec![],
+            pc_line_mapping: HashMap::new(),
+            function_labels: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn line_for_pc(&self, pc: usize) -> Option<usize> {
+        self.pc_line_mapping.get(&pc).map(|line| line + 1)
+    }
+
+    /// Program counters that may be used as targets of indirect jumps: any immediate
+    /// or data cell that is equal to the position of some label.
+    fn address_taken_labels(&self) -> BTreeSet<usize> {
+        let mut candidates = HashSet::new();
+        for opcode in self.opcodes.iter() {
+            candidates.insert(opcode.imm_0.as_u64() as usize);
+            candidates.insert(opcode.imm_1.as_u64() as usize);
+        }
+        for cell in self.data.iter() {
+            if cell[..30].iter().all(|el| *el == 0) {
+                candidates.insert(u16::from_be_bytes([cell[30], cell[31]]) as usize);
+            }
+        }
+
+        self.function_labels
+            .values()
+            .copied()
+            .filter(|pc| candidates.contains(pc))
+            .collect()
+    }
+
+    pub(crate) fn reachable_pcs(&self) -> BTreeSet<usize> {
+        let mut roots = vec![0usize];
+        if self.opcodes.iter().any(is_indirect_jump) {
+            roots.extend(self.address_taken_labels());
+        }
+
+        let mut visited = BTreeSet::new();
+        let mut queue: VecDeque<usize> = roots.into_iter().collect();
+        while let Some(pc) = queue.pop_front() {
+            if pc >= self.opcodes.len() || !visited.insert(pc) {
+                continue;
+            }
+            for flow in control_flow(pc, &self.opcodes[pc]) {
+                queue.push_back(flow.target());
+            }
+        }
+
+        visited
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    /// Sequential execution into the next block.
+    Fallthrough,
+    /// Taken static jump.
+    Jump,
+    /// Entry into the callee of a near call.
+    NearCall,
+    /// Resumption in the caller after a successful near or far call.
+    Continuation,
+    /// Landing pad of a near or far call.
+    ExceptionHandler,
+    /// The label of `ret.*.to_label`.
+    ReturnToLabel,
+}
+
+impl EdgeKind {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            EdgeKind::Fallthrough => "fallthrough",
+            EdgeKind::Jump => "jump",
+            EdgeKind::NearCall => "near_call",
+            EdgeKind::Continuation => "continuation",
+            EdgeKind::ExceptionHandler => "exception_handler",
+            EdgeKind::ReturnToLabel => "return_to_label",
+        }
+    }
+
+    const fn dot_attributes(&self) -> &'static str {
+        match self {
+            EdgeKind::Fallthrough | EdgeKind::Jump => "",
+            EdgeKind::NearCall => ", style=bold",
+            EdgeKind::Continuation => ", style=dotted",
+            EdgeKind::ExceptionHandler => ", style=dashed, color=red",
+            EdgeKind::ReturnToLabel => ", style=dashed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockInstruction {
+    pub pc: usize,
+    /// The 1-based line in the assembly text, if known.
+    pub line: Option<usize>,
+    pub text: String,
+}
+
+///
+/// The straight-line sequence of instructions with a single entry and a single exit.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub id: usize,
+    pub start_pc: usize,
+    /// The pc of the last instruction, inclusive.
+    pub end_pc: usize,
+    pub labels: Vec<String>,
+    pub instructions: Vec<BlockInstruction>,
+    /// Condition of the last instruction in the block.
+    pub condition: Condition,
+    /// Whether the block ends with a jump to a location only known at runtime.
+    pub has_indirect_jump: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+///
+/// The control flow graph of the code reachable from the entry point.
+///
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+fn describe_opcode(opcode: &LinkedOpcode) -> String {
+    let mut result = format!("{:?}", opcode.variant.opcode);
+    if opcode.condition != Condition::Always {
+        result.push_str(&format!(".{:?}", opcode.condition));
+    }
+    result.push_str(&format!(
+        " src=({:?} r{}, r{}) dst=({:?} r{}, r{}) imm=({}, {})",
+        opcode.variant.src0_operand_type,
+        opcode.src0_reg_idx,
+        opcode.src1_reg_idx,
+        opcode.variant.dst0_operand_type,
+        opcode.dst0_reg_idx,
+        opcode.dst1_reg_idx,
+        opcode.imm_0,
+        opcode.imm_1,
+    ));
+
+    result
+}
+
+fn ends_block(pc: usize, opcode: &LinkedOpcode, flows: &[Flow]) -> bool {
+    opcode.condition != Condition::Always || flows != [Flow::Next(pc + 1)]
+}
+
+impl ControlFlowGraph {
+    ///
+    /// Builds the graph from the assembly linked in production encoding mode.
+    ///
+    pub fn from_assembly(assembly: &Assembly) -> Result<Self, InstructionReadError> {
+        let code = LinkedCode::from_assembly(assembly)?;
+        let source_lines: Vec<&str> = assembly.assembly_code.lines().collect();
+
+        Ok(Self::build(&code, |pc, opcode| {
+            code.pc_line_mapping
+                .get(&pc)
+                .and_then(|line| source_lines.get(*line))
+                .map(|line| trim_comments(line).to_owned())
+                .unwrap_or_else(|| describe_opcode(opcode))
+        }))
+    }
+
+    ///
+    /// Builds the graph from the production encoding bytecode. Only the code reachable
+    /// through static control flow is recovered, as there are no labels to resolve
+    /// indirect jumps with.
+    ///
+    pub fn from_bytecode(bytecode: &[[u8; 32]]) -> Self {
+        let code = LinkedCode::from_bytecode(bytecode);
+
+        Self::build(&code, |_, opcode| describe_opcode(opcode))
+    }
+
+    fn build(code: &LinkedCode, describe: impl Fn(usize, &LinkedOpcode) -> String) -> Self {
+        let reachable = code.reachable_pcs();
+
+        let mut labels_by_pc: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (label, pc) in code.function_labels.iter() {
+            if reachable.contains(pc) {
+                labels_by_pc.entry(*pc).or_default().push(label.clone());
+            }
+        }
+        for labels in labels_by_pc.values_mut() {
+            labels.sort();
+        }
+
+        let mut leaders: BTreeSet<usize> = labels_by_pc.keys().copied().collect();
+        leaders.insert(0);
+        for pc in reachable.iter().copied() {
+            let opcode = &code.opcodes[pc];
+            let flows = control_flow(pc, opcode);
+            if ends_block(pc, opcode, &flows) {
+                leaders.insert(pc + 1);
+            }
+            for flow in flows.iter().filter(|el| el.is_static_target()) {
+                leaders.insert(flow.target());
+            }
+        }
+
+        let mut blocks: Vec<BasicBlock> = vec![];
+        let mut block_by_start_pc = HashMap::new();
+        let mut previous_pc = None;
+        for pc in reachable.iter().copied() {
+            let opcode = &code.opcodes[pc];
+            let starts_new_block =
+                leaders.contains(&pc) || previous_pc.map(|el| el + 1) != Some(pc);
+            if starts_new_block || blocks.is_empty() {
+                let id = blocks.len();
+                block_by_start_pc.insert(pc, id);
+                blocks.push(BasicBlock {
+                    id,
+                    start_pc: pc,
+                    end_pc: pc,
+                    labels: labels_by_pc.get(&pc).cloned().unwrap_or_default(),
+                    instructions: vec![],
+                    condition: Condition::Always,
+                    has_indirect_jump: false,
+                });
+            }
+            let block = blocks.last_mut().unwrap();
+            block.end_pc = pc;
+            block.condition = opcode.condition;
+            block.has_indirect_jump = is_indirect_jump(opcode);
+            block.instructions.push(BlockInstruction {
+                pc,
+                line: code.line_for_pc(pc),
+                text: describe(pc, opcode),
+            });
+            previous_pc = Some(pc);
+        }
+
+        let mut edges = vec![];
+        for block in blocks.iter() {
+            for flow in control_flow(block.end_pc, &code.opcodes[block.end_pc]) {
+                // targets outside of the code are reported by the linter
+                if let Some(to) = block_by_start_pc.get(&flow.target()) {
+                    edges.push(Edge {
+                        from: block.id,
+                        to: *to,
+                        kind: flow.kind(),
+                    });
+                }
+            }
+        }
+
+        Self { blocks, edges }
+    }
+
+    ///
+    /// Renders the graph in the Graphviz DOT format.
+    ///
+    pub fn to_dot(&self) -> String {
+        fn escape(input: &str) -> String {
+            input
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('{', "\\{")
+                .replace('}', "\\}")
+        }
+
+        let mut result = String::from("digraph cfg {\n");
+        result.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks.iter() {
+            let mut label = format!("pc {}..={}\\l", block.start_pc, block.end_pc);
+            for name in block.labels.iter() {
+                label.push_str(&format!("{}:\\l", escape(name)));
+            }
+            for instruction in block.instructions.iter() {
+                label.push_str(&format!("  {}\\l", escape(&instruction.text)));
+            }
+            result.push_str(&format!("    block{} [label=\"{}\"];\n", block.id, label));
+        }
+        for edge in self.edges.iter() {
+            result.push_str(&format!(
+                "    block{} -> block{} [label=\"{}\"{}];\n",
+                edge.from,
+                edge.to,
+                edge.kind.name(),
+                edge.kind.dot_attributes()
+            ));
+        }
+        result.push_str("}\n");
+
+        result
+    }
+
+    ///
+    /// Renders the graph as a JSON document with `blocks` and `edges` arrays.
+    ///
+    pub fn to_json(&self) -> serde_json::Value {
+        let blocks: Vec<serde_json::Value> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let instructions: Vec<serde_json::Value> = block
+                    .instructions
+                    .iter()
+                    .map(|el| {
+                        serde_json::json!({
+                            "pc": el.pc,
+                            "line": el.line,
+                            "text": el.text,
+                        })
+                    })
+                    .collect();
+
+                serde_json::json!({
+                    "id": block.id,
+                    "start_pc": block.start_pc,
+                    "end_pc": block.end_pc,
+                    "labels": block.labels,
+                    "condition": format!("{:?}", block.condition),
+                    "has_indirect_jump": block.has_indirect_jump,
+                    "instructions": instructions,
+                })
+            })
+            .collect();
+        let edges: Vec<serde_json::Value> = self
+            .edges
+            .iter()
+            .map(|edge| {
+                serde_json::json!({
+                    "from": edge.from,
+                    "to": edge.to,
+                    "kind": edge.kind.name(),
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "blocks": blocks,
+            "edges": edges,
+        })
+    }
+}
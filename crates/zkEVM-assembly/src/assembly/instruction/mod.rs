@@ -353,6 +353,21 @@ pub(crate) fn link_operand<const N: usize, E: VmEncodingMode<N>>(
     Ok(())
 }
 
+impl Instruction {
+    ///
+    /// Encodes the instruction for the given ISA version.
+    ///
+    pub fn into_decoded_opcode<const N: usize, E: VmEncodingMode<N>>(
+        self,
+        isa_version: ISAVersion,
+    ) -> Result<DecodedOpcode<N, E>, InstructionReadError> {
+        match self {
+            Instruction::UMA(instr) => instr.into_decoded_opcode(isa_version),
+            instr => DecodedOpcode::try_from(instr),
+        }
+    }
+}
+
 impl<const N: usize, E: VmEncodingMode<N>> TryFrom<Instruction> for DecodedOpcode<N, E> {
     type Error = InstructionReadError;
     fn try_from(value: Instruction) -> Result<Self, Self::Error> {
@@ -372,7 +387,7 @@ impl<const N: usize, E: VmEncodingMode<N>> TryFrom<Instruction> for DecodedOpcod
             Instruction::NearCall(instr) => DecodedOpcode::try_from(instr),
             Instruction::FarCall(instr) => DecodedOpcode::try_from(instr),
             Instruction::Ret(instr) => DecodedOpcode::try_from(instr),
-            Instruction::UMA(instr) => DecodedOpcode::try_from(instr),
+            Instruction::UMA(instr) => instr.into_decoded_opcode(crate::DEFAULT_ISA_VERSION),
         }
     }
 }
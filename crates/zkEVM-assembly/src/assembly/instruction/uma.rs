@@ -160,31 +160,40 @@ impl UMA {
     }
 }
 
-impl<const N: usize, E: VmEncodingMode<N>> TryFrom<UMA> for DecodedOpcode<N, E> {
-    type Error = InstructionReadError;
-    fn try_from(value: UMA) -> Result<Self, Self::Error> {
+impl UMA {
+    pub fn into_decoded_opcode<const N: usize, E: VmEncodingMode<N>>(
+        self,
+        isa_version: ISAVersion,
+    ) -> Result<DecodedOpcode<N, E>, InstructionReadError> {
         let mut new = DecodedOpcode::default();
         new.variant = OpcodeVariant {
-            opcode: Opcode::UMA(value.uma_type),
+            opcode: Opcode::UMA(self.uma_type),
             ..OpcodeVariant::default()
         };
-        match new.variant.opcode.input_operands(crate::get_isa_version())[0] {
+        match new.variant.opcode.input_operands(isa_version)[0] {
             Operand::RegOrImm(_) => {
-                assert!(crate::get_isa_version().0 >= 1);
-                set_src_non_memory_operand(&value.src_0, &mut new);
+                assert!(isa_version.0 >= 1);
+                set_src_non_memory_operand(&self.src_0, &mut new);
             }
             Operand::RegOnly => {
-                let as_register = value.src_0.as_register_operand(0)?;
+                let as_register = self.src_0.as_register_operand(0)?;
                 set_src0_or_dst0_register_operand(&as_register, &mut new, false);
             }
             _ => unreachable!(),
         }
-        set_register_operand(&value.src_1, &mut new, false);
-        set_src0_or_dst0_register_operand(&value.dst_0, &mut new, true);
-        set_register_operand(&value.dst_1, &mut new, true);
-        new.condition = value.condition.0;
-        new.variant.flags[zkevm_opcode_defs::UMA_INCREMENT_FLAG_IDX] = value.increment_offset;
+        set_register_operand(&self.src_1, &mut new, false);
+        set_src0_or_dst0_register_operand(&self.dst_0, &mut new, true);
+        set_register_operand(&self.dst_1, &mut new, true);
+        new.condition = self.condition.0;
+        new.variant.flags[zkevm_opcode_defs::UMA_INCREMENT_FLAG_IDX] = self.increment_offset;
 
         Ok(new)
     }
 }
+
+impl<const N: usize, E: VmEncodingMode<N>> TryFrom<UMA> for DecodedOpcode<N, E> {
+    type Error = InstructionReadError;
+    fn try_from(value: UMA) -> Result<Self, Self::Error> {
+        value.into_decoded_opcode(crate::DEFAULT_ISA_VERSION)
+    }
+}
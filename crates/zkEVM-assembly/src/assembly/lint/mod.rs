@@ -0,0 +1,447 @@
+//!
+//! The static analysis pass over the linked assembly.
+//!
+
+use super::*;
+
+use crate::assembly::cfg::{control_flow, Flow, LinkedCode, LinkedOpcode};
+use crate::assembly::linking::{
+    DEFAULT_FAR_RETURN_LABEL, DEFAULT_FAR_REVERT_LABEL, DEFAULT_UNWIND_LABEL,
+};
+use std::collections::{BTreeSet, VecDeque};
+use zkevm_opcode_defs::decoding::AllowedPcOrImm;
+use zkevm_opcode_defs::{ImmMemHandlerFlags, Operand, RegOrImmFlags, REGISTERS_COUNT};
+
+/// Registers that are populated by the far call ABI on contract entry: `r1` is the calldata
+/// pointer, `r2` holds the call flags and `r3..=r12` may carry extra ABI parameters.
+pub const FAR_CALL_ABI_REGISTERS: u16 = 0b0001_1111_1111_1110;
+
+const ALL_REGISTERS: u16 = ((1u32 << (REGISTERS_COUNT + 1)) - 1) as u16;
+
+///
+/// The linter configuration.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct LintConfig {
+    /// Whether the contract is executed in kernel mode (i.e. it is a system contract).
+    pub kernel_mode: bool,
+    /// Whether the contract is expected to be executed in static context.
+    pub static_context: bool,
+    /// Registers holding meaningful values on entry, bit `i` stands for `ri`.
+    pub initialized_registers_on_entry: u16,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            kernel_mode: false,
+            static_context: false,
+            initialized_registers_on_entry: FAR_CALL_ABI_REGISTERS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticKind {
+    /// Opcode that requires kernel mode in a contract that is not a kernel one.
+    KernelOnlyOpcode,
+    /// Opcode that is forbidden in static context in a contract expected to run in it.
+    StaticContextViolation,
+    /// Label whose code can not be reached from the entry point.
+    UnreachableLabel,
+    /// Stack pointer offset differs between paths merging into the same instruction, or drops
+    /// below its value at the entry of the frame.
+    StackPointerImbalance,
+    /// Register read before any write on some path from the entry point.
+    UninitializedRegisterRead,
+    /// Static control flow target outside of the code.
+    JumpOutsideOfCode,
+}
+
+impl DiagnosticKind {
+    pub const fn severity(&self) -> Severity {
+        match self {
+            DiagnosticKind::KernelOnlyOpcode
+            | DiagnosticKind::StaticContextViolation
+            | DiagnosticKind::JumpOutsideOfCode => Severity::Error,
+            DiagnosticKind::UnreachableLabel
+            | DiagnosticKind::StackPointerImbalance
+            | DiagnosticKind::UninitializedRegisterRead => Severity::Warning,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            DiagnosticKind::KernelOnlyOpcode => "kernel-only-opcode",
+            DiagnosticKind::StaticContextViolation => "static-context-violation",
+            DiagnosticKind::UnreachableLabel => "unreachable-label",
+            DiagnosticKind::StackPointerImbalance => "stack-pointer-imbalance",
+            DiagnosticKind::UninitializedRegisterRead => "uninitialized-register-read",
+            DiagnosticKind::JumpOutsideOfCode => "jump-outside-of-code",
+        }
+    }
+}
+
+///
+/// The single linter finding.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// The instruction index in the linked code, if the finding is bound to one.
+    pub pc: Option<usize>,
+    /// The 1-based line in the assembly text, if known.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.pc) {
+            (Some(line), _) => write!(f, "line {}: ", line)?,
+            (None, Some(pc)) => write!(f, "pc {}: ", pc)?,
+            (None, None) => {}
+        }
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.severity(),
+            self.kind.name(),
+            self.message
+        )
+    }
+}
+
+fn reads_and_writes(opcode: &LinkedOpcode) -> (u16, u16) {
+    let mut reads = 0u16;
+    let mut writes = 0u16;
+
+    match opcode.variant.src0_operand_type {
+        Operand::Full(ImmMemHandlerFlags::UseImm16Only)
+        | Operand::RegOrImm(RegOrImmFlags::UseImm16Only) => {}
+        // either a value itself or an offset for memory addressing
+        _ => reads |= 1 << opcode.src0_reg_idx,
+    }
+    reads |= 1 << opcode.src1_reg_idx;
+
+    match opcode.variant.dst0_operand_type {
+        Operand::RegOnly | Operand::RegOrImm(_) | Operand::Full(ImmMemHandlerFlags::UseRegOnly) => {
+            writes |= 1 << opcode.dst0_reg_idx
+        }
+        // register is used as an offset
+        _ => reads |= 1 << opcode.dst0_reg_idx,
+    }
+    writes |= 1 << opcode.dst1_reg_idx;
+
+    // r0 is always zero
+    (reads & !1, writes & !1)
+}
+
+fn stack_pointer_delta(opcode: &LinkedOpcode) -> Option<i64> {
+    let mut delta = 0i64;
+    if opcode.variant.src0_operand_type == Operand::Full(ImmMemHandlerFlags::UseStackWithPushPop) {
+        if opcode.src0_reg_idx != 0 {
+            return None;
+        }
+        delta -= opcode.imm_0.as_u64() as i64;
+    }
+    if opcode.variant.dst0_operand_type == Operand::Full(ImmMemHandlerFlags::UseStackWithPushPop) {
+        if opcode.dst0_reg_idx != 0 {
+            return None;
+        }
+        delta += opcode.imm_1.as_u64() as i64;
+    }
+
+    Some(delta)
+}
+
+fn check_opcode_restrictions(
+    code: &LinkedCode,
+    config: &LintConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (pc, opcode) in code.opcodes.iter().enumerate() {
+        let variant = opcode.variant;
+        if !config.kernel_mode && variant.requires_kernel_mode() {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::KernelOnlyOpcode,
+                pc: Some(pc),
+                line: code.line_for_pc(pc),
+                message: format!("{:?} can only be used in kernel mode", variant.opcode),
+            });
+        }
+        if config.static_context && !variant.can_be_used_in_static_context() {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::StaticContextViolation,
+                pc: Some(pc),
+                line: code.line_for_pc(pc),
+                message: format!("{:?} can not be used in static context", variant.opcode),
+            });
+        }
+    }
+}
+
+fn check_jump_targets(code: &LinkedCode, diagnostics: &mut Vec<Diagnostic>) {
+    let code_length = code.opcodes.len();
+    for (pc, opcode) in code.opcodes.iter().enumerate() {
+        for flow in control_flow(pc, opcode) {
+            if flow.is_static_target() && flow.target() >= code_length {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::JumpOutsideOfCode,
+                    pc: Some(pc),
+                    line: code.line_for_pc(pc),
+                    message: format!(
+                        "control flow target pc {} is outside of the code of {} instructions",
+                        flow.target(),
+                        code_length
+                    ),
+                });
+            }
+        }
+    }
+}
+
+fn check_unreachable_labels(
+    code: &LinkedCode,
+    reachable: &BTreeSet<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let default_labels = [
+        DEFAULT_UNWIND_LABEL,
+        DEFAULT_FAR_RETURN_LABEL,
+        DEFAULT_FAR_REVERT_LABEL,
+    ];
+    for (label, pc) in code.function_labels.iter() {
+        if default_labels.contains(&label.as_str()) || *pc >= code.opcodes.len() {
+            continue;
+        }
+        if !reachable.contains(pc) {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnreachableLabel,
+                pc: Some(*pc),
+                line: code.line_for_pc(*pc),
+                message: format!("label `{}` is unreachable", label),
+            });
+        }
+    }
+}
+
+fn check_uninitialized_registers(
+    code: &LinkedCode,
+    reachable: &BTreeSet<usize>,
+    config: &LintConfig,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    // forward "must be initialized" analysis, meet is an intersection
+    let mut states: HashMap<usize, u16> = HashMap::new();
+    let mut queue = VecDeque::new();
+    states.insert(0, config.initialized_registers_on_entry | 1);
+    queue.push_back(0usize);
+
+    // labels reachable only through indirect jumps are entered with unknown state,
+    // so we assume the best
+    let static_targets: HashSet<usize> = code
+        .opcodes
+        .iter()
+        .enumerate()
+        .flat_map(|(pc, opcode)| control_flow(pc, opcode))
+        .map(|el| el.target())
+        .collect();
+    for pc in code.function_labels.values().copied() {
+        if pc != 0 && reachable.contains(&pc) && !static_targets.contains(&pc) {
+            states.insert(pc, ALL_REGISTERS);
+            queue.push_back(pc);
+        }
+    }
+
+    while let Some(pc) = queue.pop_front() {
+        if pc >= code.opcodes.len() {
+            continue;
+        }
+        let opcode = &code.opcodes[pc];
+        let state = states[&pc];
+        let (_, writes) = reads_and_writes(opcode);
+        let executed = state | writes;
+        let is_conditional = opcode.condition != Condition::Always;
+
+        for flow in control_flow(pc, opcode) {
+            let incoming = match flow {
+                Flow::Next(_) if is_conditional => state,
+                Flow::Next(_) | Flow::Jump(_) | Flow::Call(_) | Flow::ReturnToLabel(_) => executed,
+                // we do not track what the callee leaves in registers
+                Flow::Continuation(_) | Flow::ExceptionHandler(_) => ALL_REGISTERS,
+            };
+            let target = flow.target();
+            let updated = match states.get(&target) {
+                Some(existing) => existing & incoming,
+                None => incoming,
+            };
+            if states.get(&target) != Some(&updated) {
+                states.insert(target, updated);
+                queue.push_back(target);
+            }
+        }
+    }
+
+    for pc in reachable.iter().copied() {
+        let Some(state) = states.get(&pc) else {
+            continue;
+        };
+        let (reads, _) = reads_and_writes(&code.opcodes[pc]);
+        let uninitialized = reads & !state;
+        for idx in 1..=REGISTERS_COUNT {
+            if uninitialized & (1 << idx) != 0 {
+                diagnostics.push(Diagnostic {
+                    kind: DiagnosticKind::UninitializedRegisterRead,
+                    pc: Some(pc),
+                    line: code.line_for_pc(pc),
+                    message: format!("r{} may be read before being written", idx),
+                });
+            }
+        }
+    }
+}
+
+fn check_stack_pointer_balance(
+    code: &LinkedCode,
+    reachable: &BTreeSet<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    // every near call starts a new frame, so we analyze each callee separately
+    // and do not follow neither calls nor returns
+    let mut entries = BTreeSet::new();
+    entries.insert(0usize);
+    for pc in reachable.iter().copied() {
+        for flow in control_flow(pc, &code.opcodes[pc]) {
+            if let Flow::Call(target) = flow {
+                entries.insert(target);
+            }
+        }
+    }
+
+    let mut reported = HashSet::new();
+    let mut underflows = HashSet::new();
+    for entry in entries.into_iter() {
+        if entry >= code.opcodes.len() {
+            continue;
+        }
+        let mut states: HashMap<usize, Option<i64>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        states.insert(entry, Some(0));
+        queue.push_back(entry);
+
+        while let Some(pc) = queue.pop_front() {
+            if pc >= code.opcodes.len() {
+                continue;
+            }
+            let opcode = &code.opcodes[pc];
+            let state = states[&pc];
+            let executed = match (state, stack_pointer_delta(opcode)) {
+                (Some(offset), Some(delta)) => Some(offset + delta),
+                _ => None,
+            };
+            // like with brackets, equal offsets on merging paths are not enough: a frame
+            // must never pop the slots that were pushed before its entry
+            if let (Some(before), Some(offset)) = (state, executed) {
+                if before >= 0 && offset < 0 && underflows.insert(pc) {
+                    diagnostics.push(Diagnostic {
+                        kind: DiagnosticKind::StackPointerImbalance,
+                        pc: Some(pc),
+                        line: code.line_for_pc(pc),
+                        message: format!(
+                            "stack pointer drops {} below its value at the frame entry at pc {}",
+                            -offset, entry
+                        ),
+                    });
+                }
+            }
+            let is_conditional = opcode.condition != Condition::Always;
+
+            let mut incoming = vec![];
+            for flow in control_flow(pc, opcode) {
+                match flow {
+                    Flow::Next(target) => {
+                        incoming.push((target, executed));
+                        if is_conditional {
+                            incoming.push((target, state));
+                        }
+                    }
+                    Flow::Jump(target) => incoming.push((target, executed)),
+                    // near and far calls restore the caller's stack pointer
+                    Flow::Continuation(target) | Flow::ExceptionHandler(target) => {
+                        incoming.push((target, state))
+                    }
+                    Flow::Call(_) | Flow::ReturnToLabel(_) => {}
+                }
+            }
+
+            for (target, value) in incoming.into_iter() {
+                let updated = match states.get(&target) {
+                    None => value,
+                    Some(existing) if *existing == value => continue,
+                    Some(Some(existing)) => {
+                        if let Some(value) = value {
+                            if reported.insert(target) {
+                                diagnostics.push(Diagnostic {
+                                    kind: DiagnosticKind::StackPointerImbalance,
+                                    pc: Some(target),
+                                    line: code.line_for_pc(target),
+                                    message: format!(
+                                        "stack pointer offset from the frame entry at pc {} is {} on one path and {} on another",
+                                        entry, existing, value
+                                    ),
+                                });
+                            }
+                        }
+                        None
+                    }
+                    Some(None) => continue,
+                };
+                states.insert(target, updated);
+                queue.push_back(target);
+            }
+        }
+    }
+}
+
+impl Assembly {
+    ///
+    /// Runs all the static checks over the assembly linked in production encoding mode.
+    ///
+    pub fn lint(&self, config: &LintConfig) -> Result<Vec<Diagnostic>, InstructionReadError> {
+        let code = LinkedCode::from_assembly(self)?;
+        let reachable = code.reachable_pcs();
+
+        let mut diagnostics = vec![];
+        check_opcode_restrictions(&code, config, &mut diagnostics);
+        check_jump_targets(&code, &mut diagnostics);
+        check_unreachable_labels(&code, &reachable, &mut diagnostics);
+        check_uninitialized_registers(&code, &reachable, config, &mut diagnostics);
+        check_stack_pointer_balance(&code, &reachable, &mut diagnostics);
+
+        diagnostics.sort_by_key(|el| (el.line, el.pc, el.kind));
+
+        Ok(diagnostics)
+    }
+}
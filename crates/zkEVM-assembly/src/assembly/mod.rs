@@ -2,13 +2,13 @@
 //! The assembly entity.
 //!
 
-use std::convert::TryInto;
-
 // pub mod bytecode;
 // pub mod data_operation;
+pub mod cfg;
 pub mod constants;
 pub mod instruction;
 pub mod linking;
+pub mod lint;
 pub mod mnemonic;
 pub mod operand;
 pub mod parse;
@@ -19,7 +19,7 @@ use self::section::ParsedSection;
 use crate::assembly::linking::AlignedRawBytecode;
 use crate::assembly::mnemonic::all_until1;
 use crate::error::{AssemblyParseError, Error};
-use crate::{get_encoding_mode, InstructionReadError, RunningVmEncodingMode};
+use crate::{AssemblerConfig, InstructionReadError, RunningVmEncodingMode, DEFAULT_ISA_VERSION};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::fs;
@@ -169,6 +169,7 @@ pub struct Assembly {
     pub(crate) global_variables: HashMap<String, LabeledGlobal>,
     pub(crate) parsed_sections: Vec<ParsedSection>,
     pub(crate) labels: HashSet<String>,
+    pub(crate) config: AssemblerConfig,
 }
 
 impl Assembly {
@@ -178,7 +179,7 @@ impl Assembly {
     pub const LABELS_DEFAULT_CAPACITY: usize = 64;
 
     pub fn compile_to_bytecode(&mut self) -> Result<Vec<[u8; 32]>, InstructionReadError> {
-        match get_encoding_mode() {
+        match self.config.encoding_mode {
             RunningVmEncodingMode::Production => {
                 self.compile_to_bytecode_for_mode::<8, EncodingModeProduction>()
             }
@@ -220,7 +221,8 @@ impl Assembly {
                     assert_eq!(opcodes_per_word, instructions.len());
                     let mut result = [0u8; 32];
                     for (i, instr) in instructions.into_iter().enumerate() {
-                        let t: DecodedOpcode<N, E> = instr.try_into()?;
+                        let t: DecodedOpcode<N, E> =
+                            instr.into_decoded_opcode(self.config.isa_version)?;
                         let serialized_bytecode = t.serialize_as_bytes();
                         result[N * i..N * (i + 1)].copy_from_slice(&serialized_bytecode);
                     }
@@ -243,9 +245,13 @@ impl Assembly {
             ));
         }
 
-        if bytecode.len() as u64 > E::PcOrImm::max().as_u64() {
+        let max_code_size_in_words = match self.config.max_code_size_in_words {
+            Some(limit) => std::cmp::min(limit, E::PcOrImm::max().as_u64()),
+            None => E::PcOrImm::max().as_u64(),
+        };
+        if bytecode.len() as u64 > max_code_size_in_words {
             return Err(InstructionReadError::CodeIsTooLong(
-                E::PcOrImm::max().as_u64(),
+                max_code_size_in_words,
                 num_instructions,
             ));
         }
@@ -283,7 +289,8 @@ impl Assembly {
             match el {
                 AlignedRawBytecode::Instructions(instructions) => {
                     for (_i, instr) in instructions.into_iter().enumerate() {
-                        let t: DecodedOpcode<N, E> = instr.try_into().unwrap();
+                        let t: DecodedOpcode<N, E> =
+                            instr.into_decoded_opcode(self.config.isa_version).unwrap();
                         result.push(t);
                     }
                 }
@@ -297,8 +304,15 @@ impl Assembly {
     pub fn from_string(
         input: String,
         metadata_hash: Option<[u8; 32]>,
+        config: AssemblerConfig,
     ) -> Result<Self, AssemblyParseError> {
         use crate::assembly::parse::*;
+        if config.isa_version > DEFAULT_ISA_VERSION {
+            return Err(AssemblyParseError::UnsupportedIsaVersion(
+                config.isa_version.0,
+            ));
+        }
+
         let newline = ['\r', '\n'];
         let text = input.trim_matches(&newline[..]);
 
@@ -314,21 +328,31 @@ impl Assembly {
             global_variables: HashMap::new(),
             parsed_sections: sections,
             labels,
+            config,
         };
 
         Ok(new)
     }
+
+    pub fn from_file(path: PathBuf, config: AssemblerConfig) -> Result<Self, Error> {
+        let mut file = fs::File::open(&path).map_err(Error::FileOpening)?;
+        let size = fs::metadata(&path).map_err(Error::FileMetadata)?.len() as usize;
+        let mut text = String::with_capacity(size);
+        file.read_to_string(&mut text).map_err(Error::FileReading)?;
+        let metadata_hash = sha3::Keccak256::digest(text.as_bytes()).into();
+        Ok(Self::from_string(text, Some(metadata_hash), config)?)
+    }
+
+    pub fn config(&self) -> &AssemblerConfig {
+        &self.config
+    }
 }
 
 impl TryFrom<PathBuf> for Assembly {
     type Error = Error;
 
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        let mut file = fs::File::open(&path).map_err(Error::FileOpening)?;
-        let size = fs::metadata(&path).map_err(Error::FileMetadata)?.len() as usize;
-        let mut text = String::with_capacity(size);
-        file.read_to_string(&mut text).map_err(Error::FileReading)?;
-        Ok(Self::try_from(text)?)
+        Self::from_file(path, AssemblerConfig::default())
     }
 }
 
@@ -353,7 +377,7 @@ impl TryFrom<String> for Assembly {
 
     fn try_from(input: String) -> Result<Self, Self::Error> {
         let metadata_hash = sha3::Keccak256::digest(input.as_bytes()).into();
-        Self::from_string(input, Some(metadata_hash))
+        Self::from_string(input, Some(metadata_hash), AssemblerConfig::default())
     }
 }
 
@@ -0,0 +1,41 @@
+//!
+//! The assembler configuration.
+//!
+
+use crate::{ISAVersion, RunningVmEncodingMode, DEFAULT_ISA_VERSION};
+
+///
+/// The assembler configuration. Every assembly carries its own one, so differently
+/// configured assemblies can be compiled concurrently in the same process.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AssemblerConfig {
+    /// The encoding mode used by `Assembly::compile_to_bytecode`.
+    pub encoding_mode: RunningVmEncodingMode,
+    /// The ISA version to encode the instructions for.
+    pub isa_version: ISAVersion,
+    /// The maximum code size in 32-byte words. The limit of the encoding mode is used if not set.
+    pub max_code_size_in_words: Option<u64>,
+}
+
+impl Default for AssemblerConfig {
+    fn default() -> Self {
+        Self {
+            encoding_mode: RunningVmEncodingMode::Production,
+            isa_version: DEFAULT_ISA_VERSION,
+            max_code_size_in_words: None,
+        }
+    }
+}
+
+impl AssemblerConfig {
+    ///
+    /// A shortcut constructor for the default configuration in the testing encoding mode.
+    ///
+    pub fn testing() -> Self {
+        Self {
+            encoding_mode: RunningVmEncodingMode::Testing,
+            ..Self::default()
+        }
+    }
+}
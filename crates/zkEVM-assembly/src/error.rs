@@ -41,6 +41,8 @@ pub enum AssemblyParseError {
     RelocationError(String),
     #[error("Label {1} was tried to be used for either PC or constant at offset {0} that is more than `{2}` addressable space")]
     CodeIsTooLong(usize, String, u64),
+    #[error("ISA version {0} is not supported by the assembler")]
+    UnsupportedIsaVersion(u8),
 }
 
 #[derive(Debug, Error, PartialEq)]
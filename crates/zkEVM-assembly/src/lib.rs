@@ -9,6 +9,7 @@
 mod tests;
 
 pub(crate) mod assembly;
+pub(crate) mod config;
 pub(crate) mod error;
 
 pub use zkevm_opcode_defs;
@@ -27,10 +28,13 @@ pub use self::assembly::instruction::ret::Ret as ReturnInstruction;
 pub use self::assembly::instruction::shift::Shift as ShiftInstruction;
 pub use self::assembly::instruction::sub::Sub as SubInstruction;
 
+pub use self::assembly::cfg::{BasicBlock, BlockInstruction, ControlFlowGraph, Edge, EdgeKind};
 pub use self::assembly::instruction::Instruction;
+pub use self::assembly::lint::{Diagnostic, DiagnosticKind, LintConfig, Severity};
 pub use self::assembly::operand::FullOperand;
 pub use self::assembly::operand::RegisterOperand;
 pub use self::assembly::Assembly;
+pub use self::config::AssemblerConfig;
 pub use self::error::{AssemblyParseError, BinaryParseError, InstructionReadError};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -54,31 +58,9 @@ impl RunningVmEncodingMode {
     }
 }
 
-use std::sync::atomic::AtomicU64;
-
-pub static ENCODING_MODE: AtomicU64 = AtomicU64::new(RunningVmEncodingMode::Production as u64);
-
-pub fn set_encoding_mode(value: RunningVmEncodingMode) {
-    ENCODING_MODE.store(value.as_u64(), std::sync::atomic::Ordering::SeqCst);
-}
-
-pub fn get_encoding_mode() -> RunningVmEncodingMode {
-    RunningVmEncodingMode::from_u64(ENCODING_MODE.load(std::sync::atomic::Ordering::Relaxed))
-}
-
 pub const DEFAULT_ISA_VERSION: ISAVersion = ISAVersion(2);
 
 const _: () = if DEFAULT_ISA_VERSION.0 != zkevm_opcode_defs::DEFAULT_ISA_VERSION.0 {
     panic!()
 } else {
 };
-
-pub static ISA_VERSION_U64: AtomicU64 = AtomicU64::new(DEFAULT_ISA_VERSION.0 as u64);
-
-pub fn set_isa_version(value: ISAVersion) {
-    ISA_VERSION_U64.store(value.0 as u64, std::sync::atomic::Ordering::SeqCst);
-}
-
-pub fn get_isa_version() -> ISAVersion {
-    ISAVersion(ISA_VERSION_U64.load(std::sync::atomic::Ordering::Relaxed) as u8)
-}
@@ -18,6 +18,42 @@ pub struct Arguments {
     /// Output file, stdout if not present
     #[structopt(parse(from_os_str))]
     pub output: Option<PathBuf>,
+
+    /// Run the static checks instead of emitting the bytecode
+    #[structopt(long = "lint")]
+    pub lint: bool,
+
+    /// Lint the contract as a kernel-space (system) one
+    #[structopt(long = "kernel-mode")]
+    pub kernel_mode: bool,
+
+    /// Lint the contract as one executed in static context
+    #[structopt(long = "static-context")]
+    pub static_context: bool,
+
+    /// Export the control flow graph in the given format instead of emitting the bytecode
+    #[structopt(long = "cfg", possible_values = &["dot", "json"])]
+    pub cfg: Option<String>,
+
+    /// Treat the input as hex encoded bytecode, only supported together with `--cfg`
+    #[structopt(long = "bytecode-input")]
+    pub bytecode_input: bool,
+
+    /// The bytecode encoding mode
+    #[structopt(
+        long = "encoding-mode",
+        possible_values = &["production", "testing"],
+        default_value = "production"
+    )]
+    pub encoding_mode: String,
+
+    /// The ISA version to encode the instructions for, the latest supported one if not present
+    #[structopt(long = "isa-version")]
+    pub isa_version: Option<u8>,
+
+    /// The maximum code size in 32-byte words, the encoding mode limit if not present
+    #[structopt(long = "max-code-size")]
+    pub max_code_size: Option<u64>,
 }
 
 impl Arguments {
@@ -4,10 +4,14 @@
 
 pub mod arguments;
 
-use zkevm_opcode_defs::decoding::EncodingModeProduction;
+use zkevm_assembly::{
+    AssemblerConfig, ControlFlowGraph, ISAVersion, LintConfig, RunningVmEncodingMode, Severity,
+    DEFAULT_ISA_VERSION,
+};
 
 use self::arguments::Arguments;
-use std::{convert::TryFrom, io::Write};
+use std::io::Write;
+use std::path::PathBuf;
 
 ///
 /// The application entry point.
@@ -17,11 +21,71 @@ fn main() {
 
     let args = Arguments::new();
 
+    if args.bytecode_input {
+        let cfg_format = args
+            .cfg
+            .as_deref()
+            .expect("Bytecode input is only supported for the control flow graph export");
+        let text = std::fs::read_to_string(&args.input).expect("Bytecode file reading");
+        let raw = hex::decode(text.trim().trim_start_matches("0x")).expect("Must be a hex string");
+        assert!(
+            raw.len() % 32 == 0,
+            "bytecode length must be a multiple of 32 bytes"
+        );
+        let bytecode: Vec<[u8; 32]> = raw
+            .chunks_exact(32)
+            .map(|el| el.try_into().unwrap())
+            .collect();
+        let cfg = ControlFlowGraph::from_bytecode(&bytecode);
+        write_output(args.output, render_cfg(&cfg, cfg_format).as_bytes());
+
+        return;
+    }
+
+    let config = AssemblerConfig {
+        encoding_mode: match args.encoding_mode.as_str() {
+            "production" => RunningVmEncodingMode::Production,
+            "testing" => RunningVmEncodingMode::Testing,
+            _ => unreachable!("encoding mode is validated by the arguments parser"),
+        },
+        isa_version: args
+            .isa_version
+            .map(ISAVersion)
+            .unwrap_or(DEFAULT_ISA_VERSION),
+        max_code_size_in_words: args.max_code_size,
+    };
     let mut assembly =
-        zkevm_assembly::Assembly::try_from(args.input).expect("Assembly file reading");
+        zkevm_assembly::Assembly::from_file(args.input, config).expect("Assembly file reading");
+
+    if args.lint {
+        let config = LintConfig {
+            kernel_mode: args.kernel_mode,
+            static_context: args.static_context,
+            ..LintConfig::default()
+        };
+        let diagnostics = assembly.lint(&config).expect("Must link the assembly");
+        for diagnostic in diagnostics.iter() {
+            println!("{}", diagnostic);
+        }
+        if diagnostics
+            .iter()
+            .any(|el| el.severity() == Severity::Error)
+        {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Some(cfg_format) = args.cfg.as_deref() {
+        let cfg = ControlFlowGraph::from_assembly(&assembly).expect("Must link the assembly");
+        write_output(args.output, render_cfg(&cfg, cfg_format).as_bytes());
+
+        return;
+    }
 
     let serialized = assembly
-        .compile_to_bytecode_for_mode::<8, EncodingModeProduction>()
+        .compile_to_bytecode()
         .expect("Must compile the bytecode");
 
     let mut pretty_bytecode = String::with_capacity((64 + 2) * serialized.len() + 100);
@@ -30,17 +94,29 @@ fn main() {
             .expect("Error occurred while trying to write in String");
     }
 
-    if let Some(path) = args.output {
+    write_output(args.output, pretty_bytecode.as_bytes());
+}
+
+fn render_cfg(cfg: &ControlFlowGraph, format: &str) -> String {
+    match format {
+        "dot" => cfg.to_dot(),
+        "json" => serde_json::to_string_pretty(&cfg.to_json()).expect("must serialize"),
+        _ => unreachable!("format is validated by the arguments parser"),
+    }
+}
+
+fn write_output(output: Option<PathBuf>, content: &[u8]) {
+    if let Some(path) = output {
         let mut file = std::fs::OpenOptions::new()
             .create(true)
             .write(true)
+            .truncate(true)
             .open(path)
             .expect("can not open an output file");
-        file.write_all(pretty_bytecode.as_bytes())
-            .expect("can not write to file");
+        file.write_all(content).expect("can not write to file");
     } else {
         std::io::stdout()
-            .write_all(pretty_bytecode.as_bytes())
+            .write_all(content)
             .expect("can not write to stdout");
     }
 }
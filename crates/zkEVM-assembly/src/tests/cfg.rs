@@ -0,0 +1,87 @@
+use crate::assembly::cfg::*;
+use crate::assembly::*;
+use std::convert::TryFrom;
+use zkevm_opcode_defs::decoding::encoding_mode_production::EncodingModeProduction;
+
+const DIAMOND_ASSEMBLY: &str = r#"
+    .text
+    .globl  __entry
+    __entry:
+        sub! r1, r0, r0
+        jump.eq @__left
+        add 1, r0, r2
+        jump @__join
+    __left:
+        add 2, r0, r2
+    __join:
+        near_call r0, @__callee, @__eh
+        ret.ok r0
+    __callee:
+        ret.ok r0
+    __eh:
+        ret.panic r0
+    "#;
+
+fn edge_kinds_from(cfg: &ControlFlowGraph, block: usize) -> Vec<EdgeKind> {
+    let mut result: Vec<_> = cfg
+        .edges
+        .iter()
+        .filter(|el| el.from == block)
+        .map(|el| el.kind)
+        .collect();
+    result.sort();
+
+    result
+}
+
+#[test]
+fn test_diamond_cfg() {
+    let assembly = Assembly::try_from(DIAMOND_ASSEMBLY.to_owned()).unwrap();
+    let cfg = ControlFlowGraph::from_assembly(&assembly).unwrap();
+
+    let starts: Vec<_> = cfg.blocks.iter().map(|el| el.start_pc).collect();
+    assert_eq!(starts, vec![0, 2, 4, 5, 6, 7, 8]);
+    assert_eq!(cfg.blocks[0].labels, vec!["__entry".to_owned()]);
+    assert_eq!(cfg.blocks[0].condition, zkevm_opcode_defs::Condition::Eq);
+    assert_eq!(
+        edge_kinds_from(&cfg, 0),
+        vec![EdgeKind::Fallthrough, EdgeKind::Jump]
+    );
+    assert_eq!(
+        edge_kinds_from(&cfg, 3),
+        vec![
+            EdgeKind::NearCall,
+            EdgeKind::Continuation,
+            EdgeKind::ExceptionHandler
+        ]
+    );
+    assert_eq!(cfg.blocks[0].instructions[1].text, "jump.eq @__left");
+
+    let dot = cfg.to_dot();
+    assert!(dot.starts_with("digraph cfg {"));
+    assert!(dot.contains("block3 -> block6 [label=\"exception_handler\""));
+
+    let json = cfg.to_json();
+    assert_eq!(json["blocks"].as_array().unwrap().len(), 7);
+    assert_eq!(json["edges"].as_array().unwrap().len(), cfg.edges.len());
+}
+
+#[test]
+fn test_cfg_from_bytecode_matches_assembly() {
+    let mut assembly = Assembly::try_from(DIAMOND_ASSEMBLY.to_owned()).unwrap();
+    let from_assembly = ControlFlowGraph::from_assembly(&assembly).unwrap();
+    let bytecode = assembly
+        .compile_to_bytecode_for_mode::<8, EncodingModeProduction>()
+        .unwrap();
+    let from_bytecode = ControlFlowGraph::from_bytecode(&bytecode);
+
+    assert_eq!(from_assembly.edges, from_bytecode.edges);
+    for (a, b) in from_assembly
+        .blocks
+        .iter()
+        .zip(from_bytecode.blocks.iter())
+    {
+        assert_eq!((a.start_pc, a.end_pc), (b.start_pc, b.end_pc));
+        assert!(b.labels.is_empty());
+    }
+}
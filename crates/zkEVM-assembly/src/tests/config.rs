@@ -0,0 +1,78 @@
+use crate::assembly::*;
+use crate::error::InstructionReadError;
+use crate::{AssemblerConfig, AssemblyParseError, ISAVersion};
+use zkevm_opcode_defs::decoding::{EncodingModeProduction, EncodingModeTesting};
+
+const ASSEMBLY: &str = r#"
+    .text
+    .globl  __entry
+    __entry:
+        add 1, r0, r1
+        add 2, r1, r2
+        sub! r2, r1, r3
+        jump.eq @__exit
+        add r3, r0, r4
+    __exit:
+        ret.ok r0
+    "#;
+
+fn compile(config: AssemblerConfig) -> Vec<[u8; 32]> {
+    let mut assembly = Assembly::from_string(ASSEMBLY.to_owned(), None, config).unwrap();
+    assembly.compile_to_bytecode().unwrap()
+}
+
+#[test]
+fn test_concurrent_compilation_in_different_modes() {
+    let production = std::thread::spawn(|| compile(AssemblerConfig::default()));
+    let testing = std::thread::spawn(|| compile(AssemblerConfig::testing()));
+    let production = production.join().unwrap();
+    let testing = testing.join().unwrap();
+
+    let mut assembly =
+        Assembly::from_string(ASSEMBLY.to_owned(), None, AssemblerConfig::default()).unwrap();
+    let expected_production = assembly
+        .clone()
+        .compile_to_bytecode_for_mode::<8, EncodingModeProduction>()
+        .unwrap();
+    let expected_testing = assembly
+        .compile_to_bytecode_for_mode::<16, EncodingModeTesting>()
+        .unwrap();
+
+    assert_eq!(production, expected_production);
+    assert_eq!(testing, expected_testing);
+    assert_ne!(production.len(), testing.len());
+}
+
+#[test]
+fn test_max_code_size() {
+    let config = AssemblerConfig {
+        max_code_size_in_words: Some(1),
+        ..AssemblerConfig::default()
+    };
+    let mut assembly = Assembly::from_string(ASSEMBLY.to_owned(), None, config).unwrap();
+    let result = assembly.compile_to_bytecode();
+    assert!(
+        matches!(result, Err(InstructionReadError::CodeIsTooLong(1, _))),
+        "{:?}",
+        result
+    );
+
+    let config = AssemblerConfig {
+        max_code_size_in_words: Some(64),
+        ..AssemblerConfig::default()
+    };
+    assert!(!compile(config).is_empty());
+}
+
+#[test]
+fn test_unsupported_isa_version() {
+    let config = AssemblerConfig {
+        isa_version: ISAVersion(crate::DEFAULT_ISA_VERSION.0 + 1),
+        ..AssemblerConfig::default()
+    };
+    let result = Assembly::from_string(ASSEMBLY.to_owned(), None, config);
+    assert_eq!(
+        result.unwrap_err(),
+        AssemblyParseError::UnsupportedIsaVersion(crate::DEFAULT_ISA_VERSION.0 + 1)
+    );
+}
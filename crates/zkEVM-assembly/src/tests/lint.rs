@@ -0,0 +1,150 @@
+use crate::assembly::lint::*;
+use crate::assembly::*;
+use std::convert::TryFrom;
+
+fn lint(asm_text: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let assembly = Assembly::try_from(asm_text.to_owned()).unwrap();
+    assembly.lint(config).unwrap()
+}
+
+fn kinds(diagnostics: &[Diagnostic]) -> Vec<DiagnosticKind> {
+    diagnostics.iter().map(|el| el.kind).collect()
+}
+
+#[test]
+fn test_clean_code() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        add 1, r0, r3
+        near_call r0, @__callee, @__eh
+        ret.ok r0
+    __callee:
+        nop stack+=[2]
+        add r3, r0, stack-[1]
+        ret.ok r0
+    __eh:
+        ret.panic r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_kernel_and_static_restrictions() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        context.inc_tx_num
+        ret.ok r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert_eq!(kinds(&diagnostics), vec![DiagnosticKind::KernelOnlyOpcode]);
+    assert_eq!(diagnostics[0].severity(), Severity::Error);
+    assert_eq!(diagnostics[0].line, Some(4));
+
+    let config = LintConfig {
+        kernel_mode: true,
+        static_context: true,
+        ..LintConfig::default()
+    };
+    let diagnostics = lint(asm_text, &config);
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![DiagnosticKind::StaticContextViolation]
+    );
+}
+
+#[test]
+fn test_unreachable_label() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        add 1, r0, r1
+        ret.ok r0
+    __dead:
+        add 2, r0, r1
+        ret.ok r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert_eq!(kinds(&diagnostics), vec![DiagnosticKind::UnreachableLabel]);
+    assert!(diagnostics[0].message.contains("__dead"));
+}
+
+#[test]
+fn test_address_taken_label_is_reachable() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        add @__target, r0, r4
+        jump r4
+    __target:
+        ret.ok r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_uninitialized_register_read() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        add r13, r0, r1
+        add r1, r0, r2
+        ret.ok r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![DiagnosticKind::UninitializedRegisterRead]
+    );
+    assert!(diagnostics[0].message.contains("r13"));
+}
+
+#[test]
+fn test_stack_pointer_imbalance() {
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        jump.eq @__skip
+        nop stack+=[2]
+    __skip:
+        ret.ok r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![DiagnosticKind::StackPointerImbalance]
+    );
+}
+
+#[test]
+fn test_stack_pointer_underflow() {
+    // both paths agree on the offset, but the callee pops more than it has pushed
+    let asm_text = r#"
+    .text
+    .globl  __entry
+    __entry:
+        near_call r0, @__callee, @__eh
+        ret.ok r0
+    __callee:
+        nop stack+=[1]
+        add stack-=[2], r0, r1
+        ret.ok r0
+    __eh:
+        ret.panic r0
+    "#;
+    let diagnostics = lint(asm_text, &LintConfig::default());
+    assert_eq!(
+        kinds(&diagnostics),
+        vec![DiagnosticKind::StackPointerImbalance]
+    );
+    assert_eq!(diagnostics[0].line, Some(8));
+}
@@ -1,4 +1,7 @@
 // mod assembly;
 // mod binary;
 
+mod cfg;
+mod config;
+mod lint;
 mod new_assembly;
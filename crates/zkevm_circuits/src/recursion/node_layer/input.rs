@@ -8,7 +8,9 @@ use boojum::gadgets::traits::auxiliary::PrettyComparison;
 use boojum::gadgets::{
     boolean::Boolean,
     traits::{
-        allocatable::*, encodable::CircuitVarLengthEncodable, selectable::Selectable,
+        allocatable::*,
+        encodable::{CircuitVarLengthEncodable, WitnessVarLengthEncodable},
+        selectable::Selectable,
         witnessable::WitnessHookable,
     },
 };
@@ -21,7 +23,14 @@ use crate::recursion::leaf_layer::input::RecursionLeafParameters;
 use boojum::field::FieldExtension;
 use boojum::serde_utils::BigArraySerde;
 
-#[derive(Derivative, CSAllocatable, CSSelectable, CSVarLengthEncodable, WitnessHookable)]
+#[derive(
+    Derivative,
+    CSAllocatable,
+    CSSelectable,
+    CSVarLengthEncodable,
+    WitnessHookable,
+    WitVarLengthEncodable,
+)]
 #[derivative(Clone, Copy, Debug)]
 #[DerivePrettyComparison("true")]
 pub struct RecursionNodeInput<F: SmallField> {
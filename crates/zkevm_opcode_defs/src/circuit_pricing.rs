@@ -0,0 +1,549 @@
+//! Derivation of the circuit prices in ergs from the capacities of the circuits.
+//! Used by `circuit_pricing_generator` to produce `circuit_prices.rs`.
+
+use crate::circuit_prices::*;
+use crate::system_params::{
+    ERGS_PER_CIRCUIT, INITIAL_STORAGE_WRITE_PUBDATA_BYTES, MAX_PUBDATA_PER_BLOCK,
+    MAX_TX_ERGS_LIMIT, REPEATED_STORAGE_WRITE_PUBDATA_BYTES,
+};
+
+/// The number of input "units" the corresponding circuits could take.
+/// It is assumed that the actual capacity of the circuits
+/// below is greater or equal to the values provided there.
+/// Some margin is suggested to not conduct a reprice upon every minor prover change.
+pub const CYCLES_PER_VM_SNAPSHOT: u32 = 23000;
+pub const CYCLES_PER_RAM_PERMUTATION: u32 = 260000;
+pub const CYCLES_PER_CODE_DECOMMITTER: u32 = 12100;
+pub const CYCLES_PER_STORAGE_APPLICATION: u32 = 118;
+pub const CYCLES_PER_KECCAK256_CIRCUIT: u32 = 2050;
+pub const CYCLES_PER_SHA256_CIRCUIT: u32 = 11500;
+pub const CYCLES_PER_ECRECOVER_CIRCUIT: u32 = 72;
+pub const CYCLES_FOR_CODE_DECOMMITTER_SORTER: u32 = 192500;
+pub const CYCLES_FOR_LOG_DEMUXER: u32 = 101500;
+pub const CYCLES_FOR_STORAGE_SORTER: u32 = 79000;
+pub const CYCLES_FOR_TRANSIENT_STORAGE_SORTER: u32 = 50875;
+pub const CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER: u32 = 88000;
+
+/// This kinds of circuit will always remain single-instance
+pub const LIMIT_FOR_L1_MESSAGES_MERKLIZER: u32 = 512;
+pub const LIMIT_FOR_INITIAL_WRITES_PUBDATA_HASHER: u32 = 4600;
+pub const LIMIT_FOR_REPEATED_WRITES_PUBDATA_HASHER: u32 = 7400;
+
+///
+/// Capacities of the circuits the prices are derived from. Field names follow the ones of
+/// `GeometryConfig`, so its serialized form can be read directly, and the fields that it
+/// does not have are taken from the defaults.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct CircuitCapacities {
+    pub cycles_per_vm_snapshot: u32,
+    pub cycles_per_ram_permutation: u32,
+    pub cycles_per_code_decommitter: u32,
+    pub cycles_per_storage_application: u32,
+    pub cycles_per_keccak256_circuit: u32,
+    pub cycles_per_sha256_circuit: u32,
+    pub cycles_per_ecrecover_circuit: u32,
+    pub cycles_code_decommitter_sorter: u32,
+    pub cycles_per_log_demuxer: u32,
+    pub cycles_per_storage_sorter: u32,
+    pub cycles_per_transient_storage_sorter: u32,
+    pub cycles_per_events_or_l1_messages_sorter: u32,
+    pub limit_for_l1_messages_pudata_hasher: u32,
+    pub limit_for_initial_writes_pubdata_hasher: u32,
+    pub limit_for_repeated_writes_pubdata_hasher: u32,
+}
+
+impl Default for CircuitCapacities {
+    fn default() -> Self {
+        Self {
+            cycles_per_vm_snapshot: CYCLES_PER_VM_SNAPSHOT,
+            cycles_per_ram_permutation: CYCLES_PER_RAM_PERMUTATION,
+            cycles_per_code_decommitter: CYCLES_PER_CODE_DECOMMITTER,
+            cycles_per_storage_application: CYCLES_PER_STORAGE_APPLICATION,
+            cycles_per_keccak256_circuit: CYCLES_PER_KECCAK256_CIRCUIT,
+            cycles_per_sha256_circuit: CYCLES_PER_SHA256_CIRCUIT,
+            cycles_per_ecrecover_circuit: CYCLES_PER_ECRECOVER_CIRCUIT,
+            cycles_code_decommitter_sorter: CYCLES_FOR_CODE_DECOMMITTER_SORTER,
+            cycles_per_log_demuxer: CYCLES_FOR_LOG_DEMUXER,
+            cycles_per_storage_sorter: CYCLES_FOR_STORAGE_SORTER,
+            cycles_per_transient_storage_sorter: CYCLES_FOR_TRANSIENT_STORAGE_SORTER,
+            cycles_per_events_or_l1_messages_sorter: CYCLES_FOR_EVENTS_OR_L1_MESSAGES_SORTER,
+            limit_for_l1_messages_pudata_hasher: LIMIT_FOR_L1_MESSAGES_MERKLIZER,
+            limit_for_initial_writes_pubdata_hasher: LIMIT_FOR_INITIAL_WRITES_PUBDATA_HASHER,
+            limit_for_repeated_writes_pubdata_hasher: LIMIT_FOR_REPEATED_WRITES_PUBDATA_HASHER,
+        }
+    }
+}
+
+impl CircuitCapacities {
+    ///
+    /// Shrinks every capacity by `safety_margin_percent`, so the small capacity regressions
+    /// of the prover do not require a reprice.
+    ///
+    pub fn with_safety_margin(&self, safety_margin_percent: u32) -> Self {
+        assert!(
+            safety_margin_percent < 100,
+            "safety margin must be less than 100%"
+        );
+        let shrink = |capacity: u32| -> u32 {
+            let shrinked = (capacity as u64) * (100 - safety_margin_percent as u64) / 100;
+            std::cmp::max(shrinked as u32, 1)
+        };
+
+        let mut result = *self;
+        for capacity in result.capacities_mut() {
+            *capacity = shrink(*capacity);
+        }
+
+        result
+    }
+
+    /// Zero capacities belong to the circuits that the geometry does not have yet (e.g. the
+    /// transient storage sorter before 1.5.0), those are taken from the defaults.
+    fn with_defaults_for_missing_circuits(&self) -> Self {
+        let mut default = Self::default();
+        let mut result = *self;
+        for (capacity, default) in result
+            .capacities_mut()
+            .into_iter()
+            .zip(default.capacities_mut())
+        {
+            if *capacity == 0 {
+                *capacity = *default;
+            }
+        }
+
+        result
+    }
+
+    /// All the capacities, destructured so that a new field can't be skipped.
+    fn capacities_mut(&mut self) -> [&mut u32; 15] {
+        let Self {
+            cycles_per_vm_snapshot,
+            cycles_per_ram_permutation,
+            cycles_per_code_decommitter,
+            cycles_per_storage_application,
+            cycles_per_keccak256_circuit,
+            cycles_per_sha256_circuit,
+            cycles_per_ecrecover_circuit,
+            cycles_code_decommitter_sorter,
+            cycles_per_log_demuxer,
+            cycles_per_storage_sorter,
+            cycles_per_transient_storage_sorter,
+            cycles_per_events_or_l1_messages_sorter,
+            limit_for_l1_messages_pudata_hasher,
+            limit_for_initial_writes_pubdata_hasher,
+            limit_for_repeated_writes_pubdata_hasher,
+        } = self;
+
+        [
+            cycles_per_vm_snapshot,
+            cycles_per_ram_permutation,
+            cycles_per_code_decommitter,
+            cycles_per_storage_application,
+            cycles_per_keccak256_circuit,
+            cycles_per_sha256_circuit,
+            cycles_per_ecrecover_circuit,
+            cycles_code_decommitter_sorter,
+            cycles_per_log_demuxer,
+            cycles_per_storage_sorter,
+            cycles_per_transient_storage_sorter,
+            cycles_per_events_or_l1_messages_sorter,
+            limit_for_l1_messages_pudata_hasher,
+            limit_for_initial_writes_pubdata_hasher,
+            limit_for_repeated_writes_pubdata_hasher,
+        ]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CircuitPricesItem {
+    Comment(&'static str),
+    Price(&'static str, u32),
+}
+
+/// Returns ceil(a/b)
+fn ceil_div(name: &str, a: u32, b: u32) -> u32 {
+    assert!(b != 0, "capacity for {} is zero", name);
+    a.div_ceil(b)
+}
+
+fn price(name: &'static str, ergs: u32, capacity: u32) -> CircuitPricesItem {
+    CircuitPricesItem::Price(name, ceil_div(name, ergs, capacity))
+}
+
+pub fn compute_circuit_prices(capacities: &CircuitCapacities) -> Vec<CircuitPricesItem> {
+    use CircuitPricesItem::*;
+
+    let max_possible_initial_bytes_circuit_wise = capacities
+        .limit_for_initial_writes_pubdata_hasher
+        * (INITIAL_STORAGE_WRITE_PUBDATA_BYTES as u32);
+    let min_price_for_initial_pubdata_write =
+        if MAX_PUBDATA_PER_BLOCK <= max_possible_initial_bytes_circuit_wise {
+            // The cost of pubdata alone would not let the users to run out of this circuit
+            // before the end of the transaction. The users can safely pay nothing for the DDoS security.
+            0
+        } else {
+            ceil_div(
+                "INITIAL_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+                MAX_TX_ERGS_LIMIT,
+                capacities.limit_for_initial_writes_pubdata_hasher,
+            )
+        };
+
+    let max_possible_repeated_bytes_circuit_wise = capacities
+        .limit_for_repeated_writes_pubdata_hasher
+        * (REPEATED_STORAGE_WRITE_PUBDATA_BYTES as u32);
+    let min_price_for_repeated_pubdata_write =
+        if MAX_PUBDATA_PER_BLOCK <= max_possible_repeated_bytes_circuit_wise {
+            // The cost of pubdata alone would not let the users to run out of this circuit
+            // before the end of the transaction. The users can safely pay nothing for the DDoS security.
+            0
+        } else {
+            ceil_div(
+                "REPEATED_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+                MAX_TX_ERGS_LIMIT,
+                capacities.limit_for_repeated_writes_pubdata_hasher,
+            )
+        };
+
+    vec![
+        price(
+            "VM_CYCLE_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_vm_snapshot,
+        ),
+        price(
+            "RAM_PERMUTATION_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_ram_permutation,
+        ),
+        // Each round of decommitter outputs 64 bytes of the code,
+        // while the user will pay for each word.
+        price(
+            "CODE_DECOMMITMENT_COST_PER_WORD_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            2 * capacities.cycles_per_code_decommitter,
+        ),
+        price(
+            "STORAGE_APPLICATION_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_storage_application,
+        ),
+        price(
+            "CODE_DECOMMITTER_SORTER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_code_decommitter_sorter,
+        ),
+        price(
+            "LOG_DEMUXER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_log_demuxer,
+        ),
+        price(
+            "STORAGE_SORTER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_storage_sorter,
+        ),
+        price(
+            "TRANSIENT_STORE_CHECKER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_transient_storage_sorter,
+        ),
+        price(
+            "EVENTS_OR_L1_MESSAGES_SORTER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_events_or_l1_messages_sorter,
+        ),
+        price(
+            "INITIAL_WRITES_PUBDATA_HASHER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.limit_for_initial_writes_pubdata_hasher,
+        ),
+        price(
+            "REPEATED_WRITES_PUBDATA_HASHER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.limit_for_repeated_writes_pubdata_hasher,
+        ),
+        price(
+            "CODE_DECOMMITMENT_SORTER_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_code_decommitter_sorter,
+        ),
+        Comment("The following circuits are single-instance and so the provided prices are just minimal prices to preserve DDoS safety"),
+        price(
+            "L1_MESSAGE_MIN_COST_IN_ERGS",
+            MAX_TX_ERGS_LIMIT,
+            capacities.limit_for_l1_messages_pudata_hasher,
+        ),
+        Price(
+            "INITIAL_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+            min_price_for_initial_pubdata_write,
+        ),
+        Price(
+            "REPEATED_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+            min_price_for_repeated_pubdata_write,
+        ),
+        Comment("Equals to max(INITIAL_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS, REPEATED_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS)"),
+        Price(
+            "STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS",
+            std::cmp::max(min_price_for_initial_pubdata_write, min_price_for_repeated_pubdata_write),
+        ),
+        Comment("The following constants should not be used in the VM directly, but only in Solidity wrappers"),
+        price(
+            "KECCAK256_CIRCUIT_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_keccak256_circuit,
+        ),
+        price(
+            "SHA256_CIRCUIT_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_sha256_circuit,
+        ),
+        price(
+            "ECRECOVER_CIRCUIT_COST_IN_ERGS",
+            ERGS_PER_CIRCUIT,
+            capacities.cycles_per_ecrecover_circuit,
+        ),
+    ]
+}
+
+///
+/// Renders the prices in the format of `circuit_prices.rs`.
+///
+pub fn render_circuit_prices(prices: &[CircuitPricesItem]) -> String {
+    let mut result = vec![
+        "// This file is auto-generated, do not edit it manually\n".to_owned(),
+        "// Any changes to this file require system upgrade!\n\n".to_owned(),
+    ];
+    for item in prices.iter() {
+        match item {
+            CircuitPricesItem::Comment(comment) => result.push(format!("\n// {}\n", comment)),
+            CircuitPricesItem::Price(name, value) => {
+                result.push(format!("pub const {}: u32 = {};\n", name, value))
+            }
+        }
+    }
+
+    result.concat()
+}
+
+///
+/// Prices that are currently compiled in from `circuit_prices.rs`.
+///
+pub fn committed_circuit_prices() -> Vec<(&'static str, u32)> {
+    vec![
+        ("VM_CYCLE_COST_IN_ERGS", VM_CYCLE_COST_IN_ERGS),
+        ("RAM_PERMUTATION_COST_IN_ERGS", RAM_PERMUTATION_COST_IN_ERGS),
+        (
+            "CODE_DECOMMITMENT_COST_PER_WORD_IN_ERGS",
+            CODE_DECOMMITMENT_COST_PER_WORD_IN_ERGS,
+        ),
+        (
+            "STORAGE_APPLICATION_COST_IN_ERGS",
+            STORAGE_APPLICATION_COST_IN_ERGS,
+        ),
+        (
+            "CODE_DECOMMITTER_SORTER_COST_IN_ERGS",
+            CODE_DECOMMITTER_SORTER_COST_IN_ERGS,
+        ),
+        ("LOG_DEMUXER_COST_IN_ERGS", LOG_DEMUXER_COST_IN_ERGS),
+        ("STORAGE_SORTER_COST_IN_ERGS", STORAGE_SORTER_COST_IN_ERGS),
+        (
+            "TRANSIENT_STORE_CHECKER_COST_IN_ERGS",
+            TRANSIENT_STORE_CHECKER_COST_IN_ERGS,
+        ),
+        (
+            "EVENTS_OR_L1_MESSAGES_SORTER_COST_IN_ERGS",
+            EVENTS_OR_L1_MESSAGES_SORTER_COST_IN_ERGS,
+        ),
+        (
+            "INITIAL_WRITES_PUBDATA_HASHER_COST_IN_ERGS",
+            INITIAL_WRITES_PUBDATA_HASHER_COST_IN_ERGS,
+        ),
+        (
+            "REPEATED_WRITES_PUBDATA_HASHER_COST_IN_ERGS",
+            REPEATED_WRITES_PUBDATA_HASHER_COST_IN_ERGS,
+        ),
+        (
+            "CODE_DECOMMITMENT_SORTER_COST_IN_ERGS",
+            CODE_DECOMMITMENT_SORTER_COST_IN_ERGS,
+        ),
+        ("L1_MESSAGE_MIN_COST_IN_ERGS", L1_MESSAGE_MIN_COST_IN_ERGS),
+        (
+            "INITIAL_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+            INITIAL_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS,
+        ),
+        (
+            "REPEATED_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS",
+            REPEATED_WRITES_PUBDATA_HASHER_MIN_COST_IN_ERGS,
+        ),
+        (
+            "STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS",
+            STORAGE_WRITE_HASHER_MIN_COST_IN_ERGS,
+        ),
+        (
+            "KECCAK256_CIRCUIT_COST_IN_ERGS",
+            KECCAK256_CIRCUIT_COST_IN_ERGS,
+        ),
+        ("SHA256_CIRCUIT_COST_IN_ERGS", SHA256_CIRCUIT_COST_IN_ERGS),
+        (
+            "ECRECOVER_CIRCUIT_COST_IN_ERGS",
+            ECRECOVER_CIRCUIT_COST_IN_ERGS,
+        ),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitPriceDiff {
+    pub name: &'static str,
+    pub committed: Option<u32>,
+    pub derived: Option<u32>,
+}
+
+impl CircuitPriceDiff {
+    pub fn is_changed(&self) -> bool {
+        self.committed != self.derived
+    }
+}
+
+impl std::fmt::Display for CircuitPriceDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.committed, self.derived) {
+            (Some(committed), Some(derived)) if committed == derived => {
+                write!(f, "  {}: {}", self.name, committed)
+            }
+            (Some(committed), Some(derived)) => {
+                let change = if committed == 0 {
+                    "new non-zero price".to_owned()
+                } else {
+                    format!(
+                        "{:+.2}%",
+                        (derived as f64 - committed as f64) * 100.0 / committed as f64
+                    )
+                };
+                write!(
+                    f,
+                    "* {}: {} -> {} ({})",
+                    self.name, committed, derived, change
+                )
+            }
+            (Some(committed), None) => write!(f, "- {}: {}", self.name, committed),
+            (None, Some(derived)) => write!(f, "+ {}: {}", self.name, derived),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+pub fn diff_circuit_prices(
+    committed: &[(&'static str, u32)],
+    derived: &[CircuitPricesItem],
+) -> Vec<CircuitPriceDiff> {
+    let derived: Vec<(&'static str, u32)> = derived
+        .iter()
+        .filter_map(|el| match el {
+            CircuitPricesItem::Price(name, value) => Some((*name, *value)),
+            CircuitPricesItem::Comment(_) => None,
+        })
+        .collect();
+
+    let mut result = Vec::with_capacity(derived.len());
+    for (name, value) in committed.iter() {
+        let new_value = derived
+            .iter()
+            .find(|(el, _)| el == name)
+            .map(|(_, value)| *value);
+        result.push(CircuitPriceDiff {
+            name,
+            committed: Some(*value),
+            derived: new_value,
+        });
+    }
+    for (name, value) in derived.iter() {
+        if !committed.iter().any(|(el, _)| el == name) {
+            result.push(CircuitPriceDiff {
+                name,
+                committed: None,
+                derived: Some(*value),
+            });
+        }
+    }
+
+    result
+}
+
+pub fn render_circuit_prices_report(diff: &[CircuitPriceDiff]) -> String {
+    let num_changed = diff.iter().filter(|el| el.is_changed()).count();
+    let mut result = format!(
+        "{} of {} circuit prices differ from circuit_prices.rs\n",
+        num_changed,
+        diff.len()
+    );
+    for el in diff.iter() {
+        result.push_str(&el.to_string());
+        result.push('\n');
+    }
+
+    result
+}
+
+///
+/// Derives the prices from `capacities` shrunk by `safety_margin_percent`, and diffs them
+/// against the committed `circuit_prices.rs`.
+///
+pub fn derive_circuit_prices(
+    capacities: &CircuitCapacities,
+    safety_margin_percent: u32,
+) -> (Vec<CircuitPricesItem>, Vec<CircuitPriceDiff>) {
+    let capacities = capacities
+        .with_defaults_for_missing_circuits()
+        .with_safety_margin(safety_margin_percent);
+    let prices = compute_circuit_prices(&capacities);
+    let diff = diff_circuit_prices(&committed_circuit_prices(), &prices);
+
+    (prices, diff)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn default_capacities_reproduce_committed_prices() {
+        let prices = compute_circuit_prices(&CircuitCapacities::default());
+        let diff = diff_circuit_prices(&committed_circuit_prices(), &prices);
+        assert!(
+            diff.iter().all(|el| !el.is_changed()),
+            "{}",
+            render_circuit_prices_report(&diff)
+        );
+        assert_eq!(
+            render_circuit_prices(&prices),
+            include_str!("circuit_prices.rs")
+        );
+    }
+
+    #[test]
+    fn safety_margin_does_not_decrease_prices() {
+        let capacities = CircuitCapacities::default();
+        let prices_with_margin = compute_circuit_prices(&capacities.with_safety_margin(20));
+        for diff in diff_circuit_prices(&committed_circuit_prices(), &prices_with_margin) {
+            assert!(diff.derived.unwrap() >= diff.committed.unwrap(), "{}", diff);
+        }
+    }
+
+    #[test]
+    fn missing_circuits_keep_default_prices() {
+        let capacities = CircuitCapacities {
+            cycles_per_transient_storage_sorter: 0,
+            ..CircuitCapacities::default()
+        };
+        let (_, diff) = derive_circuit_prices(&capacities, 0);
+        assert!(
+            diff.iter().all(|el| !el.is_changed()),
+            "{}",
+            render_circuit_prices_report(&diff)
+        );
+    }
+}
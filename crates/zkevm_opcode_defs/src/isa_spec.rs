@@ -0,0 +1,372 @@
+//! Machine-readable description of the instruction set, derived from the opcode definitions
+//! and the decoding tables that are synthesized from them.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::*;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IsaSpec {
+    pub isa_versions: Vec<IsaVersionSpec>,
+    pub variants: Vec<OpcodeVariantSpec>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IsaVersionSpec {
+    pub version: u8,
+    pub decoding_format: String,
+    pub opcodes_table_width: usize,
+    pub max_num_variants: usize,
+    pub max_num_flags: usize,
+    pub total_description_bits: usize,
+    pub total_description_and_aux_bits: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OperandSpec {
+    /// One of `reg_only`, `reg_or_imm` or `full`.
+    pub kind: &'static str,
+    /// Concrete addressing mode, if the operand kind allows more than one.
+    pub mode: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FlagSpec {
+    pub index: usize,
+    pub name: &'static str,
+    pub value: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagsEffect {
+    /// `lt`/`of`, `eq` and `gt` flags are left untouched.
+    Preserve,
+    /// Flags are recomputed from the result of the operation.
+    Set,
+    /// Flags are cleared.
+    Reset,
+    /// Flags are cleared, then `lt`/`of` is set.
+    ResetAndSetOverflow,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VariantEncoding {
+    pub isa_version: u8,
+    /// Index in the opcodes table, that is the value of the lowest `OPCODES_TABLE_WIDTH` bits
+    /// of the instruction word.
+    pub table_index: usize,
+    /// Bitspread of the opcode properties as used by the circuits.
+    pub properties_bitmask: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OpcodeVariantSpec {
+    pub opcode: &'static str,
+    pub variant: String,
+    pub opcode_index: usize,
+    pub variant_index: usize,
+    pub src0: OperandSpec,
+    pub dst0: OperandSpec,
+    pub input_operands: Vec<&'static str>,
+    pub output_operands: Vec<&'static str>,
+    pub flags: Vec<FlagSpec>,
+    pub flags_effect: FlagsEffect,
+    pub ergs_price: u32,
+    pub requires_kernel_mode: bool,
+    pub can_be_used_in_static_context: bool,
+    pub is_explicit_panic: bool,
+    pub src0_can_be_pointer: bool,
+    pub src1_can_be_pointer: bool,
+    pub introduced_in: u8,
+    pub encodings: Vec<VariantEncoding>,
+}
+
+fn opcode_and_variant_names(opcode: &Opcode) -> (&'static str, String) {
+    match opcode {
+        Opcode::Invalid(_) => ("invalid", "invalid".to_owned()),
+        Opcode::Nop(_) => ("nop", "nop".to_owned()),
+        Opcode::Add(inner) => ("add", format!("{:?}", inner)),
+        Opcode::Sub(inner) => ("sub", format!("{:?}", inner)),
+        Opcode::Mul(_) => ("mul", "mul".to_owned()),
+        Opcode::Div(_) => ("div", "div".to_owned()),
+        Opcode::Jump(_) => ("jump", "jump".to_owned()),
+        Opcode::Context(inner) => ("context", format!("{:?}", inner)),
+        Opcode::Shift(inner) => ("shift", format!("{:?}", inner)),
+        Opcode::Binop(inner) => ("binop", format!("{:?}", inner)),
+        Opcode::Ptr(inner) => ("ptr", format!("{:?}", inner)),
+        Opcode::NearCall(_) => ("near_call", "near_call".to_owned()),
+        Opcode::Log(inner) => ("log", format!("{:?}", inner)),
+        Opcode::FarCall(inner) => ("far_call", format!("{:?}", inner)),
+        Opcode::Ret(inner) => ("ret", format!("{:?}", inner)),
+        Opcode::UMA(inner) => ("uma", format!("{:?}", inner)),
+    }
+}
+
+fn non_exclusive_flag_names(opcode: &Opcode) -> [Option<&'static str>; NUM_NON_EXCLUSIVE_FLAGS] {
+    match opcode {
+        Opcode::Add(_) | Opcode::Mul(_) | Opcode::Binop(_) => [Some("set_flags"), None],
+        Opcode::Sub(_) | Opcode::Div(_) | Opcode::Shift(_) => {
+            [Some("set_flags"), Some("swap_operands")]
+        }
+        Opcode::Ptr(_) => [Some("swap_operands"), None],
+        Opcode::Ret(_) => [Some("to_label"), None],
+        Opcode::FarCall(_) => [Some("static"), Some("shard")],
+        Opcode::UMA(_) => [Some("increment"), None],
+        Opcode::Log(LogOpcode::ToL1Message) | Opcode::Log(LogOpcode::Event) => {
+            [Some("first_message"), None]
+        }
+        _ => [None, None],
+    }
+}
+
+fn flags_effect(variant: &OpcodeVariant) -> FlagsEffect {
+    match variant.opcode {
+        Opcode::Add(_)
+        | Opcode::Sub(_)
+        | Opcode::Mul(_)
+        | Opcode::Div(_)
+        | Opcode::Shift(_)
+        | Opcode::Binop(_) => {
+            if variant.flags[SET_FLAGS_FLAG_IDX] {
+                FlagsEffect::Set
+            } else {
+                FlagsEffect::Preserve
+            }
+        }
+        Opcode::NearCall(_) | Opcode::FarCall(_) => FlagsEffect::Reset,
+        Opcode::Ret(RetOpcode::Panic) => FlagsEffect::ResetAndSetOverflow,
+        Opcode::Ret(_) => FlagsEffect::Reset,
+        _ => FlagsEffect::Preserve,
+    }
+}
+
+fn operand_kind(operand: &Operand) -> &'static str {
+    match operand {
+        Operand::RegOnly => "reg_only",
+        Operand::RegOrImm(_) => "reg_or_imm",
+        Operand::Full(_) => "full",
+    }
+}
+
+fn operand_spec(operand: &Operand) -> OperandSpec {
+    let mode = match operand {
+        Operand::RegOnly => None,
+        Operand::RegOrImm(inner) => Some(format!("{:?}", inner)),
+        Operand::Full(inner) => Some(format!("{:?}", inner)),
+    };
+
+    OperandSpec {
+        kind: operand_kind(operand),
+        mode,
+    }
+}
+
+fn variant_spec(variant: &OpcodeVariant, version: ISAVersion) -> OpcodeVariantSpec {
+    let (opcode, variant_name) = opcode_and_variant_names(&variant.opcode);
+    let flags = non_exclusive_flag_names(&variant.opcode)
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| {
+            name.map(|name| FlagSpec {
+                index,
+                name,
+                value: variant.flags[index],
+            })
+        })
+        .collect();
+
+    OpcodeVariantSpec {
+        opcode,
+        variant: variant_name,
+        opcode_index: variant.opcode.variant_idx(),
+        variant_index: variant.opcode.materialize_subvariant_idx(),
+        src0: operand_spec(&variant.src0_operand_type),
+        dst0: operand_spec(&variant.dst0_operand_type),
+        input_operands: variant
+            .opcode
+            .input_operands(version)
+            .iter()
+            .map(operand_kind)
+            .collect(),
+        output_operands: variant
+            .opcode
+            .output_operands(version)
+            .iter()
+            .map(operand_kind)
+            .collect(),
+        flags,
+        flags_effect: flags_effect(variant),
+        ergs_price: variant.ergs_price(),
+        requires_kernel_mode: variant.requires_kernel_mode(),
+        can_be_used_in_static_context: variant.can_be_used_in_static_context(),
+        is_explicit_panic: variant.is_explicit_panic(),
+        src0_can_be_pointer: variant.opcode.src0_can_be_pointer(),
+        src1_can_be_pointer: variant.opcode.src1_can_be_pointer(),
+        introduced_in: version.0,
+        encodings: vec![],
+    }
+}
+
+///
+/// Walks over the decoding tables of all the ISA versions and collects every opcode variant
+/// together with its encoding in each version it is present in.
+///
+pub fn synthesize_isa_spec() -> IsaSpec {
+    let mut isa_versions = Vec::with_capacity(NUM_ISA_VERSIONS);
+    let mut variants: Vec<OpcodeVariantSpec> = vec![];
+    let mut positions = HashMap::<OpcodeVariant, usize>::new();
+
+    for version in ALL_ISA_VERSIONS.iter().copied() {
+        isa_versions.push(IsaVersionSpec {
+            version: version.0,
+            decoding_format: compute_decoding_format(version),
+            opcodes_table_width: OPCODES_TABLE_WIDTH,
+            max_num_variants: max_num_variants_for_version(version),
+            max_num_flags: max_num_flags_for_version(version),
+            total_description_bits: total_description_bits_for_version(version),
+            total_description_and_aux_bits: total_opcode_description_and_aux_bits_for_version(
+                version,
+            ),
+        });
+
+        let table = synthesize_opcode_decoding_tables(OPCODES_TABLE_WIDTH, version);
+        let bitmasks = synthesize_bit_decomposition_table(&table, version);
+        let mut seen_in_version = HashSet::new();
+        for (table_index, (variant, bitmask)) in table.iter().zip(bitmasks.iter()).enumerate() {
+            // the table is padded with invalid opcodes, and we only take canonical indexes
+            if !seen_in_version.insert(*variant) {
+                continue;
+            }
+            let position = *positions.entry(*variant).or_insert_with(|| {
+                variants.push(variant_spec(variant, version));
+                variants.len() - 1
+            });
+            variants[position].encodings.push(VariantEncoding {
+                isa_version: version.0,
+                table_index,
+                properties_bitmask: *bitmask,
+            });
+        }
+    }
+
+    IsaSpec {
+        isa_versions,
+        variants,
+    }
+}
+
+fn format_operand(operand: &OperandSpec) -> String {
+    match &operand.mode {
+        Some(mode) => format!("{}:{}", operand.kind, mode),
+        None => operand.kind.to_owned(),
+    }
+}
+
+impl IsaSpec {
+    ///
+    /// Renders a reference table of all the variants, using encodings of the latest ISA version.
+    ///
+    pub fn to_markdown(&self) -> String {
+        let mut result = String::new();
+        result.push_str("# EraVM instruction set\n\n");
+        result.push_str(
+            "This file is auto-generated by `isa_spec_generator`, do not edit it manually.\n\n",
+        );
+
+        result.push_str("## ISA versions\n\n");
+        result.push_str(
+            "| Version | Decoding format | Description bits | Description and aux bits |\n",
+        );
+        result.push_str("|---|---|---|---|\n");
+        for version in self.isa_versions.iter() {
+            writeln!(
+                result,
+                "| {} | {} | {} | {} |",
+                version.version,
+                version.decoding_format.replace('\n', " "),
+                version.total_description_bits,
+                version.total_description_and_aux_bits,
+            )
+            .unwrap();
+        }
+
+        let latest_version = self.isa_versions.last().map(|el| el.version);
+        result.push_str("\n## Opcode variants\n\n");
+        result.push_str("| Index | Opcode | Variant | src0 | dst0 | Flags | Flags effect | Ergs | Kernel only | Static context | Since ISA | Properties bitmask |\n");
+        result.push_str("|---|---|---|---|---|---|---|---|---|---|---|---|\n");
+        for variant in self.variants.iter() {
+            let latest_encoding = variant
+                .encodings
+                .iter()
+                .find(|el| Some(el.isa_version) == latest_version);
+            let (index, bitmask) = match latest_encoding {
+                Some(encoding) => (
+                    encoding.table_index.to_string(),
+                    format!("{:#018x}", encoding.properties_bitmask),
+                ),
+                None => ("-".to_owned(), "-".to_owned()),
+            };
+            let flags = variant
+                .flags
+                .iter()
+                .filter(|el| el.value)
+                .map(|el| el.name)
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                result,
+                "| {} | {} | {} | {} | {} | {} | {:?} | {} | {} | {} | {} | {} |",
+                index,
+                variant.opcode,
+                variant.variant,
+                format_operand(&variant.src0),
+                format_operand(&variant.dst0),
+                flags,
+                variant.flags_effect,
+                variant.ergs_price,
+                variant.requires_kernel_mode,
+                variant.can_be_used_in_static_context,
+                variant.introduced_in,
+                bitmask,
+            )
+            .unwrap();
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spec_matches_default_tables() {
+        let spec = synthesize_isa_spec();
+        assert_eq!(spec.isa_versions.len(), NUM_ISA_VERSIONS);
+
+        for (variant, index) in OPCODE_TO_CANONICAL_INDEX_LOOKUP_MAP.iter() {
+            let (opcode, variant_name) = opcode_and_variant_names(&variant.opcode);
+            let encoding = spec
+                .variants
+                .iter()
+                .filter(|el| el.opcode == opcode && el.variant == variant_name)
+                .flat_map(|el| el.encodings.iter())
+                .find(|el| el.isa_version == DEFAULT_ISA_VERSION.0 && el.table_index == *index)
+                .expect("every canonical variant must be present in the spec");
+            assert_eq!(
+                encoding.properties_bitmask,
+                OPCODES_PROPS_INTEGER_BITMASKS[*index]
+            );
+        }
+
+        let markdown = spec.to_markdown();
+        let num_rows = markdown.lines().filter(|el| el.starts_with("| ")).count();
+        // headers of both tables are included
+        assert_eq!(num_rows, 2 + spec.isa_versions.len() + spec.variants.len());
+    }
+}
@@ -0,0 +1,26 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use zkevm_opcode_defs::isa_spec::synthesize_isa_spec;
+
+fn save_file(content: &str, filepath: &Path) {
+    let mut f = File::create(filepath).expect("Unable to create file");
+    f.write_all(content.as_bytes())
+        .expect("Unable to write data");
+}
+
+fn main() {
+    // output directory may be passed as the only argument
+    let output_dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "isa_spec".to_owned());
+    let output_dir = Path::new(&output_dir);
+    std::fs::create_dir_all(output_dir).expect("Unable to create output directory");
+
+    let spec = synthesize_isa_spec();
+    let json = serde_json::to_string_pretty(&spec).expect("Unable to serialize ISA spec");
+
+    save_file(&json, &output_dir.join("isa_spec.json"));
+    save_file(&spec.to_markdown(), &output_dir.join("isa_spec.md"));
+}
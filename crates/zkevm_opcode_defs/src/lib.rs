@@ -3,11 +3,13 @@ pub const REGISTERS_COUNT: usize = 15;
 pub mod decoding;
 pub mod definitions;
 pub mod imm_mem_modifiers;
+pub mod isa_spec;
 pub mod opcode;
 pub mod system_params;
 pub mod utils;
 
 pub mod circuit_prices;
+pub mod circuit_pricing;
 
 use std::collections::HashMap;
 
@@ -240,7 +240,7 @@ pub fn generate_recursive_layer_vks_and_proofs(
     generate_recursive_layer_vks(source, None, || {})
 }
 
-fn generate_vk_and_finalization_hint_for_recursion(
+pub fn generate_vk_and_finalization_hint_for_recursion(
     circuit: ZkSyncRecursiveLayerCircuit,
     worker: &Worker,
 ) -> (
@@ -426,7 +426,7 @@ mod test {
     #[test]
     fn test_run_create_base_layer_vks_and_proofs() {
         let mut source = LocalFileDataSource::default();
-        source.create_folders_for_storing_data();
+        source.create_folders_for_storing_data().unwrap();
         let count = basic_vk_count();
         let progress_bar = ProgressBar::new(count as u64);
         progress_bar.set_style(ProgressStyle::default_bar()
@@ -446,7 +446,7 @@ mod test {
     #[test]
     fn test_run_create_recursion_layer_vks_and_proofs() {
         let mut source = LocalFileDataSource::default();
-        source.create_folders_for_storing_data();
+        source.create_folders_for_storing_data().unwrap();
         generate_recursive_layer_vks(&mut source, None, || {}).expect("must compute setup");
     }
 
@@ -454,7 +454,7 @@ mod test {
     #[test]
     fn test_generate_recursion_tip() {
         let mut src = LocalFileDataSource::default();
-        src.create_folders_for_storing_data();
+        src.create_folders_for_storing_data().unwrap();
         let source = &mut src;
 
         generate_recursion_tip_vk(source).unwrap();
@@ -464,7 +464,7 @@ mod test {
     #[test]
     fn test_generate_scheduler() {
         let mut src = LocalFileDataSource::default();
-        src.create_folders_for_storing_data();
+        src.create_folders_for_storing_data().unwrap();
         let source = &mut src;
 
         generate_scheduler_vk(source).unwrap();
@@ -307,7 +307,7 @@ fn get_all_recursive_circuits(
 }
 
 /// Returns all the leaf circuits.
-fn get_leaf_circuits(
+pub fn get_leaf_circuits(
     source: &mut dyn SetupDataSource,
 ) -> crate::data_source::SourceResult<Vec<ZkSyncRecursiveLayerCircuit>> {
     let mut result = vec![];
@@ -357,7 +357,7 @@ fn get_leaf_circuits(
 }
 
 /// Returns the node circuit.
-fn get_node_circuit(
+pub fn get_node_circuit(
     source: &mut dyn SetupDataSource,
 ) -> crate::data_source::SourceResult<ZkSyncRecursiveLayerCircuit> {
     use crate::zkevm_circuits::recursion::node_layer::input::*;
@@ -398,7 +398,7 @@ fn get_node_circuit(
 }
 
 /// Returns the recursion tip circuit
-fn get_recursion_tip_circuit(
+pub fn get_recursion_tip_circuit(
     source: &mut dyn SetupDataSource,
 ) -> crate::data_source::SourceResult<ZkSyncRecursiveLayerCircuit> {
     use crate::zkevm_circuits::recursion::recursion_tip::input::*;
@@ -432,7 +432,7 @@ fn get_recursion_tip_circuit(
 
 /// Returns the scheduler circuit.
 /// Source must contain the leafs, node and tip verification keys.
-fn get_scheduler_circuit(
+pub fn get_scheduler_circuit(
     source: &mut dyn SetupDataSource,
 ) -> crate::data_source::SourceResult<ZkSyncRecursiveLayerCircuit> {
     use crate::zkevm_circuits::scheduler::SchedulerConfig;
@@ -0,0 +1,723 @@
+use super::{ArtifactKind, BlockDataSource, DataSourceError, SetupDataSource, SourceResult};
+use circuit_definitions::circuit_definitions::aux_layer::{
+    ZkSyncCompressionForWrapperFinalizationHint, ZkSyncCompressionForWrapperProof,
+    ZkSyncCompressionForWrapperVerificationKey, ZkSyncCompressionLayerFinalizationHint,
+    ZkSyncCompressionLayerProof, ZkSyncCompressionLayerVerificationKey, ZkSyncSnarkWrapperProof,
+    ZkSyncSnarkWrapperSetup, ZkSyncSnarkWrapperVK,
+};
+use circuit_definitions::circuit_definitions::base_layer::{
+    ZkSyncBaseLayerFinalizationHint, ZkSyncBaseLayerProof, ZkSyncBaseLayerVerificationKey,
+};
+use circuit_definitions::circuit_definitions::recursion_layer::{
+    ZkSyncRecursionLayerFinalizationHint, ZkSyncRecursionLayerProof,
+    ZkSyncRecursionLayerVerificationKey,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::proof::Proof as SnarkProof;
+use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::setup::Setup as SnarkSetup;
+use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::setup::VerificationKey as SnarkVK;
+
+use std::sync::Arc;
+
+/// Encoding used for newly written artifacts. Reads don't depend on it: the backend records the
+/// encoding of every artifact it stores.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    Json,
+    Bincode,
+}
+
+/// Encoding of a stored artifact. Artifacts that can't be encoded with serde (SNARK wrapper
+/// setup, key and proof) are stored in their native bellman encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactEncoding {
+    Json,
+    Bincode,
+    Raw,
+}
+
+impl ArtifactEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactEncoding::Json => "json",
+            ArtifactEncoding::Bincode => "bincode",
+            ArtifactEncoding::Raw => "raw",
+        }
+    }
+
+    pub fn parse(encoding: &str) -> SourceResult<Self> {
+        match encoding {
+            "json" => Ok(ArtifactEncoding::Json),
+            "bincode" => Ok(ArtifactEncoding::Bincode),
+            "raw" => Ok(ArtifactEncoding::Raw),
+            _ => Err(DataSourceError::InvalidSource(format!(
+                "unknown artifact encoding {:?}",
+                encoding
+            ))),
+        }
+    }
+}
+
+/// Storage behind an [`ArtifactDataSource`]: encoded artifacts under their names, e.g.
+/// `recursion_layer/vk_3`.
+///
+/// It is synchronous on purpose: data sources are used from synchronous, CPU-bound proving code
+/// that fetches each artifact once per circuit, so an async interface would only push a runtime
+/// into every caller. Backends over async clients should block on the request instead (e.g. with
+/// a runtime handle they own).
+pub trait ArtifactBackend {
+    /// Returns `None` if there is no artifact `name`.
+    fn get_artifact(&self, name: &str) -> SourceResult<Option<(ArtifactEncoding, Vec<u8>)>>;
+    fn put_artifact(
+        &self,
+        name: &str,
+        encoding: ArtifactEncoding,
+        payload: &[u8],
+    ) -> SourceResult<()>;
+}
+
+/// [`SetupDataSource`] and [`BlockDataSource`] over any [`ArtifactBackend`]: maps every artifact
+/// to its name and encoding, the backend only stores bytes.
+#[derive(Clone, Debug)]
+pub struct ArtifactDataSource<B> {
+    backend: B,
+    format: ArtifactFormat,
+}
+
+impl<B: ArtifactBackend> ArtifactDataSource<B> {
+    pub fn from_backend(backend: B, format: ArtifactFormat) -> Self {
+        Self { backend, format }
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn format(&self) -> ArtifactFormat {
+        self.format
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(
+        &self,
+        name: &str,
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+        index: Option<usize>,
+    ) -> SourceResult<T> {
+        let (encoding, payload) = self.get_artifact(name, kind, circuit_type, index)?;
+        match encoding {
+            ArtifactEncoding::Json => Ok(serde_json::from_slice(&payload)?),
+            ArtifactEncoding::Bincode => Ok(bincode::deserialize(&payload)?),
+            ArtifactEncoding::Raw => Err(unexpected_encoding(name, encoding)),
+        }
+    }
+
+    fn set<T: Serialize>(&self, name: &str, artifact: &T) -> SourceResult<()> {
+        match self.format {
+            ArtifactFormat::Json => self.backend.put_artifact(
+                name,
+                ArtifactEncoding::Json,
+                &serde_json::to_vec(artifact)?,
+            ),
+            ArtifactFormat::Bincode => self.backend.put_artifact(
+                name,
+                ArtifactEncoding::Bincode,
+                &bincode::serialize(artifact)?,
+            ),
+        }
+    }
+
+    fn get_raw(
+        &self,
+        name: &str,
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+        index: Option<usize>,
+    ) -> SourceResult<Vec<u8>> {
+        let (encoding, payload) = self.get_artifact(name, kind, circuit_type, index)?;
+        if encoding != ArtifactEncoding::Raw {
+            return Err(unexpected_encoding(name, encoding));
+        }
+
+        Ok(payload)
+    }
+
+    fn set_raw(&self, name: &str, payload: &[u8]) -> SourceResult<()> {
+        self.backend
+            .put_artifact(name, ArtifactEncoding::Raw, payload)
+    }
+
+    fn get_artifact(
+        &self,
+        name: &str,
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+        index: Option<usize>,
+    ) -> SourceResult<(ArtifactEncoding, Vec<u8>)> {
+        self.backend
+            .get_artifact(name)?
+            .ok_or(DataSourceError::not_found(kind, circuit_type, index))
+    }
+}
+
+fn unexpected_encoding(name: &str, encoding: ArtifactEncoding) -> DataSourceError {
+    DataSourceError::InvalidSource(format!(
+        "artifact {} has unexpected encoding {}",
+        name,
+        encoding.as_str()
+    ))
+}
+
+impl<B: ArtifactBackend> SetupDataSource for ArtifactDataSource<B> {
+    fn get_base_layer_vk(&self, circuit_type: u8) -> SourceResult<ZkSyncBaseLayerVerificationKey> {
+        self.get(
+            &format!("base_layer/vk_{}", circuit_type),
+            ArtifactKind::BaseLayerVk,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_base_layer_finalization_hint(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncBaseLayerFinalizationHint> {
+        self.get(
+            &format!("base_layer/finalization_hint_{}", circuit_type),
+            ArtifactKind::BaseLayerFinalizationHint,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_recursion_layer_vk(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
+        self.get(
+            &format!("recursion_layer/vk_{}", circuit_type),
+            ArtifactKind::RecursionLayerVk,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_recursion_layer_node_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
+        self.get(
+            "recursion_layer/vk_node",
+            ArtifactKind::RecursionLayerNodeVk,
+            None,
+            None,
+        )
+    }
+    fn get_recursion_tip_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
+        self.get(
+            "recursion_layer/vk_recursion_tip",
+            ArtifactKind::RecursionTipVk,
+            None,
+            None,
+        )
+    }
+    fn get_recursion_layer_finalization_hint(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
+        self.get(
+            &format!("recursion_layer/finalization_hint_{}", circuit_type),
+            ArtifactKind::RecursionLayerFinalizationHint,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_recursion_layer_node_finalization_hint(
+        &self,
+    ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
+        self.get(
+            "recursion_layer/finalization_hint_node",
+            ArtifactKind::RecursionLayerNodeFinalizationHint,
+            None,
+            None,
+        )
+    }
+    fn get_recursion_tip_finalization_hint(
+        &self,
+    ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
+        self.get(
+            "recursion_layer/finalization_hint_recursion_tip",
+            ArtifactKind::RecursionTipFinalizationHint,
+            None,
+            None,
+        )
+    }
+    fn get_compression_vk(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncCompressionLayerVerificationKey> {
+        self.get(
+            &format!("aux_layer/compression_vk_{}", circuit_type),
+            ArtifactKind::CompressionVk,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_compression_hint(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncCompressionLayerFinalizationHint> {
+        self.get(
+            &format!("aux_layer/compression_hint_{}", circuit_type),
+            ArtifactKind::CompressionHint,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_compression_for_wrapper_vk(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncCompressionForWrapperVerificationKey> {
+        self.get(
+            &format!("aux_layer/compression_for_wrapper_vk_{}", circuit_type),
+            ArtifactKind::CompressionForWrapperVk,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_compression_for_wrapper_hint(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncCompressionForWrapperFinalizationHint> {
+        self.get(
+            &format!("aux_layer/compression_for_wrapper_hint_{}", circuit_type),
+            ArtifactKind::CompressionForWrapperHint,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_wrapper_setup(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperSetup> {
+        let payload = self.get_raw(
+            &format!("aux_layer/wrapper_setup_{}", circuit_type),
+            ArtifactKind::WrapperSetup,
+            Some(circuit_type),
+            None,
+        )?;
+        let setup = SnarkSetup::read(&payload[..])?;
+
+        Ok(ZkSyncSnarkWrapperSetup::from_inner(
+            circuit_type,
+            Arc::new(setup),
+        ))
+    }
+    fn get_wrapper_vk(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperVK> {
+        let payload = self.get_raw(
+            &format!("aux_layer/wrapper_vk_{}", circuit_type),
+            ArtifactKind::WrapperVk,
+            Some(circuit_type),
+            None,
+        )?;
+
+        Ok(ZkSyncSnarkWrapperVK::from_inner(
+            circuit_type,
+            SnarkVK::read(&payload[..])?,
+        ))
+    }
+
+    fn set_base_layer_vk(&mut self, vk: ZkSyncBaseLayerVerificationKey) -> SourceResult<()> {
+        let circuit_type = vk.numeric_circuit_type();
+        self.set(&format!("base_layer/vk_{}", circuit_type), &vk)
+    }
+    fn set_base_layer_finalization_hint(
+        &mut self,
+        hint: ZkSyncBaseLayerFinalizationHint,
+    ) -> SourceResult<()> {
+        let circuit_type = hint.numeric_circuit_type();
+        self.set(
+            &format!("base_layer/finalization_hint_{}", circuit_type),
+            &hint,
+        )
+    }
+    fn set_recursion_layer_vk(
+        &mut self,
+        vk: ZkSyncRecursionLayerVerificationKey,
+    ) -> SourceResult<()> {
+        let circuit_type = vk.numeric_circuit_type();
+        self.set(&format!("recursion_layer/vk_{}", circuit_type), &vk)
+    }
+    fn set_recursion_layer_node_vk(
+        &mut self,
+        vk: ZkSyncRecursionLayerVerificationKey,
+    ) -> SourceResult<()> {
+        self.set("recursion_layer/vk_node", &vk)
+    }
+    fn set_recursion_tip_vk(
+        &mut self,
+        vk: ZkSyncRecursionLayerVerificationKey,
+    ) -> SourceResult<()> {
+        self.set("recursion_layer/vk_recursion_tip", &vk)
+    }
+    fn set_recursion_layer_finalization_hint(
+        &mut self,
+        hint: ZkSyncRecursionLayerFinalizationHint,
+    ) -> SourceResult<()> {
+        let circuit_type = hint.numeric_circuit_type();
+        self.set(
+            &format!("recursion_layer/finalization_hint_{}", circuit_type),
+            &hint,
+        )
+    }
+    fn set_recursion_layer_node_finalization_hint(
+        &mut self,
+        hint: ZkSyncRecursionLayerFinalizationHint,
+    ) -> SourceResult<()> {
+        self.set("recursion_layer/finalization_hint_node", &hint)
+    }
+    fn set_recursion_tip_finalization_hint(
+        &mut self,
+        hint: ZkSyncRecursionLayerFinalizationHint,
+    ) -> SourceResult<()> {
+        self.set("recursion_layer/finalization_hint_recursion_tip", &hint)
+    }
+    fn set_compression_vk(
+        &mut self,
+        vk: ZkSyncCompressionLayerVerificationKey,
+    ) -> SourceResult<()> {
+        let circuit_type = vk.numeric_circuit_type();
+        self.set(&format!("aux_layer/compression_vk_{}", circuit_type), &vk)
+    }
+    fn set_compression_hint(
+        &mut self,
+        hint: ZkSyncCompressionLayerFinalizationHint,
+    ) -> SourceResult<()> {
+        let circuit_type = hint.numeric_circuit_type();
+        self.set(
+            &format!("aux_layer/compression_hint_{}", circuit_type),
+            &hint,
+        )
+    }
+    fn set_compression_for_wrapper_vk(
+        &mut self,
+        vk: ZkSyncCompressionForWrapperVerificationKey,
+    ) -> SourceResult<()> {
+        let circuit_type = vk.numeric_circuit_type();
+        self.set(
+            &format!("aux_layer/compression_for_wrapper_vk_{}", circuit_type),
+            &vk,
+        )
+    }
+    fn set_compression_for_wrapper_hint(
+        &mut self,
+        hint: ZkSyncCompressionForWrapperFinalizationHint,
+    ) -> SourceResult<()> {
+        let circuit_type = hint.numeric_circuit_type();
+        self.set(
+            &format!("aux_layer/compression_for_wrapper_hint_{}", circuit_type),
+            &hint,
+        )
+    }
+    fn set_wrapper_setup(&mut self, setup: ZkSyncSnarkWrapperSetup) -> SourceResult<()> {
+        let circuit_type = setup.numeric_circuit_type();
+        let mut payload = vec![];
+        setup.into_inner().write(&mut payload)?;
+        self.set_raw(
+            &format!("aux_layer/wrapper_setup_{}", circuit_type),
+            &payload,
+        )
+    }
+    fn set_wrapper_vk(&mut self, vk: ZkSyncSnarkWrapperVK) -> SourceResult<()> {
+        let circuit_type = vk.numeric_circuit_type();
+        let mut payload = vec![];
+        vk.into_inner().write(&mut payload)?;
+        self.set_raw(&format!("aux_layer/wrapper_vk_{}", circuit_type), &payload)
+    }
+}
+
+impl<B: ArtifactBackend> BlockDataSource for ArtifactDataSource<B> {
+    fn get_base_layer_proof(
+        &self,
+        circuit_type: u8,
+        index: usize,
+    ) -> SourceResult<ZkSyncBaseLayerProof> {
+        self.get(
+            &format!(
+                "proofs/base_layer/basic_circuit_proof_{}_{}",
+                circuit_type, index
+            ),
+            ArtifactKind::BaseLayerProof,
+            Some(circuit_type),
+            Some(index),
+        )
+    }
+    fn get_leaf_layer_proof(
+        &self,
+        circuit_type: u8,
+        index: usize,
+    ) -> SourceResult<ZkSyncRecursionLayerProof> {
+        self.get(
+            &format!(
+                "proofs/recursion_layer/leaf_layer_proof_{}_{}",
+                circuit_type, index
+            ),
+            ArtifactKind::LeafLayerProof,
+            Some(circuit_type),
+            Some(index),
+        )
+    }
+    fn get_node_layer_proof(
+        &self,
+        circuit_type: u8,
+        step: usize,
+        index: usize,
+    ) -> SourceResult<ZkSyncRecursionLayerProof> {
+        self.get(
+            &format!(
+                "proofs/recursion_layer/node_layer_proof_{}_{}_{}",
+                circuit_type, step, index
+            ),
+            ArtifactKind::NodeLayerProof { step },
+            Some(circuit_type),
+            Some(index),
+        )
+    }
+    fn get_scheduler_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
+        self.get(
+            "proofs/recursion_layer/scheduler_proof",
+            ArtifactKind::SchedulerProof,
+            None,
+            None,
+        )
+    }
+    fn get_compression_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncCompressionLayerProof> {
+        self.get(
+            &format!("proofs/aux_layer/compression_proof_{}", circuit_type),
+            ArtifactKind::CompressionProof,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_compression_for_wrapper_proof(
+        &self,
+        circuit_type: u8,
+    ) -> SourceResult<ZkSyncCompressionForWrapperProof> {
+        self.get(
+            &format!(
+                "proofs/aux_layer/compression_for_wrapper_proof_{}",
+                circuit_type
+            ),
+            ArtifactKind::CompressionForWrapperProof,
+            Some(circuit_type),
+            None,
+        )
+    }
+    fn get_wrapper_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperProof> {
+        let payload = self.get_raw(
+            &format!("proofs/aux_layer/wrapper_proof_{}", circuit_type),
+            ArtifactKind::WrapperProof,
+            Some(circuit_type),
+            None,
+        )?;
+
+        Ok(ZkSyncSnarkWrapperProof::from_inner(
+            circuit_type,
+            SnarkProof::read(&payload[..])?,
+        ))
+    }
+
+    fn set_base_layer_proof(
+        &mut self,
+        index: usize,
+        proof: ZkSyncBaseLayerProof,
+    ) -> SourceResult<()> {
+        let circuit_type = proof.numeric_circuit_type();
+        self.set(
+            &format!(
+                "proofs/base_layer/basic_circuit_proof_{}_{}",
+                circuit_type, index
+            ),
+            &proof,
+        )
+    }
+    fn set_leaf_layer_proof(
+        &mut self,
+        index: usize,
+        proof: ZkSyncRecursionLayerProof,
+    ) -> SourceResult<()> {
+        let circuit_type = proof.numeric_circuit_type();
+        self.set(
+            &format!(
+                "proofs/recursion_layer/leaf_layer_proof_{}_{}",
+                circuit_type, index
+            ),
+            &proof,
+        )
+    }
+    fn set_node_layer_proof(
+        &mut self,
+        circuit_type: u8,
+        step: usize,
+        index: usize,
+        proof: ZkSyncRecursionLayerProof,
+    ) -> SourceResult<()> {
+        self.set(
+            &format!(
+                "proofs/recursion_layer/node_layer_proof_{}_{}_{}",
+                circuit_type, step, index
+            ),
+            &proof,
+        )
+    }
+    fn set_scheduler_proof(&mut self, proof: ZkSyncRecursionLayerProof) -> SourceResult<()> {
+        self.set("proofs/recursion_layer/scheduler_proof", &proof)
+    }
+    fn set_compression_proof(&mut self, proof: ZkSyncCompressionLayerProof) -> SourceResult<()> {
+        let circuit_type = proof.numeric_circuit_type();
+        self.set(
+            &format!("proofs/aux_layer/compression_proof_{}", circuit_type),
+            &proof,
+        )
+    }
+    fn set_compression_for_wrapper_proof(
+        &mut self,
+        proof: ZkSyncCompressionForWrapperProof,
+    ) -> SourceResult<()> {
+        let circuit_type = proof.numeric_circuit_type();
+        self.set(
+            &format!(
+                "proofs/aux_layer/compression_for_wrapper_proof_{}",
+                circuit_type
+            ),
+            &proof,
+        )
+    }
+    fn set_wrapper_proof(&mut self, proof: ZkSyncSnarkWrapperProof) -> SourceResult<()> {
+        let circuit_type = proof.numeric_circuit_type();
+        let mut payload = vec![];
+        proof.into_inner().write(&mut payload)?;
+        self.set_raw(
+            &format!("proofs/aux_layer/wrapper_proof_{}", circuit_type),
+            &payload,
+        )
+    }
+
+    fn set_recursive_tip_proof(&mut self, proof: ZkSyncRecursionLayerProof) -> SourceResult<()> {
+        self.set("proofs/recursion_layer/recursive_tip_proof", &proof)
+    }
+    fn get_recursive_tip_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
+        self.get(
+            "proofs/recursion_layer/recursive_tip_proof",
+            ArtifactKind::RecursionTipProof,
+            None,
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::data_source::content_addressed_data_source::ContentAddressedDataSource;
+    use crate::data_source::local_file_data_source::LocalFileDataSource;
+    use crate::data_source::object_store_data_source::{
+        InMemoryObjectStore, LocalFileObjectStore, ObjectStoreDataSource,
+    };
+    use circuit_definitions::boojum::cs::implementations::setup::FinalizationHintsForProver;
+    use circuit_definitions::circuit_definitions::recursion_layer::ZkSyncRecursionLayerStorageType;
+
+    pub(in crate::data_source) fn hint(final_trace_len: usize) -> ZkSyncBaseLayerFinalizationHint {
+        ZkSyncBaseLayerFinalizationHint::MainVM(FinalizationHintsForProver {
+            final_trace_len,
+            ..Default::default()
+        })
+    }
+
+    /// Keys and proofs of the proof compression tests.
+    fn testdata() -> LocalFileDataSource {
+        let location = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/proof_wrapper_utils/testdata/proof_compression"
+        );
+        LocalFileDataSource {
+            setup_data_location: location.to_string(),
+            block_data_location: location.to_string(),
+        }
+    }
+
+    fn assert_same<T: Serialize>(left: &T, right: &T) {
+        assert_eq!(
+            serde_json::to_value(left).unwrap(),
+            serde_json::to_value(right).unwrap()
+        );
+    }
+
+    /// Writes keys and proofs through `source` and reads them back through `reopen()`.
+    fn check_roundtrip<S: SetupDataSource + BlockDataSource>(
+        mut source: S,
+        reopen: impl FnOnce() -> S,
+    ) {
+        let testdata = testdata();
+        let scheduler_type = ZkSyncRecursionLayerStorageType::SchedulerCircuit as u8;
+        let scheduler_vk = testdata.get_recursion_layer_vk(scheduler_type).unwrap();
+        let scheduler_proof = testdata.get_scheduler_proof().unwrap();
+        let compression_vk = testdata.get_compression_vk(1).unwrap();
+        let compression_proof = testdata.get_compression_proof(1).unwrap();
+        let wrapper_vk = ZkSyncSnarkWrapperVK::from_inner(1, SnarkVK::empty());
+        let wrapper_proof = ZkSyncSnarkWrapperProof::from_inner(1, SnarkProof::empty());
+
+        source
+            .set_base_layer_finalization_hint(hint(1 << 20))
+            .unwrap();
+        source.set_recursion_layer_vk(scheduler_vk.clone()).unwrap();
+        source.set_scheduler_proof(scheduler_proof.clone()).unwrap();
+        source.set_compression_vk(compression_vk.clone()).unwrap();
+        source
+            .set_compression_proof(compression_proof.clone())
+            .unwrap();
+        source.set_wrapper_vk(wrapper_vk.clone()).unwrap();
+        source.set_wrapper_proof(wrapper_proof.clone()).unwrap();
+
+        let source = reopen();
+        assert_eq!(
+            source
+                .get_base_layer_finalization_hint(1)
+                .unwrap()
+                .into_inner()
+                .final_trace_len,
+            1 << 20
+        );
+        assert_same(
+            &source.get_recursion_layer_vk(scheduler_type).unwrap(),
+            &scheduler_vk,
+        );
+        assert_same(&source.get_scheduler_proof().unwrap(), &scheduler_proof);
+        assert_same(&source.get_compression_vk(1).unwrap(), &compression_vk);
+        assert_same(
+            &source.get_compression_proof(1).unwrap(),
+            &compression_proof,
+        );
+        assert_eq!(
+            format!("{:?}", source.get_wrapper_vk(1).unwrap().into_inner()),
+            format!("{:?}", wrapper_vk.into_inner())
+        );
+        assert_eq!(
+            format!("{:?}", source.get_wrapper_proof(1).unwrap().into_inner()),
+            format!("{:?}", wrapper_proof.into_inner())
+        );
+        assert!(source.get_compression_vk(2).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn test_content_addressed_roundtrip() {
+        for format in [ArtifactFormat::Json, ArtifactFormat::Bincode] {
+            let root = tempfile::tempdir().unwrap();
+            let open = || ContentAddressedDataSource::new(root.path(), "0.28.0", format).unwrap();
+            check_roundtrip(open(), open);
+        }
+    }
+
+    #[test]
+    fn test_object_store_roundtrip() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let open = || ObjectStoreDataSource::with_prefix(store.clone(), "0.28.0");
+        check_roundtrip(open(), open);
+
+        let root = tempfile::tempdir().unwrap();
+        let open = || ObjectStoreDataSource::new(LocalFileObjectStore::new(root.path()));
+        check_roundtrip(open(), open);
+    }
+}
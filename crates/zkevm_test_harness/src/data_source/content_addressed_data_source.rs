@@ -0,0 +1,357 @@
+use super::artifact_data_source::{ArtifactBackend, ArtifactDataSource, ArtifactEncoding};
+use super::{DataSourceError, SourceResult};
+
+use crate::sha2::{Digest, Sha256};
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use super::artifact_data_source::ArtifactFormat;
+
+/// Used to make temporary file names unique between concurrent writers within one process.
+static TMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Artifact store, where artifacts are content-addressed objects and each protocol version has
+/// its own set of references to them:
+///
+/// - `<root>/objects/<first 2 hex digits>/<sha256 of the encoded artifact>`
+/// - `<root>/versions/<protocol version>/<artifact name>`, containing `<encoding> <sha256>`
+///
+/// So keys and proofs of several protocol versions can live side by side, sharing the artifacts
+/// that didn't change. All files are written to a temporary file first and then renamed, and
+/// objects are checked against their hash when read.
+pub type ContentAddressedDataSource = ArtifactDataSource<ContentAddressedStore>;
+
+impl ContentAddressedDataSource {
+    pub fn new(
+        root: impl Into<PathBuf>,
+        protocol_version: &str,
+        format: ArtifactFormat,
+    ) -> SourceResult<Self> {
+        let store = ContentAddressedStore::new(root.into(), protocol_version)?;
+        fs::create_dir_all(store.root.join("objects"))?;
+        fs::create_dir_all(store.version_dir())?;
+
+        Ok(Self::from_backend(store, format))
+    }
+
+    /// Opens an existing store without creating anything in it, all writes are rejected.
+    pub fn open_read_only(root: impl Into<PathBuf>, protocol_version: &str) -> SourceResult<Self> {
+        let mut store = ContentAddressedStore::new(root.into(), protocol_version)?;
+        if !store.version_dir().is_dir() {
+            return Err(DataSourceError::InvalidSource(format!(
+                "no artifacts of protocol version {} in {}",
+                protocol_version,
+                store.root.display()
+            )));
+        }
+        store.read_only = true;
+
+        // Reads don't depend on the format.
+        Ok(Self::from_backend(store, ArtifactFormat::Json))
+    }
+
+    /// Same store, but reading and writing the references of another protocol version.
+    pub fn for_protocol_version(&self, protocol_version: &str) -> SourceResult<Self> {
+        let root = self.backend().root.clone();
+        if self.backend().read_only {
+            Self::open_read_only(root, protocol_version)
+        } else {
+            Self::new(root, protocol_version, self.format())
+        }
+    }
+
+    pub fn protocol_version(&self) -> &str {
+        &self.backend().protocol_version
+    }
+
+    /// Protocol versions that have at least one artifact directory in the store.
+    pub fn protocol_versions(&self) -> SourceResult<Vec<String>> {
+        let mut result = vec![];
+        for entry in fs::read_dir(self.backend().root.join("versions"))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                result.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        result.sort();
+
+        Ok(result)
+    }
+}
+
+/// [`ArtifactBackend`] of a [`ContentAddressedDataSource`], for one protocol version.
+#[derive(Clone, Debug)]
+pub struct ContentAddressedStore {
+    root: PathBuf,
+    protocol_version: String,
+    read_only: bool,
+}
+
+impl ContentAddressedStore {
+    fn new(root: PathBuf, protocol_version: &str) -> SourceResult<Self> {
+        let is_valid_version = !protocol_version.is_empty()
+            && protocol_version != "."
+            && protocol_version != ".."
+            && protocol_version
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_');
+        if !is_valid_version {
+            return Err(DataSourceError::InvalidSource(format!(
+                "invalid protocol version: {:?}",
+                protocol_version
+            )));
+        }
+
+        Ok(Self {
+            root,
+            protocol_version: protocol_version.to_string(),
+            read_only: false,
+        })
+    }
+
+    fn version_dir(&self) -> PathBuf {
+        self.root.join("versions").join(&self.protocol_version)
+    }
+
+    /// Hashes come from reference files, so they are checked before being used as a path.
+    fn object_path(&self, hash: &str) -> SourceResult<PathBuf> {
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DataSourceError::InvalidSource(format!(
+                "invalid object hash {:?}",
+                hash
+            )));
+        }
+
+        Ok(self.root.join("objects").join(&hash[..2]).join(hash))
+    }
+}
+
+impl ArtifactBackend for ContentAddressedStore {
+    fn get_artifact(&self, name: &str) -> SourceResult<Option<(ArtifactEncoding, Vec<u8>)>> {
+        let reference = match fs::read_to_string(self.version_dir().join(name)) {
+            Ok(reference) => reference,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let (encoding, hash) = match reference.trim().split_once(' ') {
+            Some((encoding, hash)) => (ArtifactEncoding::parse(encoding)?, hash),
+            None => {
+                return Err(DataSourceError::InvalidSource(format!(
+                    "malformed reference for artifact {}",
+                    name
+                )))
+            }
+        };
+
+        let payload = fs::read(self.object_path(hash)?)?;
+        let actual_hash = hex::encode(Sha256::digest(&payload));
+        if actual_hash != hash {
+            return Err(DataSourceError::IntegrityCheckFailed {
+                artifact: name.to_string(),
+                expected_hash: hash.to_string(),
+                actual_hash,
+            });
+        }
+
+        Ok(Some((encoding, payload)))
+    }
+
+    fn put_artifact(
+        &self,
+        name: &str,
+        encoding: ArtifactEncoding,
+        payload: &[u8],
+    ) -> SourceResult<()> {
+        if self.read_only {
+            return Err(DataSourceError::InvalidSource(format!(
+                "can't write artifact {}, the store is opened read-only",
+                name
+            )));
+        }
+
+        let hash = hex::encode(Sha256::digest(payload));
+        let object_path = self.object_path(&hash)?;
+        // Objects are immutable, an existing one only has to be rewritten if it got corrupted.
+        let is_stored = fs::read(&object_path)
+            .map(|stored| stored == payload)
+            .unwrap_or(false);
+        if !is_stored {
+            write_atomically(&object_path, payload)?;
+        }
+
+        write_atomically(
+            &self.version_dir().join(name),
+            format!("{} {}\n", encoding.as_str(), hash).as_bytes(),
+        )
+    }
+}
+
+pub(super) fn write_atomically(path: &Path, payload: &[u8]) -> SourceResult<()> {
+    let (parent, file_name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => (parent, file_name.to_string_lossy()),
+        _ => {
+            return Err(DataSourceError::InvalidSource(format!(
+                "{} is not a file path",
+                path.display()
+            )))
+        }
+    };
+    fs::create_dir_all(parent)?;
+
+    let tmp_path = parent.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name,
+        std::process::id(),
+        TMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::write(&tmp_path, payload)?;
+    if let Err(err) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::artifact_data_source::tests::hint;
+    use crate::data_source::{DataSourceError, SetupDataSource};
+
+    fn test_root() -> tempfile::TempDir {
+        tempfile::tempdir().unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_and_versions_coexist() {
+        let root = test_root();
+        let root = root.path();
+        for format in [ArtifactFormat::Json, ArtifactFormat::Bincode] {
+            let mut old = ContentAddressedDataSource::new(root, "0.27.0", format).unwrap();
+            let mut new = old.for_protocol_version("0.28.0").unwrap();
+            old.set_base_layer_finalization_hint(hint(1 << 20)).unwrap();
+            new.set_base_layer_finalization_hint(hint(1 << 21)).unwrap();
+
+            let old_hint = old
+                .get_base_layer_finalization_hint(1)
+                .unwrap()
+                .into_inner();
+            let new_hint = new
+                .get_base_layer_finalization_hint(1)
+                .unwrap()
+                .into_inner();
+            assert_eq!(old_hint.final_trace_len, 1 << 20);
+            assert_eq!(new_hint.final_trace_len, 1 << 21);
+        }
+        assert_eq!(
+            ContentAddressedDataSource::new(root, "0.28.0", ArtifactFormat::Json)
+                .unwrap()
+                .protocol_versions()
+                .unwrap(),
+            vec!["0.27.0".to_string(), "0.28.0".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_corrupted_object_is_rejected() {
+        let root = test_root();
+        let mut source =
+            ContentAddressedDataSource::new(root.path(), "0.28.0", ArtifactFormat::Json).unwrap();
+        source
+            .set_base_layer_finalization_hint(hint(1 << 20))
+            .unwrap();
+
+        let reference = fs::read_to_string(
+            source
+                .backend()
+                .version_dir()
+                .join("base_layer/finalization_hint_1"),
+        )
+        .unwrap();
+        let hash = reference.trim().split_once(' ').unwrap().1.to_string();
+        fs::write(source.backend().object_path(&hash).unwrap(), b"{}").unwrap();
+
+        assert!(matches!(
+            source.get_base_layer_finalization_hint(1),
+            Err(DataSourceError::IntegrityCheckFailed { .. })
+        ));
+        assert!(source
+            .get_base_layer_finalization_hint(2)
+            .unwrap_err()
+            .is_not_found());
+
+        // Storing the same artifact again repairs the object.
+        source
+            .set_base_layer_finalization_hint(hint(1 << 20))
+            .unwrap();
+        assert!(source.get_base_layer_finalization_hint(1).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_reference_is_rejected() {
+        let root = test_root();
+        let source =
+            ContentAddressedDataSource::new(root.path(), "0.28.0", ArtifactFormat::Json).unwrap();
+        let reference_path = source
+            .backend()
+            .version_dir()
+            .join("base_layer/finalization_hint_1");
+        fs::create_dir_all(reference_path.parent().unwrap()).unwrap();
+
+        let traversal = format!("json ../../{}", "0".repeat(58));
+        let non_hex = format!("json {}", "z".repeat(64));
+        let unknown_encoding = format!("yaml {}", "0".repeat(64));
+        for reference in [
+            "json",
+            "json 00",
+            traversal.as_str(),
+            non_hex.as_str(),
+            unknown_encoding.as_str(),
+        ] {
+            fs::write(&reference_path, reference).unwrap();
+            assert!(
+                matches!(
+                    source.get_base_layer_finalization_hint(1),
+                    Err(DataSourceError::InvalidSource(_))
+                ),
+                "{reference}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_only_store() {
+        let root = test_root();
+        let store_root = root.path().join("store");
+        assert!(ContentAddressedDataSource::open_read_only(&store_root, "0.28.0").is_err());
+        assert!(!store_root.exists());
+
+        let mut source =
+            ContentAddressedDataSource::new(&store_root, "0.28.0", ArtifactFormat::Json).unwrap();
+        source
+            .set_base_layer_finalization_hint(hint(1 << 20))
+            .unwrap();
+
+        let mut read_only =
+            ContentAddressedDataSource::open_read_only(&store_root, "0.28.0").unwrap();
+        assert!(read_only.get_base_layer_finalization_hint(1).is_ok());
+        assert!(matches!(
+            read_only.set_base_layer_finalization_hint(hint(1 << 21)),
+            Err(DataSourceError::InvalidSource(_))
+        ));
+        assert!(read_only.for_protocol_version("0.27.0").is_err());
+        assert!(!store_root.join("versions/0.27.0").exists());
+    }
+
+    #[test]
+    fn test_invalid_protocol_version() {
+        let root = test_root();
+        let root = root.path();
+        assert!(ContentAddressedDataSource::new(root, "../0.28.0", ArtifactFormat::Json).is_err());
+        assert!(ContentAddressedDataSource::new(root, "", ArtifactFormat::Json).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::error::Error;
+use std::fmt;
+
+/// Kind of artifact stored by [`super::SetupDataSource`] and [`super::BlockDataSource`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactKind {
+    BaseLayerVk,
+    BaseLayerFinalizationHint,
+    RecursionLayerVk,
+    RecursionLayerNodeVk,
+    RecursionTipVk,
+    RecursionLayerFinalizationHint,
+    RecursionLayerNodeFinalizationHint,
+    RecursionTipFinalizationHint,
+    CompressionVk,
+    CompressionHint,
+    CompressionForWrapperVk,
+    CompressionForWrapperHint,
+    WrapperSetup,
+    WrapperVk,
+    BaseLayerProof,
+    LeafLayerProof,
+    NodeLayerProof { step: usize },
+    RecursionTipProof,
+    SchedulerProof,
+    CompressionProof,
+    CompressionForWrapperProof,
+    WrapperProof,
+}
+
+impl fmt::Display for ArtifactKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactKind::BaseLayerVk => write!(f, "base layer vk"),
+            ArtifactKind::BaseLayerFinalizationHint => write!(f, "base layer finalization hint"),
+            ArtifactKind::RecursionLayerVk => write!(f, "recursion layer vk"),
+            ArtifactKind::RecursionLayerNodeVk => write!(f, "recursion layer node vk"),
+            ArtifactKind::RecursionTipVk => write!(f, "recursion tip vk"),
+            ArtifactKind::RecursionLayerFinalizationHint => {
+                write!(f, "recursion layer finalization hint")
+            }
+            ArtifactKind::RecursionLayerNodeFinalizationHint => {
+                write!(f, "recursion layer node finalization hint")
+            }
+            ArtifactKind::RecursionTipFinalizationHint => {
+                write!(f, "recursion tip finalization hint")
+            }
+            ArtifactKind::CompressionVk => write!(f, "compression vk"),
+            ArtifactKind::CompressionHint => write!(f, "compression finalization hint"),
+            ArtifactKind::CompressionForWrapperVk => write!(f, "compression for wrapper vk"),
+            ArtifactKind::CompressionForWrapperHint => {
+                write!(f, "compression for wrapper finalization hint")
+            }
+            ArtifactKind::WrapperSetup => write!(f, "wrapper setup"),
+            ArtifactKind::WrapperVk => write!(f, "wrapper vk"),
+            ArtifactKind::BaseLayerProof => write!(f, "base layer proof"),
+            ArtifactKind::LeafLayerProof => write!(f, "leaf layer proof"),
+            ArtifactKind::NodeLayerProof { step } => write!(f, "node layer proof at step {}", step),
+            ArtifactKind::RecursionTipProof => write!(f, "recursion tip proof"),
+            ArtifactKind::SchedulerProof => write!(f, "scheduler proof"),
+            ArtifactKind::CompressionProof => write!(f, "compression proof"),
+            ArtifactKind::CompressionForWrapperProof => write!(f, "compression for wrapper proof"),
+            ArtifactKind::WrapperProof => write!(f, "wrapper proof"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DataSourceError {
+    /// The source doesn't have the requested artifact.
+    NotFound {
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+        index: Option<usize>,
+    },
+    Io(std::io::Error),
+    /// The artifact exists, but can't be encoded or decoded.
+    Serialization(Box<dyn Error + Send + Sync>),
+    /// The stored artifact doesn't match the hash it was stored under.
+    IntegrityCheckFailed {
+        artifact: String,
+        expected_hash: String,
+        actual_hash: String,
+    },
+    /// The source itself is misconfigured or its metadata is malformed.
+    InvalidSource(String),
+    /// Failure of the storage backend, e.g. a remote object store.
+    Backend(Box<dyn Error + Send + Sync>),
+}
+
+impl DataSourceError {
+    pub fn not_found(kind: ArtifactKind, circuit_type: Option<u8>, index: Option<usize>) -> Self {
+        DataSourceError::NotFound {
+            kind,
+            circuit_type,
+            index,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, DataSourceError::NotFound { .. })
+    }
+}
+
+impl fmt::Display for DataSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataSourceError::NotFound {
+                kind,
+                circuit_type,
+                index,
+            } => {
+                write!(f, "no data for {}", kind)?;
+                if let Some(circuit_type) = circuit_type {
+                    write!(f, " for circuit type {}", circuit_type)?;
+                }
+                if let Some(index) = index {
+                    write!(f, " index {}", index)?;
+                }
+                Ok(())
+            }
+            DataSourceError::Io(err) => write!(f, "I/O error: {}", err),
+            DataSourceError::Serialization(err) => write!(f, "serialization error: {}", err),
+            DataSourceError::IntegrityCheckFailed {
+                artifact,
+                expected_hash,
+                actual_hash,
+            } => write!(
+                f,
+                "artifact {} is corrupted: expected hash {}, got {}",
+                artifact, expected_hash, actual_hash
+            ),
+            DataSourceError::InvalidSource(reason) => write!(f, "invalid data source: {}", reason),
+            DataSourceError::Backend(err) => write!(f, "storage backend error: {}", err),
+        }
+    }
+}
+
+impl Error for DataSourceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DataSourceError::Io(err) => Some(err),
+            DataSourceError::Serialization(err) | DataSourceError::Backend(err) => {
+                Some(err.as_ref())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DataSourceError {
+    fn from(err: std::io::Error) -> Self {
+        DataSourceError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for DataSourceError {
+    fn from(err: serde_json::Error) -> Self {
+        DataSourceError::Serialization(Box::new(err))
+    }
+}
+
+impl From<bincode::Error> for DataSourceError {
+    fn from(err: bincode::Error) -> Self {
+        DataSourceError::Serialization(err)
+    }
+}
@@ -1,4 +1,4 @@
-use super::{BlockDataSource, SetupDataSource, SourceResult};
+use super::{ArtifactKind, BlockDataSource, DataSourceError, SetupDataSource, SourceResult};
 use circuit_definitions::boojum::cs::implementations::setup::FinalizationHintsForProver;
 use circuit_definitions::circuit_definitions::aux_layer::{
     EIP4844VerificationKey, ZkSyncCompressionForWrapperFinalizationHint,
@@ -15,7 +15,6 @@ use circuit_definitions::circuit_definitions::recursion_layer::{
     ZkSyncRecursionLayerVerificationKey,
 };
 use std::collections::HashMap;
-use std::io::{Error, ErrorKind};
 
 pub struct InMemoryDataSource {
     ///data structures required for holding [`SetupDataSource`] result
@@ -79,10 +78,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.base_layer_vk
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::BaseLayerVk,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_base_layer_finalization_hint(
@@ -92,10 +92,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.base_layer_finalization_hint
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::BaseLayerFinalizationHint,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_recursion_layer_vk(
@@ -105,19 +106,21 @@ impl SetupDataSource for InMemoryDataSource {
         self.recursion_layer_vk
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionLayerVk,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_recursion_layer_node_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
         self.recursion_layer_node_vk
             .clone()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for recursion layer node vk"),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionLayerNodeVk,
+                None,
+                None,
+            ))
     }
 
     fn get_recursion_layer_finalization_hint(
@@ -127,10 +130,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.recursion_layer_finalization_hint
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionLayerFinalizationHint,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_recursion_layer_node_finalization_hint(
@@ -138,10 +142,11 @@ impl SetupDataSource for InMemoryDataSource {
     ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
         self.recursion_layer_node_finalization_hint
             .clone()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for recursion layer node vk"),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionLayerNodeFinalizationHint,
+                None,
+                None,
+            ))
     }
 
     fn get_compression_vk(
@@ -151,10 +156,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.compression_vk
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionVk,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_compression_hint(
@@ -164,10 +170,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.compression_hint
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionHint,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_compression_for_wrapper_vk(
@@ -177,10 +184,11 @@ impl SetupDataSource for InMemoryDataSource {
         self.compression_for_wrapper_vk
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionForWrapperVk,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_compression_for_wrapper_hint(
@@ -190,30 +198,33 @@ impl SetupDataSource for InMemoryDataSource {
         self.compression_for_wrapper_hint
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionForWrapperHint,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_wrapper_setup(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperSetup> {
         self.wrapper_setup
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::WrapperSetup,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_wrapper_vk(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperVK> {
         self.wrapper_vk
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::WrapperVk,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn set_base_layer_vk(&mut self, vk: ZkSyncBaseLayerVerificationKey) -> SourceResult<()> {
@@ -311,20 +322,24 @@ impl SetupDataSource for InMemoryDataSource {
     }
 
     fn get_recursion_tip_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
-        self.recursion_tip_vk.clone().ok_or(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("no data for recursion tip vk"),
-        )))
+        self.recursion_tip_vk
+            .clone()
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionTipVk,
+                None,
+                None,
+            ))
     }
     fn get_recursion_tip_finalization_hint(
         &self,
     ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
         self.recursion_tip_finalization_hint
             .clone()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for recursion tip finalization hint"),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionTipFinalizationHint,
+                None,
+                None,
+            ))
     }
     fn set_recursion_tip_vk(
         &mut self,
@@ -351,13 +366,11 @@ impl BlockDataSource for InMemoryDataSource {
         self.base_layer_proofs
             .get(&(circuit_type, index))
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!(
-                    "no base layer proof for circuit type {} index {}",
-                    circuit_type, index
-                ),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::BaseLayerProof,
+                Some(circuit_type),
+                Some(index),
+            ))
     }
 
     fn get_leaf_layer_proof(
@@ -368,13 +381,11 @@ impl BlockDataSource for InMemoryDataSource {
         self.leaf_layer_proofs
             .get(&(circuit_type, index))
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!(
-                    "no leaf layer proof for circuit type {} index {}",
-                    circuit_type, index
-                ),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::LeafLayerProof,
+                Some(circuit_type),
+                Some(index),
+            ))
     }
 
     fn get_node_layer_proof(
@@ -386,30 +397,32 @@ impl BlockDataSource for InMemoryDataSource {
         self.node_layer_proofs
             .get(&(circuit_type, step, index))
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!(
-                    "no node layer proof for circuit type {} index {} step {}",
-                    circuit_type, index, step
-                ),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::NodeLayerProof { step },
+                Some(circuit_type),
+                Some(index),
+            ))
     }
 
     fn get_scheduler_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.scheduler_proof.clone().ok_or(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("no scheduler proof"),
-        )))
+        self.scheduler_proof
+            .clone()
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::SchedulerProof,
+                None,
+                None,
+            ))
     }
 
     fn get_compression_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncCompressionLayerProof> {
         self.compression_proof
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionProof,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_compression_for_wrapper_proof(
@@ -419,20 +432,22 @@ impl BlockDataSource for InMemoryDataSource {
         self.compression_for_wrapper_proof
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::CompressionForWrapperProof,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn get_wrapper_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperProof> {
         self.wrapper_proof
             .get(&circuit_type)
             .cloned()
-            .ok_or(Box::new(Error::new(
-                ErrorKind::Other,
-                format!("no data for circuit type {}", circuit_type),
-            )))
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::WrapperProof,
+                Some(circuit_type),
+                None,
+            ))
     }
 
     fn set_base_layer_proof(
@@ -499,9 +514,12 @@ impl BlockDataSource for InMemoryDataSource {
     }
 
     fn get_recursive_tip_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.recursion_tip_proof.clone().ok_or(Box::new(Error::new(
-            ErrorKind::Other,
-            format!("no recursion tip proof"),
-        )))
+        self.recursion_tip_proof
+            .clone()
+            .ok_or(DataSourceError::not_found(
+                ArtifactKind::RecursionTipProof,
+                None,
+                None,
+            ))
     }
 }
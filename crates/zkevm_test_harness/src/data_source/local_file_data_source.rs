@@ -1,4 +1,4 @@
-use super::{BlockDataSource, SetupDataSource, SourceResult};
+use super::{ArtifactKind, BlockDataSource, DataSourceError, SetupDataSource, SourceResult};
 use circuit_definitions::boojum::cs::implementations::setup::FinalizationHintsForProver;
 use circuit_definitions::circuit_definitions::aux_layer::{
     EIP4844VerificationKey, ZkSyncCompressionForWrapperFinalizationHint,
@@ -21,8 +21,9 @@ use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::set
 use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::setup::VerificationKey as SnarkVK;
 
 use derivative::*;
+use std::fs::File;
+use std::io::ErrorKind;
 use std::sync::Arc;
-use std::{error::Error, fs::File};
 
 #[derive(Derivative)]
 #[derivative(Clone, Debug)]
@@ -41,25 +42,43 @@ impl Default for LocalFileDataSource {
 }
 
 impl LocalFileDataSource {
-    fn get_proof<T: for<'de> Deserialize<'de>>(&self, file_name: String) -> SourceResult<T> {
-        let file = File::open(format!("{}/{}.json", self.block_data_location, file_name))
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
-        let result = serde_json::from_reader(file).map_err(|el| Box::new(el) as Box<dyn Error>)?;
+    fn get_proof<T: for<'de> Deserialize<'de>>(
+        &self,
+        file_name: String,
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+        index: Option<usize>,
+    ) -> SourceResult<T> {
+        let file = open_artifact(
+            format!("{}/{}.json", self.block_data_location, file_name),
+            kind,
+            circuit_type,
+            index,
+        )?;
+        let result = serde_json::from_reader(file)?;
 
         Ok(result)
     }
 
     fn set_proof<T: Serialize>(&self, file_name: String, proof: T) -> SourceResult<()> {
-        let file = File::create(format!("{}/{}.json", self.block_data_location, file_name))
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
-        serde_json::to_writer(file, &proof).map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        let file = File::create(format!("{}/{}.json", self.block_data_location, file_name))?;
+        serde_json::to_writer(file, &proof)?;
         Ok(())
     }
 
-    fn get_setup_data<T: for<'de> Deserialize<'de>>(&self, file_name: String) -> SourceResult<T> {
-        let file = File::open(format!("{}/{}.json", self.setup_data_location, file_name))
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
-        let result = serde_json::from_reader(file).map_err(|el| Box::new(el) as Box<dyn Error>)?;
+    fn get_setup_data<T: for<'de> Deserialize<'de>>(
+        &self,
+        file_name: String,
+        kind: ArtifactKind,
+        circuit_type: Option<u8>,
+    ) -> SourceResult<T> {
+        let file = open_artifact(
+            format!("{}/{}.json", self.setup_data_location, file_name),
+            kind,
+            circuit_type,
+            None,
+        )?;
+        let result = serde_json::from_reader(file)?;
 
         Ok(result)
     }
@@ -72,104 +91,152 @@ impl LocalFileDataSource {
     }
 
     /// creates folders if missing
-    pub fn create_folders_for_storing_data(&self) {
+    pub fn create_folders_for_storing_data(&self) -> SourceResult<()> {
         let subfolders = ["/base_layer", "/recursion_layer", "/aux_layer"];
 
         for subfolder in subfolders.iter() {
             let dir_location = format!("{}{}", self.setup_data_location, subfolder);
             if std::fs::read_dir(&dir_location).is_err() {
-                std::fs::create_dir_all(dir_location).unwrap();
+                std::fs::create_dir_all(dir_location)?;
             }
 
             let dir_location = format!("{}{}", self.block_data_location, subfolder);
             if std::fs::read_dir(&dir_location).is_err() {
-                std::fs::create_dir_all(dir_location).unwrap();
+                std::fs::create_dir_all(dir_location)?;
             }
         }
+
+        Ok(())
     }
     pub fn write_pretty<T: Serialize>(filepath: String, proof: T) -> SourceResult<()> {
-        std::fs::write(&filepath, serde_json::to_string_pretty(&proof).unwrap())
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        std::fs::write(&filepath, serde_json::to_string_pretty(&proof)?)?;
         Ok(())
     }
 }
 
+/// Opens an artifact file, reporting a missing file as a missing artifact.
+fn open_artifact(
+    path: String,
+    kind: ArtifactKind,
+    circuit_type: Option<u8>,
+    index: Option<usize>,
+) -> SourceResult<File> {
+    File::open(path).map_err(|err| match err.kind() {
+        ErrorKind::NotFound => DataSourceError::not_found(kind, circuit_type, index),
+        _ => DataSourceError::Io(err),
+    })
+}
+
 impl SetupDataSource for LocalFileDataSource {
     fn get_base_layer_vk(&self, circuit_type: u8) -> SourceResult<ZkSyncBaseLayerVerificationKey> {
-        self.get_setup_data(format!("base_layer/vk_{}", circuit_type))
+        self.get_setup_data(
+            format!("base_layer/vk_{}", circuit_type),
+            ArtifactKind::BaseLayerVk,
+            Some(circuit_type),
+        )
     }
     fn get_base_layer_finalization_hint(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncBaseLayerFinalizationHint> {
-        self.get_setup_data(format!("base_layer/finalization_hint_{}", circuit_type))
+        self.get_setup_data(
+            format!("base_layer/finalization_hint_{}", circuit_type),
+            ArtifactKind::BaseLayerFinalizationHint,
+            Some(circuit_type),
+        )
     }
     fn get_recursion_layer_vk(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
-        self.get_setup_data(format!("recursion_layer/vk_{}", circuit_type))
+        self.get_setup_data(
+            format!("recursion_layer/vk_{}", circuit_type),
+            ArtifactKind::RecursionLayerVk,
+            Some(circuit_type),
+        )
     }
     fn get_recursion_layer_node_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
-        self.get_setup_data("recursion_layer/vk_node".to_string())
+        self.get_setup_data(
+            "recursion_layer/vk_node".to_string(),
+            ArtifactKind::RecursionLayerNodeVk,
+            None,
+        )
     }
     fn get_recursion_layer_finalization_hint(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
-        self.get_setup_data(format!(
-            "recursion_layer/finalization_hint_{}",
-            circuit_type
-        ))
+        self.get_setup_data(
+            format!("recursion_layer/finalization_hint_{}", circuit_type),
+            ArtifactKind::RecursionLayerFinalizationHint,
+            Some(circuit_type),
+        )
     }
     fn get_recursion_layer_node_finalization_hint(
         &self,
     ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
-        self.get_setup_data("recursion_layer/finalization_hint_node".to_string())
+        self.get_setup_data(
+            "recursion_layer/finalization_hint_node".to_string(),
+            ArtifactKind::RecursionLayerNodeFinalizationHint,
+            None,
+        )
     }
 
     fn get_compression_vk(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncCompressionLayerVerificationKey> {
-        self.get_setup_data(format!("aux_layer/compression_vk_{}", circuit_type))
+        self.get_setup_data(
+            format!("aux_layer/compression_vk_{}", circuit_type),
+            ArtifactKind::CompressionVk,
+            Some(circuit_type),
+        )
     }
     fn get_compression_hint(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncCompressionLayerFinalizationHint> {
-        self.get_setup_data(format!("aux_layer/compression_hint_{}", circuit_type))
+        self.get_setup_data(
+            format!("aux_layer/compression_hint_{}", circuit_type),
+            ArtifactKind::CompressionHint,
+            Some(circuit_type),
+        )
     }
     fn get_compression_for_wrapper_vk(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncCompressionForWrapperVerificationKey> {
-        self.get_setup_data(format!(
-            "aux_layer/compression_for_wrapper_vk_{}",
-            circuit_type
-        ))
+        self.get_setup_data(
+            format!("aux_layer/compression_for_wrapper_vk_{}", circuit_type),
+            ArtifactKind::CompressionForWrapperVk,
+            Some(circuit_type),
+        )
     }
     fn get_compression_for_wrapper_hint(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncCompressionForWrapperFinalizationHint> {
-        self.get_setup_data(format!(
-            "aux_layer/compression_for_wrapper_hint_{}",
-            circuit_type
-        ))
+        self.get_setup_data(
+            format!("aux_layer/compression_for_wrapper_hint_{}", circuit_type),
+            ArtifactKind::CompressionForWrapperHint,
+            Some(circuit_type),
+        )
     }
     fn get_wrapper_setup(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperSetup> {
         println!("Read wrapper setup from file. Can take a while.");
         let start = std::time::Instant::now();
 
-        let mut file = File::open(format!(
-            "{}/aux_layer/wrapper_setup_{}.setup",
-            self.setup_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        let mut file = open_artifact(
+            format!(
+                "{}/aux_layer/wrapper_setup_{}.setup",
+                self.setup_data_location, circuit_type
+            ),
+            ArtifactKind::WrapperSetup,
+            Some(circuit_type),
+            None,
+        )?;
 
-        let result =
-            Arc::new(SnarkSetup::read(&mut file).map_err(|el| Box::new(el) as Box<dyn Error>)?);
+        let result = Arc::new(SnarkSetup::read(&mut file)?);
 
         let result = ZkSyncSnarkWrapperSetup::from_inner(circuit_type, result);
 
@@ -178,16 +245,17 @@ impl SetupDataSource for LocalFileDataSource {
         Ok(result)
     }
     fn get_wrapper_vk(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperVK> {
-        let mut file = File::open(format!(
-            "{}/aux_layer/wrapper_vk_{}.key",
-            self.setup_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        let mut file = open_artifact(
+            format!(
+                "{}/aux_layer/wrapper_vk_{}.key",
+                self.setup_data_location, circuit_type
+            ),
+            ArtifactKind::WrapperVk,
+            Some(circuit_type),
+            None,
+        )?;
 
-        let result = ZkSyncSnarkWrapperVK::from_inner(
-            circuit_type,
-            SnarkVK::read(&mut file).map_err(|el| Box::new(el) as Box<dyn Error>)?,
-        );
+        let result = ZkSyncSnarkWrapperVK::from_inner(circuit_type, SnarkVK::read(&mut file)?);
 
         Ok(result)
     }
@@ -279,13 +347,9 @@ impl SetupDataSource for LocalFileDataSource {
         let mut file = File::create(format!(
             "{}/aux_layer/wrapper_setup_{}.setup",
             self.setup_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        ))?;
 
-        setup
-            .into_inner()
-            .write(&mut file)
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        setup.into_inner().write(&mut file)?;
 
         println!("Wrapper setup written to file. Took {:?}", start.elapsed());
 
@@ -296,23 +360,28 @@ impl SetupDataSource for LocalFileDataSource {
         let mut file = File::create(format!(
             "{}/aux_layer/wrapper_vk_{}.key",
             self.setup_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        ))?;
 
-        vk.into_inner()
-            .write(&mut file)
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        vk.into_inner().write(&mut file)?;
 
         Ok(())
     }
 
     fn get_recursion_tip_vk(&self) -> SourceResult<ZkSyncRecursionLayerVerificationKey> {
-        self.get_setup_data("recursion_layer/vk_recursion_tip".to_string())
+        self.get_setup_data(
+            "recursion_layer/vk_recursion_tip".to_string(),
+            ArtifactKind::RecursionTipVk,
+            None,
+        )
     }
     fn get_recursion_tip_finalization_hint(
         &self,
     ) -> SourceResult<ZkSyncRecursionLayerFinalizationHint> {
-        self.get_setup_data("recursion_layer/finalization_hint_recursion_tip".to_string())
+        self.get_setup_data(
+            "recursion_layer/finalization_hint_recursion_tip".to_string(),
+            ArtifactKind::RecursionTipFinalizationHint,
+            None,
+        )
     }
     fn set_recursion_tip_vk(
         &mut self,
@@ -337,10 +406,12 @@ impl BlockDataSource for LocalFileDataSource {
         circuit_type: u8,
         index: usize,
     ) -> SourceResult<ZkSyncBaseLayerProof> {
-        self.get_proof(format!(
-            "base_layer/basic_circuit_proof_{}_{}",
-            circuit_type, index
-        ))
+        self.get_proof(
+            format!("base_layer/basic_circuit_proof_{}_{}", circuit_type, index),
+            ArtifactKind::BaseLayerProof,
+            Some(circuit_type),
+            Some(index),
+        )
     }
 
     fn get_leaf_layer_proof(
@@ -348,10 +419,15 @@ impl BlockDataSource for LocalFileDataSource {
         circuit_type: u8,
         index: usize,
     ) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.get_proof(format!(
-            "recursion_layer/leaf_layer_proof_{}_{}",
-            circuit_type, index
-        ))
+        self.get_proof(
+            format!(
+                "recursion_layer/leaf_layer_proof_{}_{}",
+                circuit_type, index
+            ),
+            ArtifactKind::LeafLayerProof,
+            Some(circuit_type),
+            Some(index),
+        )
     }
     fn get_node_layer_proof(
         &self,
@@ -359,39 +435,58 @@ impl BlockDataSource for LocalFileDataSource {
         step: usize,
         index: usize,
     ) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.get_proof(format!(
-            "recursion_layer/node_layer_proof_{}_{}_{}",
-            circuit_type, step, index
-        ))
+        self.get_proof(
+            format!(
+                "recursion_layer/node_layer_proof_{}_{}_{}",
+                circuit_type, step, index
+            ),
+            ArtifactKind::NodeLayerProof { step },
+            Some(circuit_type),
+            Some(index),
+        )
     }
 
     fn get_scheduler_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.get_proof("recursion_layer/scheduler_proof".to_string())
+        self.get_proof(
+            "recursion_layer/scheduler_proof".to_string(),
+            ArtifactKind::SchedulerProof,
+            None,
+            None,
+        )
     }
     fn get_compression_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncCompressionLayerProof> {
-        self.get_proof(format!("aux_layer/compression_proof_{}", circuit_type))
+        self.get_proof(
+            format!("aux_layer/compression_proof_{}", circuit_type),
+            ArtifactKind::CompressionProof,
+            Some(circuit_type),
+            None,
+        )
     }
 
     fn get_compression_for_wrapper_proof(
         &self,
         circuit_type: u8,
     ) -> SourceResult<ZkSyncCompressionForWrapperProof> {
-        self.get_proof(format!(
-            "aux_layer/compression_for_wrapper_proof_{}",
-            circuit_type
-        ))
+        self.get_proof(
+            format!("aux_layer/compression_for_wrapper_proof_{}", circuit_type),
+            ArtifactKind::CompressionForWrapperProof,
+            Some(circuit_type),
+            None,
+        )
     }
     fn get_wrapper_proof(&self, circuit_type: u8) -> SourceResult<ZkSyncSnarkWrapperProof> {
-        let mut file = File::open(format!(
-            "{}/aux_layer/wrapper_proof_{}.proof",
-            self.block_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        let mut file = open_artifact(
+            format!(
+                "{}/aux_layer/wrapper_proof_{}.proof",
+                self.block_data_location, circuit_type
+            ),
+            ArtifactKind::WrapperProof,
+            Some(circuit_type),
+            None,
+        )?;
 
-        let result = ZkSyncSnarkWrapperProof::from_inner(
-            circuit_type,
-            SnarkProof::read(&mut file).map_err(|el| Box::new(el) as Box<dyn Error>)?,
-        );
+        let result =
+            ZkSyncSnarkWrapperProof::from_inner(circuit_type, SnarkProof::read(&mut file)?);
 
         Ok(result)
     }
@@ -462,13 +557,9 @@ impl BlockDataSource for LocalFileDataSource {
         let mut file = File::create(format!(
             "{}/aux_layer/wrapper_proof_{}.proof",
             self.block_data_location, circuit_type
-        ))
-        .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        ))?;
 
-        proof
-            .into_inner()
-            .write(&mut file)
-            .map_err(|el| Box::new(el) as Box<dyn Error>)?;
+        proof.into_inner().write(&mut file)?;
 
         Ok(())
     }
@@ -477,6 +568,11 @@ impl BlockDataSource for LocalFileDataSource {
     }
 
     fn get_recursive_tip_proof(&self) -> SourceResult<ZkSyncRecursionLayerProof> {
-        self.get_proof("recursion_layer/recursive_tip_proof".to_string())
+        self.get_proof(
+            "recursion_layer/recursive_tip_proof".to_string(),
+            ArtifactKind::RecursionTipProof,
+            None,
+            None,
+        )
     }
 }
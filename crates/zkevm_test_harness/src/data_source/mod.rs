@@ -1,13 +1,17 @@
-use std::error::Error;
-
 use circuit_definitions::boojum::cs::implementations::setup::FinalizationHintsForProver;
 use circuit_definitions::circuit_definitions::aux_layer::*;
 use circuit_definitions::circuit_definitions::base_layer::*;
 use circuit_definitions::circuit_definitions::recursion_layer::*;
 
-pub type SourceResult<T> = Result<T, Box<dyn Error>>;
+pub type SourceResult<T> = Result<T, DataSourceError>;
+pub mod artifact_data_source;
+pub mod content_addressed_data_source;
+mod error;
 pub mod in_memory_data_source;
 pub mod local_file_data_source;
+pub mod object_store_data_source;
+
+pub use self::error::{ArtifactKind, DataSourceError};
 
 // Object save trait to just get things for SYSTEM
 pub trait SetupDataSource {
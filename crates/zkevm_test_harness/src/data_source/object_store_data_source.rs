@@ -0,0 +1,236 @@
+use super::artifact_data_source::{
+    ArtifactBackend, ArtifactDataSource, ArtifactEncoding, ArtifactFormat,
+};
+use super::content_addressed_data_source::write_atomically;
+use super::{DataSourceError, SourceResult};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Minimal blob store interface, e.g. over an S3-compatible bucket. Data sources are used from
+/// synchronous proving code, so implementations backed by async clients should block on the
+/// request (e.g. with a runtime handle they own).
+pub trait ObjectStore: Send + Sync {
+    /// Returns `None` if there is no object under `key`.
+    fn get(&self, key: &str) -> SourceResult<Option<Vec<u8>>>;
+    fn put(&self, key: &str, value: Vec<u8>) -> SourceResult<()>;
+}
+
+impl<S: ObjectStore + ?Sized> ObjectStore for Arc<S> {
+    fn get(&self, key: &str) -> SourceResult<Option<Vec<u8>>> {
+        (**self).get(key)
+    }
+    fn put(&self, key: &str, value: Vec<u8>) -> SourceResult<()> {
+        (**self).put(key, value)
+    }
+}
+
+/// Stores every object as a file under `root`, keys are relative paths.
+#[derive(Clone, Debug)]
+pub struct LocalFileObjectStore {
+    root: PathBuf,
+}
+
+impl LocalFileObjectStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> SourceResult<PathBuf> {
+        let relative = Path::new(key);
+        let is_valid = !key.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !is_valid {
+            return Err(DataSourceError::InvalidSource(format!(
+                "invalid object key: {:?}",
+                key
+            )));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+impl ObjectStore for LocalFileObjectStore {
+    fn get(&self, key: &str) -> SourceResult<Option<Vec<u8>>> {
+        match fs::read(self.path(key)?) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+    fn put(&self, key: &str, value: Vec<u8>) -> SourceResult<()> {
+        write_atomically(&self.path(key)?, &value)
+    }
+}
+
+/// In-process fake of a remote store, for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryObjectStore {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<_> = self.objects.lock().unwrap().keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+}
+
+impl ObjectStore for InMemoryObjectStore {
+    fn get(&self, key: &str) -> SourceResult<Option<Vec<u8>>> {
+        Ok(self.objects.lock().unwrap().get(key).cloned())
+    }
+    fn put(&self, key: &str, value: Vec<u8>) -> SourceResult<()> {
+        self.objects.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// [`SetupDataSource`](super::SetupDataSource) and [`BlockDataSource`](super::BlockDataSource)
+/// over any [`ObjectStore`]. Artifacts are encoded with bincode, SNARK wrapper artifacts with their
+/// native bellman encoding. All keys are put under `prefix`, so e.g. several protocol versions or
+/// batches can share a bucket.
+pub type ObjectStoreDataSource<S> = ArtifactDataSource<ObjectStoreBackend<S>>;
+
+impl<S: ObjectStore> ObjectStoreDataSource<S> {
+    pub fn new(store: S) -> Self {
+        Self::with_prefix(store, "")
+    }
+
+    pub fn with_prefix(store: S, prefix: &str) -> Self {
+        let backend = ObjectStoreBackend {
+            store,
+            prefix: prefix.trim_matches('/').to_string(),
+        };
+        Self::from_backend(backend, ArtifactFormat::Bincode)
+    }
+
+    pub fn store(&self) -> &S {
+        &self.backend().store
+    }
+}
+
+/// [`ArtifactBackend`] of an [`ObjectStoreDataSource`]. Every object starts with an
+/// `<encoding>\n` line, so objects can be decoded without knowing how they were written.
+#[derive(Clone, Debug)]
+pub struct ObjectStoreBackend<S> {
+    store: S,
+    prefix: String,
+}
+
+impl<S> ObjectStoreBackend<S> {
+    fn key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+}
+
+impl<S: ObjectStore> ArtifactBackend for ObjectStoreBackend<S> {
+    fn get_artifact(&self, name: &str) -> SourceResult<Option<(ArtifactEncoding, Vec<u8>)>> {
+        let key = self.key(name);
+        let Some(mut object) = self.store.get(&key)? else {
+            return Ok(None);
+        };
+        let Some(header_len) = object.iter().position(|b| *b == b'\n') else {
+            return Err(DataSourceError::InvalidSource(format!(
+                "object {} has no encoding header",
+                key
+            )));
+        };
+        let encoding = ArtifactEncoding::parse(&String::from_utf8_lossy(&object[..header_len]))?;
+        object.drain(..=header_len);
+
+        Ok(Some((encoding, object)))
+    }
+
+    fn put_artifact(
+        &self,
+        name: &str,
+        encoding: ArtifactEncoding,
+        payload: &[u8],
+    ) -> SourceResult<()> {
+        let encoding = encoding.as_str();
+        let mut object = Vec::with_capacity(encoding.len() + 1 + payload.len());
+        object.extend_from_slice(encoding.as_bytes());
+        object.push(b'\n');
+        object.extend_from_slice(payload);
+        self.store.put(&self.key(name), object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::artifact_data_source::tests::hint;
+    use crate::data_source::{ArtifactKind, BlockDataSource, SetupDataSource};
+
+    #[test]
+    fn test_in_memory_store_roundtrip() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let mut source = ObjectStoreDataSource::with_prefix(store.clone(), "0.28.0/");
+        source
+            .set_base_layer_finalization_hint(hint(1 << 20))
+            .unwrap();
+
+        assert_eq!(
+            store.keys(),
+            vec!["0.28.0/base_layer/finalization_hint_1".to_string()]
+        );
+        let restored = source.get_base_layer_finalization_hint(1).unwrap();
+        assert_eq!(restored.into_inner().final_trace_len, 1 << 20);
+
+        let other_version = ObjectStoreDataSource::with_prefix(store, "0.27.0");
+        assert!(matches!(
+            other_version.get_base_layer_finalization_hint(1),
+            Err(DataSourceError::NotFound {
+                kind: ArtifactKind::BaseLayerFinalizationHint,
+                circuit_type: Some(1),
+                index: None,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_local_file_store_roundtrip() {
+        let root = tempfile::tempdir().unwrap();
+
+        let mut source = ObjectStoreDataSource::new(LocalFileObjectStore::new(root.path()));
+        source
+            .set_base_layer_finalization_hint(hint(1 << 21))
+            .unwrap();
+        let restored = source.get_base_layer_finalization_hint(1).unwrap();
+        assert_eq!(restored.into_inner().final_trace_len, 1 << 21);
+        assert!(source.get_scheduler_proof().unwrap_err().is_not_found());
+
+        assert!(source.store().get("../outside").is_err());
+    }
+
+    #[test]
+    fn test_malformed_object_is_rejected() {
+        let store = Arc::new(InMemoryObjectStore::new());
+        let source = ObjectStoreDataSource::new(store.clone());
+        for object in [&b"no header"[..], b"yaml\n{}"] {
+            store
+                .put("base_layer/finalization_hint_1", object.to_vec())
+                .unwrap();
+            assert!(matches!(
+                source.get_base_layer_finalization_hint(1),
+                Err(DataSourceError::InvalidSource(_))
+            ));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use codegen::Scope;
 use rayon::prelude::*;
+use structopt::StructOpt;
 
 use zkevm_test_harness::capacity_estimator::{
     code_decommitter_capacity, code_decommittments_sorter_capacity, ecadd_capacity, ecmul_capacity,
@@ -8,7 +9,27 @@ use zkevm_test_harness::capacity_estimator::{
     ram_permutation_capacity, secp256r1_verify_capacity, sha256_rf_capacity,
     storage_application_capacity, storage_sorter_capacity, transient_storage_sorter_capacity,
 };
+use zkevm_test_harness::geometry_config::ProtocolGeometry;
 use zkevm_test_harness::toolset::GeometryConfig;
+use zkevm_test_harness::zk_evm::zkevm_opcode_defs::circuit_pricing::{
+    derive_circuit_prices, render_circuit_prices, render_circuit_prices_report, CircuitCapacities,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "geometry_config_generator")]
+struct Arguments {
+    /// Use the geometry of the given protocol version (e.g. `1.5.3`) instead of measuring it
+    #[structopt(long = "protocol-geometry")]
+    protocol_geometry: Option<String>,
+
+    /// Derive the circuit prices from the geometry and report the difference with `circuit_prices.rs`
+    #[structopt(long = "prices")]
+    prices: bool,
+
+    /// Shrink the capacities by the given percentage before deriving the prices
+    #[structopt(long = "safety-margin", default_value = "0")]
+    safety_margin: u32,
+}
 
 fn all_runners() -> Vec<Box<dyn Fn() -> usize + Send>> {
     vec![
@@ -87,8 +108,59 @@ pub fn compute_config() -> GeometryConfig {
     config
 }
 
+fn parse_protocol_geometry(version: &str) -> ProtocolGeometry {
+    match version {
+        "1.4.0" => ProtocolGeometry::V1_4_0,
+        "1.4.1" => ProtocolGeometry::V1_4_1,
+        "1.4.2" => ProtocolGeometry::V1_4_2,
+        "1.5.0" => ProtocolGeometry::V1_5_0,
+        "1.5.1" => ProtocolGeometry::V1_5_1,
+        "1.5.2" => ProtocolGeometry::V1_5_2,
+        "1.5.3" => ProtocolGeometry::V1_5_3,
+        _ => panic!("unknown protocol geometry version {}", version),
+    }
+}
+
+fn circuit_capacities(config: &GeometryConfig) -> CircuitCapacities {
+    CircuitCapacities {
+        cycles_per_vm_snapshot: config.cycles_per_vm_snapshot,
+        cycles_per_ram_permutation: config.cycles_per_ram_permutation,
+        cycles_per_code_decommitter: config.cycles_per_code_decommitter,
+        cycles_per_storage_application: config.cycles_per_storage_application,
+        cycles_per_keccak256_circuit: config.cycles_per_keccak256_circuit,
+        cycles_per_sha256_circuit: config.cycles_per_sha256_circuit,
+        cycles_per_ecrecover_circuit: config.cycles_per_ecrecover_circuit,
+        cycles_code_decommitter_sorter: config.cycles_code_decommitter_sorter,
+        cycles_per_log_demuxer: config.cycles_per_log_demuxer,
+        cycles_per_storage_sorter: config.cycles_per_storage_sorter,
+        cycles_per_transient_storage_sorter: config.cycles_per_transient_storage_sorter,
+        cycles_per_events_or_l1_messages_sorter: config.cycles_per_events_or_l1_messages_sorter,
+        limit_for_l1_messages_pudata_hasher: config.limit_for_l1_messages_pudata_hasher,
+        ..CircuitCapacities::default()
+    }
+}
+
 fn main() {
-    let computed_config = compute_config();
+    let args = Arguments::from_args();
+
+    let config = match args.protocol_geometry.as_deref() {
+        Some(version) => parse_protocol_geometry(version).config(),
+        None => {
+            let computed_config = compute_config();
+            print_config(&computed_config);
+            computed_config
+        }
+    };
+
+    if args.prices {
+        let (prices, diff) =
+            derive_circuit_prices(&circuit_capacities(&config), args.safety_margin);
+        println!("{}", render_circuit_prices_report(&diff));
+        println!("Derived prices:\n{}", render_circuit_prices(&prices));
+    }
+}
+
+fn print_config(computed_config: &GeometryConfig) {
     let mut scope = Scope::new();
     scope.import("crate::toolset", "GeometryConfig");
     let function = scope.new_fn("get_geometry_config");
@@ -42,6 +42,8 @@ pub mod witness;
 
 // Debugging tools (for example for failed proofs).
 pub mod debug;
+pub mod proof_inspection;
+pub mod proof_chain_replay;
 
 pub use crate::zk_evm::ethereum_types;
 
@@ -0,0 +1,764 @@
+//!
+//! Replays the aggregation of a block from stored artifacts: base layer proofs and the
+//! recursion queues produced by `run_vms`. Every produced proof is
+//! checkpointed into the block data source, so an interrupted replay continues from the
+//! last stored proof instead of starting from the base layer again. The hash of the input is
+//! kept in a checkpoint file next to the proofs, so the proofs of a different block are never
+//! reused.
+//!
+use crate::boojum::cs::implementations::hints::{DenseVariablesCopyHint, DenseWitnessCopyHint};
+use crate::boojum::cs::implementations::polynomial_storage::{SetupBaseStorage, SetupStorage};
+use crate::boojum::cs::implementations::pow::NoPow;
+use crate::boojum::cs::implementations::setup::FinalizationHintsForProver;
+use crate::boojum::cs::implementations::verifier::VerificationKey;
+use crate::boojum::cs::oracle::merkle_tree::MerkleTreeWithCap;
+use crate::boojum::field::goldilocks::{GoldilocksExt2, GoldilocksField};
+use crate::boojum::field::{Field, U64Representable};
+use crate::boojum::gadgets::queue::QueueState;
+use crate::boojum::gadgets::recursion::recursive_tree_hasher::CircuitGoldilocksPoseidon2Sponge;
+use crate::boojum::gadgets::traits::allocatable::CSAllocatable;
+use crate::boojum::worker::Worker;
+use crate::compute_setups::{
+    compute_leaf_params, get_recursion_tip_circuit, get_scheduler_circuit,
+};
+use crate::data_source::{BlockDataSource, DataSourceError, SetupDataSource};
+use crate::empty_node_proof;
+use crate::proof_wrapper_utils::{
+    compute_compression_circuits, compute_compression_for_wrapper_circuit,
+    compute_wrapper_proof_and_vk, open_trusted_setup_file, WrapperConfig,
+};
+use crate::prover_utils::{
+    create_recursive_layer_setup_data, prove_recursion_layer_circuit, verify_any_proof,
+    verify_recursion_layer_proof, ProofEnvelope, ProofVerificationError,
+};
+use crate::sha2::{Digest, Sha256};
+use crate::snark_wrapper::franklin_crypto::bellman::worker::Worker as BellmanWorker;
+use crate::witness::recursive_aggregation::{
+    compute_node_vk_commitment, compute_recursive_circuit_input_commitment, create_leaf_witnesses,
+    create_node_witnesses, RecursionQueueSimulator,
+};
+use crate::witness::utils::take_sponge_like_queue_state_from_simulator;
+use crate::zkevm_circuits::fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH;
+use crate::zkevm_circuits::fsm_input_output::ClosedFormInputCompactFormWitness;
+use crate::zkevm_circuits::recursion::recursion_tip::input::{
+    RecursionTipInputWitness, RECURSION_TIP_ARITY,
+};
+use crate::zkevm_circuits::scheduler::auxiliary::{
+    BaseLayerCircuitType, NUM_CIRCUIT_TYPES_TO_SCHEDULE,
+};
+use crate::zkevm_circuits::scheduler::input::SchedulerCircuitInstanceWitness;
+use circuit_definitions::circuit_definitions::base_layer::{
+    ZkSyncBaseLayerClosedFormInput, ZkSyncBaseLayerStorage,
+};
+use circuit_definitions::circuit_definitions::recursion_layer::{
+    base_circuit_type_into_recursive_leaf_circuit_type, RecursiveProofsTreeHasher,
+    ZkSyncRecursionLayerProof, ZkSyncRecursionLayerStorageType,
+    ZkSyncRecursionLayerVerificationKey, ZkSyncRecursiveLayerCircuit,
+};
+use circuit_definitions::{
+    recursion_layer_proof_config, RECURSION_LAYER_CAP_SIZE, RECURSION_LAYER_FRI_LDE_FACTOR,
+};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+/// Name of the replay input file in the block data folder.
+pub const REPLAY_INPUT_FILE: &str = "replay_input.json";
+/// Name of the checkpoint file with the hash of the input of the stored proofs, in the block
+/// data folder.
+pub const REPLAY_CHECKPOINT_FILE: &str = "replay_input_hash";
+
+/// Recursion queue of a single base circuit type, with the closed form inputs of its circuits.
+pub type RecursionQueue = (
+    u64,
+    RecursionQueueSimulator<GoldilocksField>,
+    Vec<ZkSyncBaseLayerClosedFormInput<GoldilocksField>>,
+);
+
+/// The part of the `run_vms` output that is needed on top of the base layer
+/// proofs to aggregate them.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ReplayInput {
+    pub recursion_queues: Vec<RecursionQueue>,
+    pub scheduler_partial_input: SchedulerCircuitInstanceWitness<
+        GoldilocksField,
+        CircuitGoldilocksPoseidon2Sponge,
+        GoldilocksExt2,
+    >,
+}
+
+impl ReplayInput {
+    pub fn new(
+        scheduler_partial_input: SchedulerCircuitInstanceWitness<
+            GoldilocksField,
+            CircuitGoldilocksPoseidon2Sponge,
+            GoldilocksExt2,
+        >,
+    ) -> Self {
+        Self {
+            recursion_queues: vec![],
+            scheduler_partial_input,
+        }
+    }
+
+    /// Adds the queue as sent in `WitnessGenerationArtifact::RecursionQueue`.
+    pub fn add_recursion_queue(
+        &mut self,
+        (circuit_type, queue, inputs): (
+            u64,
+            RecursionQueueSimulator<GoldilocksField>,
+            Vec<ClosedFormInputCompactFormWitness<GoldilocksField>>,
+        ),
+    ) {
+        let inputs = inputs
+            .into_iter()
+            .map(|el| ZkSyncBaseLayerStorage::from_inner(circuit_type as u8, el))
+            .collect();
+        self.recursion_queues.push((circuit_type, queue, inputs));
+    }
+
+    pub fn read_from_file(path: impl AsRef<Path>) -> Result<Self, DataSourceError> {
+        let file = File::open(path)?;
+        let result = serde_json::from_reader(file)?;
+
+        Ok(result)
+    }
+
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> Result<(), DataSourceError> {
+        let file = File::create(path)?;
+        serde_json::to_writer(file, self)?;
+
+        Ok(())
+    }
+
+    /// Hex encoded sha256 of the JSON encoding.
+    pub fn hash(&self) -> Result<String, DataSourceError> {
+        let encoding = serde_json::to_vec(self)?;
+
+        Ok(hex::encode(Sha256::digest(encoding)))
+    }
+}
+
+/// Step of the aggregation that produces a single proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayStep {
+    Leaf {
+        circuit_type: u8,
+        index: usize,
+    },
+    Node {
+        circuit_type: u8,
+        depth: usize,
+        index: usize,
+    },
+    RecursionTip,
+    Scheduler,
+}
+
+impl fmt::Display for ReplayStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayStep::Leaf {
+                circuit_type,
+                index,
+            } => write!(f, "leaf {} for circuit type {}", index, circuit_type),
+            ReplayStep::Node {
+                circuit_type,
+                depth,
+                index,
+            } => write!(
+                f,
+                "node {} at depth {} for circuit type {}",
+                index, depth, circuit_type
+            ),
+            ReplayStep::RecursionTip => write!(f, "recursion tip"),
+            ReplayStep::Scheduler => write!(f, "scheduler"),
+        }
+    }
+}
+
+/// Progress of the replay, reported to the caller of [`replay_proof_chain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayEvent {
+    /// The proof of the step is computed.
+    Proving(ReplayStep),
+    /// The proof of the step is taken from the source, after checking it.
+    Reusing(ReplayStep),
+    /// The scheduler proof is compressed and wrapped.
+    Wrapping,
+}
+
+impl fmt::Display for ReplayEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayEvent::Proving(step) => write!(f, "proving {}", step),
+            ReplayEvent::Reusing(step) => write!(f, "reusing the stored proof of {}", step),
+            ReplayEvent::Wrapping => write!(f, "compressing and wrapping the scheduler proof"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Missing or broken artifacts. Setup data must be generated before the replay.
+    DataSource(DataSourceError),
+    /// The produced proof doesn't verify against the stored verification key.
+    InvalidProof(ReplayStep),
+    /// The proof left in the source by an earlier replay doesn't verify against the stored
+    /// verification key.
+    InvalidStoredProof(ReplayStep),
+    /// The proof left in the source by an earlier replay is valid, but proves a different
+    /// input of the circuit.
+    StoredProofInputMismatch(ReplayStep),
+    /// The proofs in the source were produced from a different input.
+    InputMismatch {
+        stored_hash: String,
+        input_hash: String,
+    },
+    /// The input doesn't describe a block, e.g. it has queues of unknown circuit types.
+    InvalidInput(String),
+    /// The trusted setup needed to wrap the proof can't be opened.
+    TrustedSetup(std::io::Error),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::DataSource(err) => write!(f, "{}", err),
+            ReplayError::InvalidProof(step) => {
+                write!(
+                    f,
+                    "proof of {} doesn't match the stored verification key",
+                    step
+                )
+            }
+            ReplayError::InvalidStoredProof(step) => write!(
+                f,
+                "stored proof of {} doesn't match the stored verification key, remove it to prove it again",
+                step
+            ),
+            ReplayError::StoredProofInputMismatch(step) => write!(
+                f,
+                "stored proof of {} proves a different input of the circuit, remove it to prove it again",
+                step
+            ),
+            ReplayError::InputMismatch {
+                stored_hash,
+                input_hash,
+            } => write!(
+                f,
+                "stored proofs were produced from the input with hash {}, not {}",
+                stored_hash, input_hash
+            ),
+            ReplayError::InvalidInput(reason) => write!(f, "invalid replay input: {}", reason),
+            ReplayError::TrustedSetup(err) => write!(f, "can't open the trusted setup: {}", err),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReplayError::DataSource(err) => Some(err),
+            ReplayError::TrustedSetup(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<DataSourceError> for ReplayError {
+    fn from(err: DataSourceError) -> Self {
+        ReplayError::DataSource(err)
+    }
+}
+
+///
+/// Aggregates the base layer proofs from `source` up to the scheduler proof and, if
+/// `wrapper_config` is given, compresses and wraps it. Proofs already present in `source`
+/// are reused, so the replay can be restarted after a failure.
+///
+/// `source` must contain the verification keys and finalization hints of the base, leaf,
+/// node, recursion tip and scheduler circuits. Reused proofs are verified against them, and
+/// `on_event` is called before every proof that is computed or reused.
+///
+/// `checkpoint` is the file with the hash of the input the proofs in `source` were produced
+/// from, usually [`REPLAY_CHECKPOINT_FILE`] in the block folder. It's created by the first
+/// replay, and later replays of a different input fail instead of reusing the proofs.
+///
+pub fn replay_proof_chain<DS: SetupDataSource + BlockDataSource>(
+    source: &mut DS,
+    input: &ReplayInput,
+    checkpoint: &Path,
+    wrapper_config: Option<WrapperConfig>,
+    mut on_event: impl FnMut(ReplayEvent),
+) -> Result<ZkSyncRecursionLayerProof, ReplayError> {
+    let worker = Worker::new();
+
+    let leaf_params = compute_leaf_params(source)?;
+    let node_vk = source.get_recursion_layer_node_vk()?;
+    let node_vk_commitment = compute_node_vk_commitment(node_vk.clone());
+
+    // fail before the aggregation rather than after it
+    if wrapper_config.is_some() {
+        open_trusted_setup_file().map_err(ReplayError::TrustedSetup)?;
+    }
+
+    check_checkpoint(checkpoint, input.hash()?)?;
+
+    // queues are matched with the proofs in the order of circuit types
+    let mut recursion_queues = input.recursion_queues.clone();
+    recursion_queues.sort_by_key(|(circuit_type, _, _)| *circuit_type);
+
+    let mut node_proofs = vec![];
+    for subset in recursion_queues.iter().cloned() {
+        let base_circuit_type = subset.0 as u8;
+        if subset.1.num_items == 0 {
+            continue;
+        }
+
+        let params = leaf_params
+            .iter()
+            .find(|el| el.0 == base_circuit_type)
+            .cloned()
+            .ok_or_else(|| {
+                ReplayError::InvalidInput(format!(
+                    "unknown base circuit type {} of a recursion queue",
+                    subset.0
+                ))
+            })?;
+        let leaf_circuit_type = base_circuit_type_into_recursive_leaf_circuit_type(
+            BaseLayerCircuitType::from_numeric_value(base_circuit_type),
+        );
+
+        let base_proofs = (0..subset.1.num_items as usize)
+            .map(|idx| source.get_base_layer_proof(base_circuit_type, idx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let base_vk = source.get_base_layer_vk(base_circuit_type)?;
+
+        let (mut aggregations, leaf_circuits, _) =
+            create_leaf_witnesses(subset, base_proofs, base_vk, params);
+
+        let leaf_vk = source.get_recursion_layer_vk(leaf_circuit_type as u8)?;
+        let mut setup = None;
+        let mut proofs = vec![];
+        for (index, circuit) in leaf_circuits.into_iter().enumerate() {
+            let step = ReplayStep::Leaf {
+                circuit_type: leaf_circuit_type as u8,
+                index,
+            };
+            let proof = match source.get_leaf_layer_proof(leaf_circuit_type as u8, index) {
+                Ok(proof) => {
+                    on_event(ReplayEvent::Reusing(step));
+                    let expected_input = compute_recursive_circuit_input_commitment(&circuit);
+                    check_stored_proof(source, proof, leaf_circuit_type, expected_input, step)?
+                }
+                Err(err) if err.is_not_found() => {
+                    on_event(ReplayEvent::Proving(step));
+                    let proof = prove(circuit, &leaf_vk, &mut setup, &worker, step)?;
+                    source.set_leaf_layer_proof(index, proof.clone())?;
+                    proof
+                }
+                Err(err) => return Err(err.into()),
+            };
+            proofs.push(proof);
+        }
+
+        // nodes aggregate the leaves first, and then the nodes of the previous depth
+        // until a single proof is left
+        let mut setup = None;
+        let mut vk = leaf_vk;
+        let mut depth = 0;
+        loop {
+            let (next_aggregations, node_circuits) =
+                create_node_witnesses(aggregations, proofs, vk, node_vk_commitment, &leaf_params);
+            aggregations = next_aggregations;
+
+            proofs = vec![];
+            for (index, circuit) in node_circuits.into_iter().enumerate() {
+                let step = ReplayStep::Node {
+                    circuit_type: leaf_circuit_type as u8,
+                    depth,
+                    index,
+                };
+                let proof = match source.get_node_layer_proof(leaf_circuit_type as u8, depth, index)
+                {
+                    Ok(proof) => {
+                        on_event(ReplayEvent::Reusing(step));
+                        check_stored_proof(
+                            source,
+                            proof,
+                            ZkSyncRecursionLayerStorageType::NodeLayerCircuit,
+                            compute_recursive_circuit_input_commitment(&circuit),
+                            step,
+                        )?
+                    }
+                    Err(err) if err.is_not_found() => {
+                        on_event(ReplayEvent::Proving(step));
+                        let proof = prove(circuit, &node_vk, &mut setup, &worker, step)?;
+                        source.set_node_layer_proof(
+                            leaf_circuit_type as u8,
+                            depth,
+                            index,
+                            proof.clone(),
+                        )?;
+                        proof
+                    }
+                    Err(err) => return Err(err.into()),
+                };
+                proofs.push(proof);
+            }
+
+            if aggregations.len() == 1 {
+                break;
+            }
+            vk = node_vk.clone();
+            depth += 1;
+        }
+
+        node_proofs.push((base_circuit_type, proofs.pop().unwrap()));
+    }
+
+    let tip_proof = match source.get_recursive_tip_proof() {
+        Ok(proof) => {
+            on_event(ReplayEvent::Reusing(ReplayStep::RecursionTip));
+            check_stored_proof(
+                source,
+                proof,
+                ZkSyncRecursionLayerStorageType::RecursionTipCircuit,
+                None,
+                ReplayStep::RecursionTip,
+            )?
+        }
+        Err(err) if err.is_not_found() => {
+            on_event(ReplayEvent::Proving(ReplayStep::RecursionTip));
+            let circuit =
+                recursion_tip_circuit(source, &recursion_queues, node_proofs, node_vk_commitment)?;
+            let vk = source.get_recursion_tip_vk()?;
+            let proof = prove(circuit, &vk, &mut None, &worker, ReplayStep::RecursionTip)?;
+            source.set_recursive_tip_proof(proof.clone())?;
+            proof
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let scheduler_proof = match source.get_scheduler_proof() {
+        Ok(proof) => {
+            on_event(ReplayEvent::Reusing(ReplayStep::Scheduler));
+            check_stored_proof(
+                source,
+                proof,
+                ZkSyncRecursionLayerStorageType::SchedulerCircuit,
+                None,
+                ReplayStep::Scheduler,
+            )?
+        }
+        Err(err) if err.is_not_found() => {
+            on_event(ReplayEvent::Proving(ReplayStep::Scheduler));
+            let mut circuit = get_scheduler_circuit(source)?;
+            let ZkSyncRecursiveLayerCircuit::SchedulerCircuit(inner) = &mut circuit else {
+                unreachable!("must be a scheduler circuit");
+            };
+            inner.witness = input.scheduler_partial_input.clone();
+            inner.witness.proof_witnesses = vec![tip_proof.into_inner()].into();
+
+            let vk = source.get_recursion_layer_vk(circuit.numeric_circuit_type())?;
+            let proof = prove(circuit, &vk, &mut None, &worker, ReplayStep::Scheduler)?;
+            source.set_scheduler_proof(proof.clone())?;
+            proof
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    if let Some(config) = wrapper_config {
+        on_event(ReplayEvent::Wrapping);
+        // these steps skip the proofs that are already in the source on their own
+        compute_compression_circuits(source, config, &worker)?;
+        compute_compression_for_wrapper_circuit(source, config, &worker)?;
+        compute_wrapper_proof_and_vk(source, config, &BellmanWorker::new())?;
+    }
+
+    Ok(scheduler_proof)
+}
+
+/// Fails if the stored proofs were produced from another input, creates the checkpoint if
+/// there's none yet. Sources without a checkpoint, e.g. filled by the complex tests, are
+/// adopted by the input, their proofs are still checked.
+fn check_checkpoint(checkpoint: &Path, input_hash: String) -> Result<(), ReplayError> {
+    match fs::read_to_string(checkpoint) {
+        Ok(stored_hash) if stored_hash.trim() != input_hash => Err(ReplayError::InputMismatch {
+            stored_hash: stored_hash.trim().to_owned(),
+            input_hash,
+        }),
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            fs::write(checkpoint, input_hash).map_err(DataSourceError::from)?;
+            Ok(())
+        }
+        Err(err) => Err(DataSourceError::from(err).into()),
+    }
+}
+
+/// Checks that a proof left by an earlier replay is a valid proof of the expected circuit and,
+/// for the leaf and node circuits, of the input of the circuit being replayed.
+fn check_stored_proof<DS: SetupDataSource>(
+    source: &DS,
+    proof: ZkSyncRecursionLayerProof,
+    circuit_type: ZkSyncRecursionLayerStorageType,
+    expected_input: Option<[GoldilocksField; INPUT_OUTPUT_COMMITMENT_LENGTH]>,
+    step: ReplayStep,
+) -> Result<ZkSyncRecursionLayerProof, ReplayError> {
+    let result =
+        verify_any_proof(&ProofEnvelope::RecursionLayer(proof.clone()), source).map_err(|err| {
+            match err {
+                ProofVerificationError::DataSource(err) => ReplayError::DataSource(err),
+                ProofVerificationError::UnknownCircuitType { .. } => {
+                    ReplayError::InvalidStoredProof(step)
+                }
+            }
+        })?;
+    if result.circuit_type != circuit_type as u8 || !result.is_valid {
+        return Err(ReplayError::InvalidStoredProof(step));
+    }
+    if let Some(expected_input) = expected_input {
+        let public_inputs = &proof.clone().into_inner().public_inputs;
+        if public_inputs.as_slice() != expected_input.as_slice() {
+            return Err(ReplayError::StoredProofInputMismatch(step));
+        }
+    }
+
+    Ok(proof)
+}
+
+fn recursion_tip_circuit<DS: SetupDataSource>(
+    source: &mut DS,
+    recursion_queues: &[RecursionQueue],
+    node_proofs: Vec<(u8, ZkSyncRecursionLayerProof)>,
+    node_vk_commitment: [GoldilocksField; 4],
+) -> Result<ZkSyncRecursiveLayerCircuit, ReplayError> {
+    let mut circuit = get_recursion_tip_circuit(source)?;
+    let ZkSyncRecursiveLayerCircuit::RecursionTipCircuit(inner) = &mut circuit else {
+        unreachable!("must be a recursion tip circuit");
+    };
+
+    let mut branch_circuit_type_set = [GoldilocksField::ZERO; RECURSION_TIP_ARITY];
+    let mut queue_set: [_; RECURSION_TIP_ARITY] =
+        std::array::from_fn(|_| QueueState::placeholder_witness());
+    for ((circuit_type, queue_state), (src_type, src_queue, _)) in branch_circuit_type_set
+        .iter_mut()
+        .zip(queue_set.iter_mut())
+        .zip(recursion_queues.iter())
+    {
+        *circuit_type = GoldilocksField::from_u64_unchecked(*src_type);
+        *queue_state = take_sponge_like_queue_state_from_simulator(src_queue);
+    }
+
+    let leaf_layer_parameters = compute_leaf_params(source)?
+        .into_iter()
+        .map(|el| el.1)
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+    inner.witness.input = RecursionTipInputWitness {
+        leaf_layer_parameters,
+        node_layer_vk_commitment: node_vk_commitment,
+        branch_circuit_type_set,
+        queue_set,
+    };
+
+    // circuit types without circuits are padded with empty proofs
+    let proof_witnesses: Vec<_> = BaseLayerCircuitType::as_iter_u8()
+        .map(|circuit_type| {
+            node_proofs
+                .iter()
+                .find(|el| el.0 == circuit_type)
+                .map(|el| el.1.clone())
+                .unwrap_or_else(empty_node_proof)
+                .into_inner()
+        })
+        .collect();
+    assert_eq!(proof_witnesses.len(), NUM_CIRCUIT_TYPES_TO_SCHEDULE);
+    inner.witness.proof_witnesses = proof_witnesses.into();
+
+    Ok(circuit)
+}
+
+type RecursionLayerSetup = (
+    SetupBaseStorage<GoldilocksField, GoldilocksField>,
+    SetupStorage<GoldilocksField, GoldilocksField>,
+    VerificationKey<GoldilocksField, RecursiveProofsTreeHasher>,
+    MerkleTreeWithCap<GoldilocksField, RecursiveProofsTreeHasher>,
+    DenseVariablesCopyHint,
+    DenseWitnessCopyHint,
+    FinalizationHintsForProver,
+);
+
+/// Proves the circuit with the setup for its type, which is created once and reused for the
+/// following circuits of the same type.
+fn prove(
+    circuit: ZkSyncRecursiveLayerCircuit,
+    vk: &ZkSyncRecursionLayerVerificationKey,
+    setup: &mut Option<RecursionLayerSetup>,
+    worker: &Worker,
+    step: ReplayStep,
+) -> Result<ZkSyncRecursionLayerProof, ReplayError> {
+    let (setup_base, setup, _, setup_tree, vars_hint, wits_hint, finalization_hint) = setup
+        .get_or_insert_with(|| {
+            create_recursive_layer_setup_data(
+                circuit.clone(),
+                worker,
+                RECURSION_LAYER_FRI_LDE_FACTOR,
+                RECURSION_LAYER_CAP_SIZE,
+            )
+        });
+
+    let vk = vk.clone().into_inner();
+    let proof = prove_recursion_layer_circuit::<NoPow>(
+        circuit.clone(),
+        worker,
+        recursion_layer_proof_config(),
+        setup_base,
+        setup,
+        setup_tree,
+        &vk,
+        vars_hint,
+        wits_hint,
+        finalization_hint,
+    );
+
+    if !verify_recursion_layer_proof::<NoPow>(&circuit, &proof, &vk) {
+        return Err(ReplayError::InvalidProof(step));
+    }
+
+    Ok(ZkSyncRecursionLayerProof::from_inner(
+        circuit.numeric_circuit_type(),
+        proof,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::in_memory_data_source::InMemoryDataSource;
+    use crate::data_source::local_file_data_source::LocalFileDataSource;
+    use crate::data_source::ArtifactKind;
+
+    /// Source with the setup of the repository and an empty block folder.
+    fn setup_source(block_dir: &tempfile::TempDir) -> LocalFileDataSource {
+        let source = LocalFileDataSource {
+            setup_data_location: "setup".to_string(),
+            block_data_location: block_dir.path().to_string_lossy().into_owned(),
+        };
+        source.create_folders_for_storing_data().unwrap();
+        source
+    }
+
+    #[test]
+    fn test_replay_input_roundtrip() {
+        let mut input = ReplayInput::new(SchedulerCircuitInstanceWitness::placeholder());
+        input.add_recursion_queue((1, RecursionQueueSimulator::empty(), vec![]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(REPLAY_INPUT_FILE);
+        input.write_to_file(&path).unwrap();
+        let restored = ReplayInput::read_from_file(&path).unwrap();
+
+        assert_eq!(restored.recursion_queues.len(), 1);
+        assert_eq!(restored.recursion_queues[0].0, 1);
+        assert_eq!(restored.recursion_queues[0].1.num_items, 0);
+    }
+
+    #[test]
+    fn test_replay_requires_setup_data() {
+        let mut source = InMemoryDataSource::new();
+        let input = ReplayInput::new(SchedulerCircuitInstanceWitness::placeholder());
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = dir.path().join(REPLAY_CHECKPOINT_FILE);
+
+        let result = replay_proof_chain(&mut source, &input, &checkpoint, None, |_| {});
+        assert!(matches!(
+            result,
+            Err(ReplayError::DataSource(DataSourceError::NotFound {
+                kind: ArtifactKind::BaseLayerVk,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_replay_rejects_proofs_of_another_input() {
+        let block_dir = tempfile::tempdir().unwrap();
+        let mut source = setup_source(&block_dir);
+        let input = ReplayInput::new(SchedulerCircuitInstanceWitness::placeholder());
+        let checkpoint = block_dir.path().join(REPLAY_CHECKPOINT_FILE);
+        std::fs::write(&checkpoint, "00".repeat(32)).unwrap();
+
+        let result = replay_proof_chain(&mut source, &input, &checkpoint, None, |_| {});
+        assert!(matches!(
+            result,
+            Err(ReplayError::InputMismatch { input_hash, .. }) if input_hash == input.hash().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_replay_checks_stored_proofs() {
+        let block_dir = tempfile::tempdir().unwrap();
+        let mut source = setup_source(&block_dir);
+        let input = ReplayInput::new(SchedulerCircuitInstanceWitness::placeholder());
+
+        // a valid proof, but of the scheduler
+        let testdata = LocalFileDataSource {
+            setup_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+            block_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+        };
+        source
+            .set_recursive_tip_proof(testdata.get_scheduler_proof().unwrap())
+            .unwrap();
+
+        let checkpoint = block_dir.path().join(REPLAY_CHECKPOINT_FILE);
+        let mut events = vec![];
+        let result = replay_proof_chain(&mut source, &input, &checkpoint, None, |event| {
+            events.push(event)
+        });
+        assert!(matches!(
+            result,
+            Err(ReplayError::InvalidStoredProof(ReplayStep::RecursionTip))
+        ));
+        assert_eq!(events, [ReplayEvent::Reusing(ReplayStep::RecursionTip)]);
+        // the first replay adopts the source
+        assert_eq!(
+            std::fs::read_to_string(&checkpoint).unwrap(),
+            input.hash().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_stored_proof_must_prove_the_replayed_input() {
+        let testdata = LocalFileDataSource {
+            setup_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+            block_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+        };
+        let proof = testdata.get_scheduler_proof().unwrap();
+        let public_inputs: [GoldilocksField; INPUT_OUTPUT_COMMITMENT_LENGTH] =
+            proof.clone().into_inner().public_inputs.try_into().unwrap();
+        let circuit_type = ZkSyncRecursionLayerStorageType::SchedulerCircuit;
+        let step = ReplayStep::Scheduler;
+
+        assert!(check_stored_proof(
+            &testdata,
+            proof.clone(),
+            circuit_type,
+            Some(public_inputs),
+            step
+        )
+        .is_ok());
+
+        let mut other_inputs = public_inputs;
+        other_inputs[0] = GoldilocksField::from_u64_unchecked(other_inputs[0].as_u64() ^ 1);
+        assert!(matches!(
+            check_stored_proof(&testdata, proof, circuit_type, Some(other_inputs), step),
+            Err(ReplayError::StoredProofInputMismatch(ReplayStep::Scheduler))
+        ));
+    }
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use zkevm_test_harness::data_source::local_file_data_source::LocalFileDataSource;
+use zkevm_test_harness::proof_chain_replay::{
+    replay_proof_chain, ReplayInput, REPLAY_CHECKPOINT_FILE, REPLAY_INPUT_FILE,
+};
+use zkevm_test_harness::proof_wrapper_utils::WrapperConfig;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "proof_chain_replayer")]
+struct Arguments {
+    /// Setup folder with the verification keys and finalization hints
+    #[structopt(long = "setup-path", parse(from_os_str), default_value = "setup")]
+    setup_path: PathBuf,
+
+    /// Folder with the base layer proofs, where the produced proofs are stored
+    #[structopt(long = "block-path", parse(from_os_str), default_value = "test_proofs")]
+    block_path: PathBuf,
+
+    /// Recursion queues and the scheduler input. Defaults to `replay_input.json` in the block folder
+    #[structopt(long = "input", parse(from_os_str))]
+    input: Option<PathBuf>,
+
+    /// Also compress and wrap the scheduler proof, with the given number of compression layers
+    #[structopt(long = "compression-layers")]
+    compression_layers: Option<u8>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let opt = Arguments::from_args();
+
+    let wrapper_config = match opt.compression_layers {
+        Some(layers) if layers == 0 || layers > WrapperConfig::MAX_COMPRESSION_LAYERS => {
+            return Err(format!(
+                "the number of compression layers must be between 1 and {}",
+                WrapperConfig::MAX_COMPRESSION_LAYERS
+            )
+            .into());
+        }
+        layers => layers.map(WrapperConfig::new),
+    };
+
+    let input_path = opt
+        .input
+        .unwrap_or_else(|| opt.block_path.join(REPLAY_INPUT_FILE));
+    let input = ReplayInput::read_from_file(input_path)?;
+
+    let mut source = LocalFileDataSource {
+        setup_data_location: opt.setup_path.to_string_lossy().into_owned(),
+        block_data_location: opt.block_path.to_string_lossy().into_owned(),
+    };
+    source.create_folders_for_storing_data()?;
+
+    replay_proof_chain(
+        &mut source,
+        &input,
+        &opt.block_path.join(REPLAY_CHECKPOINT_FILE),
+        wrapper_config,
+        |event| println!("Replay: {}", event),
+    )?;
+    println!("Replay finished");
+
+    Ok(())
+}
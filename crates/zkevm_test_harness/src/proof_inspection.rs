@@ -0,0 +1,367 @@
+//!
+//! Loading of proof and circuit artifacts of unknown type, decoding of their public inputs
+//! and verification against the keys from a data source.
+//!
+use crate::data_source::SetupDataSource;
+use crate::debug::CircuitWrapper;
+use crate::prover_utils::{
+    verify_any_proof, ProofEnvelope, ProofVerificationError, ProofVerificationResult, PublicInputs,
+};
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArtifactType {
+    BaseLayerProof,
+    RecursionLayerProof,
+    CompressionLayerProof,
+    CompressionForWrapperProof,
+    SnarkWrapperProof,
+    Circuit,
+}
+
+impl ArtifactType {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ArtifactType::BaseLayerProof => "base",
+            ArtifactType::RecursionLayerProof => "recursion",
+            ArtifactType::CompressionLayerProof => "compression",
+            ArtifactType::CompressionForWrapperProof => "compression_for_wrapper",
+            ArtifactType::SnarkWrapperProof => "snark_wrapper",
+            ArtifactType::Circuit => "circuit",
+        }
+    }
+}
+
+impl FromStr for ArtifactType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            ArtifactType::BaseLayerProof,
+            ArtifactType::RecursionLayerProof,
+            ArtifactType::CompressionLayerProof,
+            ArtifactType::CompressionForWrapperProof,
+            ArtifactType::SnarkWrapperProof,
+            ArtifactType::Circuit,
+        ]
+        .into_iter()
+        .find(|el| el.name() == s)
+        .ok_or_else(|| format!("unknown artifact type: {}", s))
+    }
+}
+
+#[derive(Debug)]
+pub enum InspectionError {
+    /// The artifact can't be decoded as any known artifact type.
+    UnrecognizedArtifact,
+    /// The artifact can't be decoded as the requested artifact type.
+    Decoding(Box<dyn Error + Send + Sync>),
+    /// Circuits have nothing to verify.
+    NotAProof,
+    Verification(ProofVerificationError),
+}
+
+impl fmt::Display for InspectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InspectionError::UnrecognizedArtifact => write!(
+                f,
+                "can't recognize the artifact, the artifact type must be given explicitly"
+            ),
+            InspectionError::Decoding(err) => write!(f, "can't decode the artifact: {}", err),
+            InspectionError::NotAProof => write!(f, "the artifact is not a proof"),
+            InspectionError::Verification(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for InspectionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InspectionError::Decoding(err) => Some(err.as_ref()),
+            InspectionError::Verification(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ProofVerificationError> for InspectionError {
+    fn from(err: ProofVerificationError) -> Self {
+        InspectionError::Verification(err)
+    }
+}
+
+pub enum InspectedArtifact {
+    Proof(Box<ProofEnvelope>),
+    Circuit(Box<CircuitWrapper>),
+}
+
+impl InspectedArtifact {
+    ///
+    /// Decodes a JSON or bincode artifact. Without the explicit type, JSON artifacts are
+    /// recognized by their contents, while bincode ones are expected to be circuits as
+    /// passed to [`crate::debug::debug_circuit`].
+    ///
+    pub fn from_bytes(
+        bytes: &[u8],
+        artifact_type: Option<ArtifactType>,
+    ) -> Result<Self, InspectionError> {
+        let is_json = bytes
+            .iter()
+            .find(|el| !el.is_ascii_whitespace())
+            .is_some_and(|el| *el == b'{');
+
+        if let Some(artifact_type) = artifact_type {
+            let input = if is_json {
+                EncodedArtifact::Json(bytes)
+            } else {
+                EncodedArtifact::Bincode(bytes)
+            };
+            return Self::decode(artifact_type, input).map_err(InspectionError::Decoding);
+        }
+
+        if !is_json {
+            return Self::decode(ArtifactType::Circuit, EncodedArtifact::Bincode(bytes))
+                .map_err(|_| InspectionError::UnrecognizedArtifact);
+        }
+
+        let value: serde_json::Value =
+            serde_json::from_slice(bytes).map_err(|err| InspectionError::Decoding(err.into()))?;
+        Self::candidate_types(&value)
+            .into_iter()
+            .find_map(|el| Self::decode(el, EncodedArtifact::JsonValue(&value)).ok())
+            .ok_or(InspectionError::UnrecognizedArtifact)
+    }
+
+    /// Artifacts are serialized as enums, so the variant name tells the layer. The compression
+    /// layers, their wrapper-friendly version and the SNARK wrapper share the variant names.
+    fn candidate_types(value: &serde_json::Value) -> Vec<ArtifactType> {
+        let variant = match value.as_object() {
+            Some(object) if object.len() == 1 => object.keys().next().unwrap().as_str(),
+            _ => return vec![],
+        };
+
+        match variant {
+            "Base" | "Recursive" => vec![ArtifactType::Circuit],
+            "SchedulerCircuit" | "NodeLayerCircuit" | "RecursionTipCircuit" => {
+                vec![ArtifactType::RecursionLayerProof]
+            }
+            a if a.starts_with("LeafLayerCircuitFor") => vec![ArtifactType::RecursionLayerProof],
+            a if a.starts_with("CompressionMode") => vec![
+                ArtifactType::SnarkWrapperProof,
+                ArtifactType::CompressionForWrapperProof,
+                ArtifactType::CompressionLayerProof,
+            ],
+            _ => vec![ArtifactType::BaseLayerProof],
+        }
+    }
+
+    fn decode(
+        artifact_type: ArtifactType,
+        input: EncodedArtifact,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let proof = match artifact_type {
+            ArtifactType::BaseLayerProof => ProofEnvelope::BaseLayer(input.decode()?),
+            ArtifactType::RecursionLayerProof => ProofEnvelope::RecursionLayer(input.decode()?),
+            ArtifactType::CompressionLayerProof => ProofEnvelope::CompressionLayer(input.decode()?),
+            ArtifactType::CompressionForWrapperProof => {
+                ProofEnvelope::CompressionForWrapper(input.decode()?)
+            }
+            ArtifactType::SnarkWrapperProof => {
+                ProofEnvelope::SnarkWrapper(Box::new(input.decode()?))
+            }
+            ArtifactType::Circuit => {
+                return Ok(InspectedArtifact::Circuit(Box::new(input.decode()?)))
+            }
+        };
+
+        Ok(InspectedArtifact::Proof(Box::new(proof)))
+    }
+
+    pub fn artifact_type(&self) -> ArtifactType {
+        match self {
+            InspectedArtifact::Proof(proof) => match proof.as_ref() {
+                ProofEnvelope::BaseLayer(_) => ArtifactType::BaseLayerProof,
+                ProofEnvelope::RecursionLayer(_) => ArtifactType::RecursionLayerProof,
+                ProofEnvelope::CompressionLayer(_) => ArtifactType::CompressionLayerProof,
+                ProofEnvelope::CompressionForWrapper(_) => ArtifactType::CompressionForWrapperProof,
+                ProofEnvelope::SnarkWrapper(_) => ArtifactType::SnarkWrapperProof,
+            },
+            InspectedArtifact::Circuit(_) => ArtifactType::Circuit,
+        }
+    }
+
+    pub fn numeric_circuit_type(&self) -> u8 {
+        match self {
+            InspectedArtifact::Proof(proof) => proof.numeric_circuit_type(),
+            InspectedArtifact::Circuit(circuit) => match circuit.as_ref() {
+                CircuitWrapper::Base(circuit) => circuit.numeric_circuit_type(),
+                CircuitWrapper::Recursive(circuit) => circuit.numeric_circuit_type(),
+            },
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            InspectedArtifact::Proof(proof) => {
+                let layer = match proof.as_ref() {
+                    ProofEnvelope::BaseLayer(_) => "base layer",
+                    ProofEnvelope::RecursionLayer(_) => "recursion layer",
+                    ProofEnvelope::CompressionLayer(_) => "compression layer",
+                    ProofEnvelope::CompressionForWrapper(_) => "compression for wrapper",
+                    ProofEnvelope::SnarkWrapper(_) => "SNARK wrapper",
+                };
+                format!("{} proof: {}", layer, proof.short_description())
+            }
+            InspectedArtifact::Circuit(circuit) => match circuit.as_ref() {
+                CircuitWrapper::Base(circuit) => {
+                    format!("base layer circuit: {}", circuit.short_description())
+                }
+                CircuitWrapper::Recursive(circuit) => {
+                    format!("recursion layer circuit: {}", circuit.short_description())
+                }
+            },
+        }
+    }
+
+    /// Circuits have no public inputs until they are proven.
+    pub fn public_inputs(&self) -> Option<PublicInputs> {
+        match self {
+            InspectedArtifact::Proof(proof) => Some(proof.public_inputs()),
+            InspectedArtifact::Circuit(_) => None,
+        }
+    }
+
+    /// Verifies the proof against the verification key of its circuit type from `source`.
+    pub fn verify<DS: SetupDataSource>(
+        &self,
+        source: &DS,
+    ) -> Result<ProofVerificationResult, InspectionError> {
+        match self {
+            InspectedArtifact::Proof(proof) => Ok(verify_any_proof(proof, source)?),
+            InspectedArtifact::Circuit(_) => Err(InspectionError::NotAProof),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum EncodedArtifact<'a> {
+    Json(&'a [u8]),
+    JsonValue(&'a serde_json::Value),
+    Bincode(&'a [u8]),
+}
+
+impl EncodedArtifact<'_> {
+    fn decode<T: DeserializeOwned>(self) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let result = match self {
+            EncodedArtifact::Json(bytes) => serde_json::from_slice(bytes)?,
+            EncodedArtifact::JsonValue(value) => T::deserialize(value)?,
+            EncodedArtifact::Bincode(bytes) => bincode::deserialize(bytes)?,
+        };
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boojum::field::U64Representable;
+    use crate::data_source::local_file_data_source::LocalFileDataSource;
+
+    const TESTDATA: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/proof_wrapper_utils/testdata/proof_compression"
+    );
+
+    fn testdata_source() -> LocalFileDataSource {
+        LocalFileDataSource {
+            setup_data_location: TESTDATA.to_string(),
+            block_data_location: TESTDATA.to_string(),
+        }
+    }
+
+    fn read_testdata(name: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/{}", TESTDATA, name)).unwrap()
+    }
+
+    #[test]
+    fn test_testdata_proofs_are_recognized_and_verified() {
+        let source = testdata_source();
+        for (name, artifact_type, circuit_type) in [
+            (
+                "recursion_layer/scheduler_proof.json",
+                ArtifactType::RecursionLayerProof,
+                1,
+            ),
+            (
+                "aux_layer/compression_proof_1.json",
+                ArtifactType::CompressionLayerProof,
+                1,
+            ),
+            (
+                "aux_layer/compression_proof_4.json",
+                ArtifactType::CompressionLayerProof,
+                4,
+            ),
+            (
+                "aux_layer/compression_for_wrapper_proof_2.json",
+                ArtifactType::CompressionForWrapperProof,
+                2,
+            ),
+        ] {
+            let artifact = InspectedArtifact::from_bytes(&read_testdata(name), None).unwrap();
+            assert_eq!(artifact.artifact_type(), artifact_type, "{}", name);
+            assert_eq!(artifact.numeric_circuit_type(), circuit_type, "{}", name);
+
+            // All of them pass through the public inputs of the same scheduler proof.
+            match artifact.public_inputs() {
+                Some(PublicInputs::BlockHash { public_inputs, .. }) => assert_eq!(
+                    public_inputs.map(|el| el.as_u64_reduced()),
+                    [
+                        1961802424684827,
+                        44478075017541700,
+                        68433799240021256,
+                        10969447465159162
+                    ],
+                    "{}",
+                    name
+                ),
+                _ => panic!("{} has unexpected public inputs", name),
+            }
+
+            assert!(artifact.verify(&source).unwrap().is_valid, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_tampered_proof_is_not_verified() {
+        let mut proof: serde_json::Value =
+            serde_json::from_slice(&read_testdata("aux_layer/compression_proof_1.json")).unwrap();
+        let public_input = &mut proof["CompressionMode1Circuit"]["public_inputs"][0];
+        *public_input = (public_input.as_u64().unwrap() + 1).into();
+
+        let artifact =
+            InspectedArtifact::from_bytes(&serde_json::to_vec(&proof).unwrap(), None).unwrap();
+        assert_eq!(
+            artifact.artifact_type(),
+            ArtifactType::CompressionLayerProof
+        );
+        assert!(!artifact.verify(&testdata_source()).unwrap().is_valid);
+    }
+
+    #[test]
+    fn test_unrecognized_artifact() {
+        let result = InspectedArtifact::from_bytes(br#"{"MainVM": {}}"#, None);
+        assert!(matches!(result, Err(InspectionError::UnrecognizedArtifact)));
+
+        let result = InspectedArtifact::from_bytes(
+            br#"{"MainVM": {}}"#,
+            Some(ArtifactType::RecursionLayerProof),
+        );
+        assert!(matches!(result, Err(InspectionError::Decoding(_))));
+    }
+}
@@ -0,0 +1,82 @@
+use std::error::Error;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use zkevm_test_harness::data_source::content_addressed_data_source::ContentAddressedDataSource;
+use zkevm_test_harness::data_source::local_file_data_source::LocalFileDataSource;
+use zkevm_test_harness::proof_inspection::{ArtifactType, InspectedArtifact};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "proof_inspector")]
+struct Arguments {
+    /// Proof or circuit artifact, in JSON or bincode
+    #[structopt(parse(from_os_str))]
+    path: PathBuf,
+
+    /// Type of the artifact: base, recursion, compression, compression_for_wrapper,
+    /// snark_wrapper or circuit. Recognized automatically if not set
+    #[structopt(long = "type")]
+    artifact_type: Option<ArtifactType>,
+
+    /// Verify against the keys in the given setup folder (as used by `LocalFileDataSource`)
+    #[structopt(long = "setup-path", parse(from_os_str))]
+    setup_path: Option<PathBuf>,
+
+    /// Verify against the keys in the given content-addressed store
+    #[structopt(long = "store-path", parse(from_os_str), requires = "protocol-version")]
+    store_path: Option<PathBuf>,
+
+    /// Protocol version of the keys in the content-addressed store
+    #[structopt(long = "protocol-version")]
+    protocol_version: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let opt = Arguments::from_args();
+
+    let bytes = std::fs::read(&opt.path)?;
+    let artifact = InspectedArtifact::from_bytes(&bytes, opt.artifact_type)?;
+
+    println!("Artifact: {}", artifact.description());
+    println!("Circuit type: {}", artifact.numeric_circuit_type());
+    if let Some(public_inputs) = artifact.public_inputs() {
+        println!("{}", public_inputs);
+    }
+
+    if artifact.artifact_type() == ArtifactType::Circuit {
+        return Ok(());
+    }
+
+    let result = match (opt.setup_path, opt.store_path) {
+        (Some(_), Some(_)) => {
+            return Err("only one of --setup-path and --store-path can be used".into());
+        }
+        (Some(setup_path), None) => {
+            let source = LocalFileDataSource {
+                setup_data_location: setup_path.to_string_lossy().into_owned(),
+                ..Default::default()
+            };
+            artifact.verify(&source)?
+        }
+        (None, Some(store_path)) => {
+            let source = ContentAddressedDataSource::open_read_only(
+                store_path,
+                opt.protocol_version.as_deref().unwrap(),
+            )?;
+            artifact.verify(&source)?
+        }
+        (None, None) => {
+            println!("Verification: skipped, no keys given");
+            return Ok(());
+        }
+    };
+
+    if result.is_valid {
+        println!("Verification: valid");
+        Ok(())
+    } else {
+        println!("Verification: INVALID");
+        std::process::exit(1);
+    }
+}
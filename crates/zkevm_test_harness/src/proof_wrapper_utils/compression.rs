@@ -41,20 +41,14 @@ pub(crate) fn compute_compression_circuits<DS: SetupDataSource + BlockDataSource
     source: &mut DS,
     config: WrapperConfig,
     worker: &Worker,
-) {
+) -> SourceResult<()> {
     for circuit_type in config.get_compression_types() {
         if source.get_compression_proof(circuit_type).is_err()
             || source.get_compression_vk(circuit_type).is_err()
             || source.get_compression_hint(circuit_type).is_err()
         {
-            let proof = get_proof_for_previous_circuit(source, circuit_type).expect(&format!(
-                "Proof of previous circuit should be present. Current circuit type: {}",
-                circuit_type
-            ));
-            let vk = get_vk_for_previous_circuit(source, circuit_type).expect(&format!(
-                "VK of previous circuit should be present. Current circuit type: {}",
-                circuit_type
-            ));
+            let proof = get_proof_for_previous_circuit(source, circuit_type)?;
+            let vk = get_vk_for_previous_circuit(source, circuit_type)?;
 
             let compression_circuit =
                 ZkSyncCompressionLayerCircuit::from_witness_and_vk(Some(proof), vk, circuit_type);
@@ -62,26 +56,22 @@ pub(crate) fn compute_compression_circuits<DS: SetupDataSource + BlockDataSource
             let (vk, finalization_hint, proof) =
                 compute_compression_circuit_inner(compression_circuit, &worker);
 
-            source
-                .set_compression_vk(ZkSyncCompressionLayerStorage::from_inner(
-                    circuit_type,
-                    vk.clone(),
-                ))
-                .unwrap();
-            source
-                .set_compression_hint(ZkSyncCompressionLayerStorage::from_inner(
-                    circuit_type,
-                    finalization_hint.clone(),
-                ))
-                .unwrap();
-            source
-                .set_compression_proof(ZkSyncCompressionLayerStorage::from_inner(
-                    circuit_type,
-                    proof,
-                ))
-                .unwrap();
+            source.set_compression_vk(ZkSyncCompressionLayerStorage::from_inner(
+                circuit_type,
+                vk.clone(),
+            ))?;
+            source.set_compression_hint(ZkSyncCompressionLayerStorage::from_inner(
+                circuit_type,
+                finalization_hint.clone(),
+            ))?;
+            source.set_compression_proof(ZkSyncCompressionLayerStorage::from_inner(
+                circuit_type,
+                proof,
+            ))?;
         }
     }
+
+    Ok(())
 }
 
 fn compute_compression_circuit_inner(
@@ -43,7 +43,7 @@ pub(crate) fn compute_compression_for_wrapper_circuit<DS: SetupDataSource + Bloc
     source: &mut DS,
     config: WrapperConfig,
     worker: &Worker,
-) {
+) -> SourceResult<()> {
     let circuit_type = config.get_compression_for_wrapper_type();
 
     if source.get_compression_for_wrapper_vk(circuit_type).is_err()
@@ -54,14 +54,8 @@ pub(crate) fn compute_compression_for_wrapper_circuit<DS: SetupDataSource + Bloc
             .get_compression_for_wrapper_proof(circuit_type)
             .is_err()
     {
-        let proof = get_proof_for_previous_circuit(source, circuit_type).expect(&format!(
-            "Proof of previous circuit should be present. Current circuit type: {}",
-            circuit_type
-        ));
-        let vk = get_vk_for_previous_circuit(source, circuit_type).expect(&format!(
-            "VK of previous circuit should be present. Current circuit type: {}",
-            circuit_type
-        ));
+        let proof = get_proof_for_previous_circuit(source, circuit_type)?;
+        let vk = get_vk_for_previous_circuit(source, circuit_type)?;
 
         let compression_circuit =
             ZkSyncCompressionForWrapperCircuit::from_witness_and_vk(Some(proof), vk, circuit_type);
@@ -70,25 +64,21 @@ pub(crate) fn compute_compression_for_wrapper_circuit<DS: SetupDataSource + Bloc
             compute_compression_for_wrapper_circuit_inner(compression_circuit, worker);
 
         // we did it above
-        source
-            .set_compression_for_wrapper_vk(ZkSyncCompressionLayerStorage::from_inner(
-                circuit_type,
-                vk.clone(),
-            ))
-            .unwrap();
-        source
-            .set_compression_for_wrapper_hint(ZkSyncCompressionLayerStorage::from_inner(
-                circuit_type,
-                finalization_hint.clone(),
-            ))
-            .unwrap();
-        source
-            .set_compression_for_wrapper_proof(ZkSyncCompressionLayerStorage::from_inner(
-                circuit_type,
-                proof,
-            ))
-            .unwrap();
+        source.set_compression_for_wrapper_vk(ZkSyncCompressionLayerStorage::from_inner(
+            circuit_type,
+            vk.clone(),
+        ))?;
+        source.set_compression_for_wrapper_hint(ZkSyncCompressionLayerStorage::from_inner(
+            circuit_type,
+            finalization_hint.clone(),
+        ))?;
+        source.set_compression_for_wrapper_proof(ZkSyncCompressionLayerStorage::from_inner(
+            circuit_type,
+            proof,
+        ))?;
     }
+
+    Ok(())
 }
 
 fn compute_compression_for_wrapper_circuit_inner(
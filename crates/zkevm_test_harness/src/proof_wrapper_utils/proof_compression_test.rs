@@ -144,7 +144,7 @@ mod test {
             setup_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
             block_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
         };
-        source.create_folders_for_storing_data();
+        source.create_folders_for_storing_data().unwrap();
         let proof = source.get_scheduler_proof().unwrap();
         let vk = source
             .get_recursion_layer_vk(ZkSyncRecursionLayerStorageType::SchedulerCircuit as u8)
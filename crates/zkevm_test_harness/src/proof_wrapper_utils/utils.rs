@@ -10,6 +10,22 @@ pub fn check_trusted_setup_file_existace() {
         .expect(format!("Trying to open CRS FILE: {:?}", crs_file_path).as_str());
 }
 
+/// Opens the trusted setup file, for the callers that can't panic if it's missing
+pub fn open_trusted_setup_file() -> std::io::Result<std::fs::File> {
+    let crs_file_str = std::env::var(CRS_FILE_ENV_VAR).map_err(|err| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} env variable: {}", CRS_FILE_ENV_VAR, err),
+        )
+    })?;
+    std::fs::File::open(&crs_file_str).map_err(|err| {
+        std::io::Error::new(
+            err.kind(),
+            format!("trying to open CRS FILE {:?}: {}", crs_file_str, err),
+        )
+    })
+}
+
 /// Uploads trusted setup file to the RAM
 pub fn get_trusted_setup() -> Crs<Bn256, CrsForMonomialForm> {
     let crs_file_str = std::env::var(CRS_FILE_ENV_VAR).expect("CRS_FILE env variable:");
@@ -59,7 +75,7 @@ pub fn get_proof_for_previous_circuit<DS: SetupDataSource + BlockDataSource>(
     }
 }
 
-pub fn get_vk_for_previous_circuit<DS: SetupDataSource + BlockDataSource>(
+pub fn get_vk_for_previous_circuit<DS: SetupDataSource>(
     source: &DS,
     circuit_type: u8,
 ) -> SourceResult<ZkSyncCompressionVerificationKey> {
@@ -28,7 +28,7 @@ pub(crate) fn compute_wrapper_proof_and_vk<DS: SetupDataSource + BlockDataSource
     source: &mut DS,
     config: WrapperConfig,
     worker: &BellmanWorker,
-) {
+) -> SourceResult<()> {
     let wrapper_type = config.get_wrapper_type();
 
     // {
@@ -56,19 +56,19 @@ pub(crate) fn compute_wrapper_proof_and_vk<DS: SetupDataSource + BlockDataSource
 
     println!("Computing Bn256 wrapper setup");
     if source.get_wrapper_setup(wrapper_type).is_err() {
-        let vk = source.get_compression_for_wrapper_vk(wrapper_type).unwrap();
+        let vk = source.get_compression_for_wrapper_vk(wrapper_type)?;
 
         let snark_setup = compute_wrapper_setup_inner(vk, config, worker);
 
         let snark_setup =
             ZkSyncCompressionLayerStorage::from_inner(wrapper_type, Arc::new(snark_setup));
-        source.set_wrapper_setup(snark_setup).unwrap();
+        source.set_wrapper_setup(snark_setup)?;
     }
 
     println!("Computing Bn256 wrapper vk");
     if source.get_wrapper_vk(wrapper_type).is_err() {
         let start = std::time::Instant::now();
-        let snark_setup = source.get_wrapper_setup(wrapper_type).unwrap();
+        let snark_setup = source.get_wrapper_setup(wrapper_type)?;
 
         let crs_mons = get_trusted_setup();
         let snark_vk = SnarkVK::from_setup(&snark_setup.into_inner(), worker, &crs_mons).unwrap();
@@ -80,22 +80,20 @@ pub(crate) fn compute_wrapper_proof_and_vk<DS: SetupDataSource + BlockDataSource
         );
 
         let snark_vk = ZkSyncCompressionLayerStorage::from_inner(wrapper_type, snark_vk);
-        source.set_wrapper_vk(snark_vk).unwrap();
+        source.set_wrapper_vk(snark_vk)?;
     }
 
     println!("Computing Bn256 wrapper proof");
     if source.get_wrapper_proof(wrapper_type).is_err() {
-        let proof = source
-            .get_compression_for_wrapper_proof(wrapper_type)
-            .unwrap();
-        let vk = source.get_compression_for_wrapper_vk(wrapper_type).unwrap();
+        let proof = source.get_compression_for_wrapper_proof(wrapper_type)?;
+        let vk = source.get_compression_for_wrapper_vk(wrapper_type)?;
 
-        let snark_setup = source.get_wrapper_setup(wrapper_type).unwrap();
+        let snark_setup = source.get_wrapper_setup(wrapper_type)?;
 
         let snark_proof = compute_wrapper_proof_inner(proof, vk, snark_setup, config, worker);
 
         println!("Verifying");
-        let snark_vk = source.get_wrapper_vk(wrapper_type).unwrap();
+        let snark_vk = source.get_wrapper_vk(wrapper_type)?;
         use crate::snark_wrapper::franklin_crypto::bellman::plonk::better_better_cs::verifier::verify;
         let is_valid =
             verify::<_, _, RollingKeccakTranscript<Fr>>(&snark_vk.into_inner(), &snark_proof, None)
@@ -103,8 +101,10 @@ pub(crate) fn compute_wrapper_proof_and_vk<DS: SetupDataSource + BlockDataSource
         assert!(is_valid);
 
         let snark_proof = ZkSyncCompressionLayerStorage::from_inner(wrapper_type, snark_proof);
-        source.set_wrapper_proof(snark_proof).unwrap();
+        source.set_wrapper_proof(snark_proof)?;
     }
+
+    Ok(())
 }
 
 pub(crate) fn compute_wrapper_setup_inner(
@@ -0,0 +1,453 @@
+use crate::boojum::cs::implementations::pow::NoPow;
+use crate::boojum::field::goldilocks::GoldilocksField;
+use crate::boojum::field::{PrimeField as BoojumPrimeField, U64Representable};
+use crate::data_source::{DataSourceError, SetupDataSource};
+use crate::franklin_crypto::bellman::pairing::bn256::Fr;
+use crate::franklin_crypto::bellman::plonk::better_better_cs::verifier::verify;
+use crate::franklin_crypto::bellman::plonk::commitments::transcript::keccak_transcript::RollingKeccakTranscript;
+use crate::franklin_crypto::bellman::{PrimeField, PrimeFieldRepr};
+use crate::proof_wrapper_utils::get_vk_for_previous_circuit;
+use circuit_definitions::circuit_definitions::aux_layer::{
+    ZkSyncCompressionForWrapperCircuit, ZkSyncCompressionForWrapperProof,
+    ZkSyncCompressionLayerCircuit, ZkSyncCompressionLayerProof, ZkSyncCompressionLayerStorageType,
+    ZkSyncSnarkWrapperProof,
+};
+use circuit_definitions::circuit_definitions::base_layer::ZkSyncBaseLayerProof;
+use circuit_definitions::circuit_definitions::recursion_layer::{
+    ZkSyncRecursionLayerProof, ZkSyncRecursionLayerStorageType,
+};
+use circuit_definitions::zkevm_circuits::fsm_input_output::CLOSED_FORM_COMMITTMENT_LENGTH;
+use circuit_definitions::zkevm_circuits::scheduler::aux::BaseLayerCircuitType;
+use circuit_definitions::zkevm_circuits::scheduler::NUM_SCHEDULER_PUBLIC_INPUTS;
+use std::error::Error;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use super::{
+    verify_base_layer_proof_for_type, verify_compression_for_wrapper_proof,
+    verify_compression_layer_proof, verify_recursion_layer_proof_for_type,
+};
+
+/// Every scheduler public input holds 7 big-endian bytes of the block hash.
+const BYTES_PER_SCHEDULER_PUBLIC_INPUT: usize = GoldilocksField::CAPACITY_BITS / 8;
+pub const BLOCK_HASH_PREFIX_LENGTH: usize =
+    BYTES_PER_SCHEDULER_PUBLIC_INPUT * NUM_SCHEDULER_PUBLIC_INPUTS;
+
+/// Public inputs of a proof, decoded according to the circuit that produced them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PublicInputs {
+    /// Base layer circuits commit to their closed form input, recursive circuits below the
+    /// scheduler to the input they aggregate.
+    InputCommitment([GoldilocksField; CLOSED_FORM_COMMITTMENT_LENGTH]),
+    /// The scheduler, and the compression layers that pass its public inputs through, expose
+    /// the prefix of `keccak256(previous block content hash || new block content hash)`.
+    BlockHash {
+        public_inputs: [GoldilocksField; NUM_SCHEDULER_PUBLIC_INPUTS],
+        hash_prefix: [u8; BLOCK_HASH_PREFIX_LENGTH],
+    },
+    /// The SNARK wrapper packs the scheduler public inputs into a single scalar.
+    SnarkWrapper {
+        public_input: Fr,
+        hash_prefix: [u8; BLOCK_HASH_PREFIX_LENGTH],
+    },
+    /// Public inputs of unexpected length.
+    Unknown(Vec<GoldilocksField>),
+}
+
+impl PublicInputs {
+    fn input_commitment(public_inputs: &[GoldilocksField]) -> Self {
+        match public_inputs.try_into() {
+            Ok(commitment) => PublicInputs::InputCommitment(commitment),
+            Err(_) => PublicInputs::Unknown(public_inputs.to_vec()),
+        }
+    }
+
+    fn block_hash(public_inputs: &[GoldilocksField]) -> Self {
+        let Ok(public_inputs) =
+            <[GoldilocksField; NUM_SCHEDULER_PUBLIC_INPUTS]>::try_from(public_inputs)
+        else {
+            return PublicInputs::Unknown(public_inputs.to_vec());
+        };
+
+        let mut hash_prefix = [0u8; BLOCK_HASH_PREFIX_LENGTH];
+        for (chunk, el) in hash_prefix
+            .chunks_exact_mut(BYTES_PER_SCHEDULER_PUBLIC_INPUT)
+            .zip(public_inputs.iter())
+        {
+            chunk.copy_from_slice(
+                &el.as_u64_reduced().to_be_bytes()[8 - BYTES_PER_SCHEDULER_PUBLIC_INPUT..],
+            );
+        }
+
+        PublicInputs::BlockHash {
+            public_inputs,
+            hash_prefix,
+        }
+    }
+
+    fn snark_wrapper(public_input: Fr) -> Self {
+        // the packing in `compress_stark_pi_to_snark_pi` is a big-endian concatenation of the
+        // scheduler public inputs, so they are the low bytes of the scalar
+        let mut encoding = vec![];
+        public_input
+            .into_repr()
+            .write_be(&mut encoding)
+            .expect("must write into a vector");
+        let mut hash_prefix = [0u8; BLOCK_HASH_PREFIX_LENGTH];
+        hash_prefix.copy_from_slice(&encoding[encoding.len() - BLOCK_HASH_PREFIX_LENGTH..]);
+
+        PublicInputs::SnarkWrapper {
+            public_input,
+            hash_prefix,
+        }
+    }
+
+    /// Prefix of the block hash, for the proofs of the scheduler and the layers above it.
+    pub fn block_hash_prefix(&self) -> Option<[u8; BLOCK_HASH_PREFIX_LENGTH]> {
+        match self {
+            PublicInputs::BlockHash { hash_prefix, .. }
+            | PublicInputs::SnarkWrapper { hash_prefix, .. } => Some(*hash_prefix),
+            PublicInputs::InputCommitment(_) | PublicInputs::Unknown(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PublicInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_elements(f: &mut fmt::Formatter<'_>, elements: &[GoldilocksField]) -> fmt::Result {
+            let elements: Vec<_> = elements
+                .iter()
+                .map(|el| format!("0x{:016x}", el.as_u64_reduced()))
+                .collect();
+            write!(f, "[{}]", elements.join(", "))
+        }
+
+        match self {
+            PublicInputs::InputCommitment(commitment) => {
+                write!(f, "input commitment: ")?;
+                write_elements(f, commitment)
+            }
+            PublicInputs::BlockHash {
+                public_inputs,
+                hash_prefix,
+            } => {
+                write!(f, "public inputs: ")?;
+                write_elements(f, public_inputs)?;
+                write!(f, "\nblock hash prefix: 0x{}", hex::encode(hash_prefix))
+            }
+            PublicInputs::SnarkWrapper {
+                public_input,
+                hash_prefix,
+            } => write!(
+                f,
+                "public input: {}\nblock hash prefix: 0x{}",
+                public_input,
+                hex::encode(hash_prefix)
+            ),
+            PublicInputs::Unknown(public_inputs) => {
+                write!(f, "unrecognized public inputs: ")?;
+                write_elements(f, public_inputs)
+            }
+        }
+    }
+}
+
+/// Proof of any layer, as stored by the prover.
+#[derive(Clone)]
+pub enum ProofEnvelope {
+    BaseLayer(ZkSyncBaseLayerProof),
+    RecursionLayer(ZkSyncRecursionLayerProof),
+    CompressionLayer(ZkSyncCompressionLayerProof),
+    CompressionForWrapper(ZkSyncCompressionForWrapperProof),
+    SnarkWrapper(Box<ZkSyncSnarkWrapperProof>),
+}
+
+impl ProofEnvelope {
+    pub fn numeric_circuit_type(&self) -> u8 {
+        match self {
+            ProofEnvelope::BaseLayer(proof) => proof.numeric_circuit_type(),
+            ProofEnvelope::RecursionLayer(proof) => proof.numeric_circuit_type(),
+            ProofEnvelope::CompressionLayer(proof) => proof.numeric_circuit_type(),
+            ProofEnvelope::CompressionForWrapper(proof) => proof.numeric_circuit_type(),
+            ProofEnvelope::SnarkWrapper(proof) => proof.numeric_circuit_type(),
+        }
+    }
+
+    pub fn short_description(&self) -> &'static str {
+        match self {
+            ProofEnvelope::BaseLayer(proof) => proof.short_description(),
+            ProofEnvelope::RecursionLayer(proof) => proof.short_description(),
+            ProofEnvelope::CompressionLayer(proof) => proof.short_description(),
+            ProofEnvelope::CompressionForWrapper(proof) => proof.short_description(),
+            ProofEnvelope::SnarkWrapper(proof) => proof.short_description(),
+        }
+    }
+
+    pub fn public_inputs(&self) -> PublicInputs {
+        match self {
+            ProofEnvelope::BaseLayer(proof) => {
+                PublicInputs::input_commitment(&proof.clone().into_inner().public_inputs)
+            }
+            ProofEnvelope::RecursionLayer(proof) => {
+                let is_scheduler = proof.numeric_circuit_type()
+                    == ZkSyncRecursionLayerStorageType::SchedulerCircuit as u8;
+                let public_inputs = proof.clone().into_inner().public_inputs;
+                if is_scheduler {
+                    PublicInputs::block_hash(&public_inputs)
+                } else {
+                    PublicInputs::input_commitment(&public_inputs)
+                }
+            }
+            ProofEnvelope::CompressionLayer(proof) => {
+                PublicInputs::block_hash(&proof.clone().into_inner().public_inputs)
+            }
+            ProofEnvelope::CompressionForWrapper(proof) => {
+                PublicInputs::block_hash(&proof.clone().into_inner().public_inputs)
+            }
+            ProofEnvelope::SnarkWrapper(proof) => {
+                match proof.as_ref().clone().into_inner().inputs.as_slice() {
+                    [public_input] => PublicInputs::snark_wrapper(*public_input),
+                    _ => PublicInputs::Unknown(vec![]),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofVerificationResult {
+    pub circuit_type: u8,
+    pub is_valid: bool,
+    pub public_inputs: PublicInputs,
+}
+
+#[derive(Debug)]
+pub enum ProofVerificationError {
+    /// The proof claims a circuit type that doesn't exist in its layer.
+    UnknownCircuitType {
+        layer: &'static str,
+        circuit_type: u8,
+    },
+    DataSource(DataSourceError),
+}
+
+impl fmt::Display for ProofVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofVerificationError::UnknownCircuitType {
+                layer,
+                circuit_type,
+            } => write!(f, "unknown {} circuit type {}", layer, circuit_type),
+            ProofVerificationError::DataSource(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ProofVerificationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ProofVerificationError::DataSource(err) => Some(err),
+            ProofVerificationError::UnknownCircuitType { .. } => None,
+        }
+    }
+}
+
+impl From<DataSourceError> for ProofVerificationError {
+    fn from(err: DataSourceError) -> Self {
+        ProofVerificationError::DataSource(err)
+    }
+}
+
+fn is_known_compression_circuit_type(circuit_type: u8) -> bool {
+    (ZkSyncCompressionLayerStorageType::CompressionMode1Circuit as u8
+        ..=ZkSyncCompressionLayerStorageType::CompressionMode5Circuit as u8)
+        .contains(&circuit_type)
+}
+
+///
+/// Verifies a proof of any layer against the verification key of its circuit type from `source`.
+/// Proofs of an unknown circuit type are rejected with an error before touching the source.
+/// Malformed proofs are reported as invalid, including the ones the verifier panics on.
+///
+pub fn verify_any_proof<DS: SetupDataSource>(
+    proof: &ProofEnvelope,
+    source: &DS,
+) -> Result<ProofVerificationResult, ProofVerificationError> {
+    let circuit_type = proof.numeric_circuit_type();
+    let unknown_circuit_type = |layer| ProofVerificationError::UnknownCircuitType {
+        layer,
+        circuit_type,
+    };
+
+    let is_valid = match proof {
+        ProofEnvelope::BaseLayer(proof) => {
+            if !BaseLayerCircuitType::as_iter_u8().any(|el| el == circuit_type) {
+                return Err(unknown_circuit_type("base layer"));
+            }
+            let vk = source.get_base_layer_vk(circuit_type)?.into_inner();
+            verify_without_panics(|| {
+                verify_base_layer_proof_for_type::<NoPow>(
+                    circuit_type,
+                    &proof.clone().into_inner(),
+                    &vk,
+                )
+            })
+        }
+        ProofEnvelope::RecursionLayer(proof) => {
+            let storage_type = ZkSyncRecursionLayerStorageType::from_numeric_value(circuit_type)
+                .ok_or_else(|| unknown_circuit_type("recursion layer"))?;
+            let vk = match storage_type {
+                ZkSyncRecursionLayerStorageType::NodeLayerCircuit => {
+                    source.get_recursion_layer_node_vk()?
+                }
+                ZkSyncRecursionLayerStorageType::RecursionTipCircuit => {
+                    source.get_recursion_tip_vk()?
+                }
+                _ => source.get_recursion_layer_vk(circuit_type)?,
+            };
+            verify_without_panics(|| {
+                verify_recursion_layer_proof_for_type::<NoPow>(
+                    storage_type,
+                    &proof.clone().into_inner(),
+                    &vk.into_inner(),
+                )
+            })
+        }
+        ProofEnvelope::CompressionLayer(proof) => {
+            if !is_known_compression_circuit_type(circuit_type) {
+                return Err(unknown_circuit_type("compression layer"));
+            }
+            let vk = source.get_compression_vk(circuit_type)?.into_inner();
+            // the circuit is built around the key of the proof it compresses
+            let circuit = ZkSyncCompressionLayerCircuit::from_witness_and_vk(
+                None,
+                get_vk_for_previous_circuit(source, circuit_type)?,
+                circuit_type,
+            );
+            verify_without_panics(|| {
+                verify_compression_layer_proof::<NoPow>(&circuit, &proof.clone().into_inner(), &vk)
+            })
+        }
+        ProofEnvelope::CompressionForWrapper(proof) => {
+            if !is_known_compression_circuit_type(circuit_type) {
+                return Err(unknown_circuit_type("compression for wrapper"));
+            }
+            let vk = source
+                .get_compression_for_wrapper_vk(circuit_type)?
+                .into_inner();
+            let circuit = ZkSyncCompressionForWrapperCircuit::from_witness_and_vk(
+                None,
+                get_vk_for_previous_circuit(source, circuit_type)?,
+                circuit_type,
+            );
+            verify_without_panics(|| {
+                verify_compression_for_wrapper_proof::<NoPow>(
+                    &circuit,
+                    &proof.clone().into_inner(),
+                    &vk,
+                )
+            })
+        }
+        ProofEnvelope::SnarkWrapper(proof) => {
+            let vk = source.get_wrapper_vk(circuit_type)?.into_inner();
+            verify_without_panics(|| {
+                verify::<_, _, RollingKeccakTranscript<Fr>>(
+                    &vk,
+                    &proof.as_ref().clone().into_inner(),
+                    None,
+                )
+                .unwrap_or(false)
+            })
+        }
+    };
+
+    Ok(ProofVerificationResult {
+        circuit_type,
+        is_valid,
+        public_inputs: proof.public_inputs(),
+    })
+}
+
+/// The verifiers index into the proof by the shape the key expects, so a malformed proof
+/// panics instead of failing verification.
+fn verify_without_panics(verify: impl FnOnce() -> bool) -> bool {
+    catch_unwind(AssertUnwindSafe(verify)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_source::local_file_data_source::LocalFileDataSource;
+    use crate::data_source::BlockDataSource;
+    use crate::proof_wrapper_utils::compress_stark_pi_to_snark_pi;
+
+    #[test]
+    fn test_block_hash_prefix_matches_snark_public_input() {
+        let public_inputs = [
+            0x00a1b2c3d4e5f607,
+            0x0011223344556677,
+            0x0000000000000001,
+            0x00ffffffffffffff,
+        ]
+        .map(GoldilocksField::from_u64_unchecked);
+
+        let hash_prefix = PublicInputs::block_hash(&public_inputs)
+            .block_hash_prefix()
+            .expect("scheduler public inputs must be recognized");
+        assert_eq!(
+            hex::encode(hash_prefix),
+            "a1b2c3d4e5f6071122334455667700000000000001ffffffffffffff"
+        );
+
+        let snark_hash_prefix =
+            PublicInputs::snark_wrapper(compress_stark_pi_to_snark_pi(public_inputs))
+                .block_hash_prefix()
+                .expect("SNARK public input must be recognized");
+        assert_eq!(hash_prefix, snark_hash_prefix);
+    }
+
+    #[test]
+    fn test_verify_any_proof() {
+        let source = LocalFileDataSource {
+            setup_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+            block_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+        };
+
+        let scheduler_proof = ProofEnvelope::RecursionLayer(source.get_scheduler_proof().unwrap());
+        let scheduler_result = verify_any_proof(&scheduler_proof, &source).unwrap();
+        assert!(scheduler_result.is_valid);
+        assert_eq!(
+            scheduler_result.circuit_type,
+            ZkSyncRecursionLayerStorageType::SchedulerCircuit as u8
+        );
+
+        let compression_proof =
+            ProofEnvelope::CompressionLayer(source.get_compression_proof(1).unwrap());
+        let compression_result = verify_any_proof(&compression_proof, &source).unwrap();
+        assert!(compression_result.is_valid);
+        // compression passes the scheduler public inputs through
+        assert_eq!(
+            compression_result.public_inputs,
+            scheduler_result.public_inputs
+        );
+    }
+
+    #[test]
+    fn test_malformed_proof_is_not_verified() {
+        let source = LocalFileDataSource {
+            setup_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+            block_data_location: "src/proof_wrapper_utils/testdata/proof_compression".to_string(),
+        };
+
+        let mut proof = source.get_compression_proof(1).unwrap().into_inner();
+        proof.queries_per_fri_repetition.clear();
+        proof.stage_2_oracle_cap.clear();
+        let proof = ProofEnvelope::CompressionLayer(ZkSyncCompressionLayerProof::from_inner(
+            ZkSyncCompressionLayerStorageType::CompressionMode1Circuit as u8,
+            proof,
+        ));
+
+        let result = verify_any_proof(&proof, &source).unwrap();
+        assert!(!result.is_valid);
+    }
+}
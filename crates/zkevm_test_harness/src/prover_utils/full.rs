@@ -7,6 +7,7 @@ use crate::boojum::cs::implementations::hints::*;
 use crate::boojum::cs::implementations::polynomial_storage::*;
 use crate::boojum::cs::implementations::verifier::*;
 use crate::boojum::cs::oracle::merkle_tree::*;
+use crate::boojum::cs::oracle::TreeHasher;
 use crate::boojum::field::goldilocks::GoldilocksExt2;
 use crate::boojum::worker::Worker;
 use crate::GoldilocksField;
@@ -64,6 +65,68 @@ pub fn create_base_layer_setup_data(
     )
 }
 
+/// Circuit synthesized in setup mode, before the setup is materialized. The setup base (selectors
+/// placement, lookup tables, constant and copy permutation columns) is cheap to compute from it
+/// and, together with the proof config, determines the verification key.
+pub struct SynthesizedSetup {
+    cs: CSReferenceAssembly<F, P, SetupCSConfig>,
+    finalization_hint: FinalizationHintsForProver,
+}
+
+impl SynthesizedSetup {
+    pub fn for_base_layer(circuit: ZkSyncBaseLayerCircuit) -> Self {
+        let (cs, finalization_hint) = get_cs_finalization_hint_for_base_layer(circuit);
+        Self {
+            cs,
+            finalization_hint,
+        }
+    }
+
+    pub fn for_recursive_layer(circuit: ZkSyncRecursiveLayerCircuit) -> Self {
+        let (cs, finalization_hint) = get_cs_finalization_hint_for_recursive_layer(circuit);
+        Self {
+            cs,
+            finalization_hint,
+        }
+    }
+
+    pub fn for_compression_layer(circuit: ZkSyncCompressionLayerCircuit) -> Self {
+        let (cs, finalization_hint) = get_cs_finalization_hint_for_compression(circuit);
+        Self {
+            cs,
+            finalization_hint,
+        }
+    }
+
+    pub fn for_compression_for_wrapper(circuit: ZkSyncCompressionForWrapperCircuit) -> Self {
+        let (cs, finalization_hint) = get_cs_finalization_hint_for_compression_for_wrapper(circuit);
+        Self {
+            cs,
+            finalization_hint,
+        }
+    }
+
+    pub fn setup_base(&self, worker: &Worker) -> SetupBaseStorage<F, P> {
+        self.cs.create_base_setup(worker, &mut ())
+    }
+
+    /// Same verification key and finalization hint as the matching `create_*_setup_data`.
+    pub fn into_vk_and_finalization_hint<TH: TreeHasher<F>>(
+        self,
+        worker: &Worker,
+        fri_lde_factor: usize,
+        merkle_tree_cap_size: usize,
+    ) -> (VerificationKey<F, TH>, FinalizationHintsForProver) {
+        let (_setup, vk, _setup_tree) = self.cs.materialize_setup_storage_and_vk::<TH>(
+            fri_lde_factor,
+            merkle_tree_cap_size,
+            worker,
+            &mut (),
+        );
+        (vk, self.finalization_hint)
+    }
+}
+
 use crate::boojum::cs::implementations::proof::Proof;
 use crate::boojum::cs::implementations::prover::ProofConfig;
 use crate::boojum::cs::implementations::transcript::GoldilocksPoisedon2Transcript;
@@ -1,8 +1,10 @@
+mod any_proof;
 mod full;
 pub mod light;
 
 use crate::boojum::cs::implementations::reference_cs::CSReferenceAssembly;
 use crate::boojum::cs::implementations::setup::FinalizationHintsForProver;
+pub use any_proof::*;
 use circuit_definitions::boojum::config::SetupCSConfig;
 use circuit_definitions::boojum::cs::cs_builder::new_builder;
 use circuit_definitions::boojum::cs::cs_builder_reference::CsReferenceImplementationBuilder;
@@ -12,6 +12,9 @@ mod wrapper_negative_tests;
 #[cfg(test)]
 mod precompiles;
 
+#[cfg(test)]
+mod replay_tests;
+
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::mpsc::sync_channel;
 use std::thread;
@@ -440,7 +443,7 @@ fn run_and_try_create_witness_inner(
     let mut setup_data = None;
 
     let mut source = LocalFileDataSource::default();
-    source.create_folders_for_storing_data();
+    source.create_folders_for_storing_data().unwrap();
 
     use crate::data_source::*;
 
@@ -1391,7 +1394,7 @@ fn run_single() {
     let verifier = verifier_builder.create_verifier();
 
     let source = LocalFileDataSource::default();
-    source.create_folders_for_storing_data();
+    source.create_folders_for_storing_data().unwrap();
     let vk = source.get_recursion_layer_node_vk().unwrap().into_inner();
 
     for (idx, proof) in inner.witness.proof_witnesses.iter().enumerate() {
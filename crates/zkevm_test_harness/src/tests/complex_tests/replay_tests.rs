@@ -0,0 +1,72 @@
+use super::*;
+
+use crate::data_source::local_file_data_source::LocalFileDataSource;
+use crate::proof_chain_replay::{
+    replay_proof_chain, ReplayEvent, ReplayInput, REPLAY_CHECKPOINT_FILE, REPLAY_INPUT_FILE,
+};
+
+/// Input of `proof_chain_replayer` for the block of `basic_test`.
+fn basic_test_replay_input() -> ReplayInput {
+    let test_artifact = read_basic_test_artifact();
+    let blobs = std::array::from_fn(|i| {
+        if i == 0 {
+            Some(vec![0xff; ENCODABLE_BYTES_PER_BLOB])
+        } else {
+            None
+        }
+    });
+    let geometry = crate::geometry_config::ProtocolGeometry::latest().config();
+
+    let (_, recursion_queues, scheduler_partial_input) =
+        generate_base_layer(test_artifact, 40000, geometry, blobs);
+
+    ReplayInput {
+        recursion_queues,
+        scheduler_partial_input,
+    }
+}
+
+/// Stores the replay input next to the proofs of `basic_test`, so that the aggregation can be
+/// replayed with `proof_chain_replayer`.
+#[ignore = "Too slow"]
+#[test]
+fn generate_replay_input() {
+    let source = LocalFileDataSource::default();
+    source.create_folders_for_storing_data().unwrap();
+
+    basic_test_replay_input()
+        .write_to_file(format!(
+            "{}/{}",
+            source.block_data_location, REPLAY_INPUT_FILE
+        ))
+        .unwrap();
+}
+
+#[ignore = "Too slow, requires the setup and the base layer proofs of `basic_test`"]
+#[test]
+fn test_replay_proof_chain() {
+    let mut source = LocalFileDataSource::default();
+    source.create_folders_for_storing_data().unwrap();
+    let input = basic_test_replay_input();
+    let checkpoint = std::path::Path::new(&source.block_data_location).join(REPLAY_CHECKPOINT_FILE);
+
+    let scheduler_proof = replay_proof_chain(&mut source, &input, &checkpoint, None, |event| {
+        println!("{}", event)
+    })
+    .unwrap();
+
+    // everything is checkpointed, so the second replay only checks the stored proofs
+    let mut events = vec![];
+    let replayed_proof = replay_proof_chain(&mut source, &input, &checkpoint, None, |event| {
+        events.push(event)
+    })
+    .unwrap();
+    assert!(!events.is_empty());
+    assert!(events
+        .iter()
+        .all(|event| matches!(event, ReplayEvent::Reusing(_))));
+    assert_eq!(
+        serde_json::to_value(replayed_proof).unwrap(),
+        serde_json::to_value(scheduler_proof).unwrap()
+    );
+}
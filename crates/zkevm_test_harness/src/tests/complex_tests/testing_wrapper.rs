@@ -13,7 +13,7 @@ pub(crate) fn test_compression_for_compression_num(config: WrapperConfig) {
     let bellman_worker = BellmanWorker::new();
 
     let mut file_source = LocalFileDataSource::default();
-    file_source.create_folders_for_storing_data();
+    file_source.create_folders_for_storing_data().unwrap();
     let mut source = InMemoryDataSource::new();
 
     // Load scheduler proof and vk
@@ -28,9 +28,9 @@ pub(crate) fn test_compression_for_compression_num(config: WrapperConfig) {
         )
         .unwrap();
 
-    compute_compression_circuits(&mut source, config, &worker);
-    compute_compression_for_wrapper_circuit(&mut source, config, &worker);
-    compute_wrapper_proof_and_vk(&mut source, config, &bellman_worker);
+    compute_compression_circuits(&mut source, config, &worker).unwrap();
+    compute_compression_for_wrapper_circuit(&mut source, config, &worker).unwrap();
+    compute_wrapper_proof_and_vk(&mut source, config, &bellman_worker).unwrap();
 
     // Write wrapper proof and vk
     let wrapper_type = config.get_wrapper_type();
@@ -71,7 +71,7 @@ fn test_wrapper_pi() {
     let config = get_testing_wrapper_config();
 
     let mut source = LocalFileDataSource::default();
-    source.create_folders_for_storing_data();
+    source.create_folders_for_storing_data().unwrap();
 
     test_wrapper_pi_inner(&mut source, config);
 }
@@ -115,7 +115,7 @@ fn test_wrapper_vk_generation() {
     let config = get_testing_wrapper_config();
 
     let mut source = LocalFileDataSource::default();
-    source.create_folders_for_storing_data();
+    source.create_folders_for_storing_data().unwrap();
 
     let scheduler_vk = source
         .get_recursion_layer_vk(ZkSyncRecursionLayerStorageType::SchedulerCircuit as u8)
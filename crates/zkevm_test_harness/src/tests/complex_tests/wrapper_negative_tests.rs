@@ -50,9 +50,9 @@ fn get_compression_for_wrapper_proof_for_testing(
         // Scheduler vk and proof should be present!
         let worker = Worker::new();
         // 1. All but one layers of compression with Goldilocks Poseidon2 hash
-        compute_compression_circuits(&mut source, config, &worker);
+        compute_compression_circuits(&mut source, config, &worker).unwrap();
         // 2. Final compression with Bn256 Poseidon2 hash
-        compute_compression_for_wrapper_circuit(&mut source, config, &worker);
+        compute_compression_for_wrapper_circuit(&mut source, config, &worker).unwrap();
     }
 
     source
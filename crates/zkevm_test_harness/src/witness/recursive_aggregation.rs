@@ -12,9 +12,13 @@ use crate::boojum::gadgets::num::Num;
 use crate::boojum::gadgets::queue::full_state_queue::FullStateCircuitQueueRawWitness;
 use crate::boojum::gadgets::recursion::recursive_tree_hasher::CircuitGoldilocksPoseidon2Sponge;
 use crate::witness::utils::take_sponge_like_queue_state_from_simulator;
+use crate::zkevm_circuits::fsm_input_output::circuit_inputs::INPUT_OUTPUT_COMMITMENT_LENGTH;
 use crate::zkevm_circuits::recursion::{
     leaf_layer::input::*,
-    node_layer::{input::RecursionNodeInputWitness, NodeLayerRecursionConfig},
+    node_layer::{
+        input::{RecursionNodeInput, RecursionNodeInputWitness},
+        NodeLayerRecursionConfig,
+    },
     VK_COMMITMENT_LENGTH,
 };
 use crate::zkevm_circuits::scheduler::LEAF_LAYER_PARAMETERS_COMMITMENT_LENGTH;
@@ -248,6 +252,53 @@ pub fn compute_node_vk_commitment(
     vk_commitment
 }
 
+/// Public input of the proof of a leaf or node circuit, i.e. the commitment to the circuit
+/// input. `None` for the recursion tip and scheduler circuits.
+pub fn compute_recursive_circuit_input_commitment(
+    circuit: &ZkSyncRecursiveLayerCircuit,
+) -> Option<[F; INPUT_OUTPUT_COMMITMENT_LENGTH]> {
+    let round_function = ZkSyncDefaultRoundFunction::default();
+    use crate::witness::utils::*;
+
+    match circuit {
+        ZkSyncRecursiveLayerCircuit::SchedulerCircuit(_)
+        | ZkSyncRecursiveLayerCircuit::RecursionTipCircuit(_) => None,
+        ZkSyncRecursiveLayerCircuit::NodeLayerCircuit(inner) => {
+            Some(compute_encodable_witness_commitment::<
+                RecursionNodeInput<F>,
+                INPUT_OUTPUT_COMMITMENT_LENGTH,
+                _,
+            >(inner.witness.input.clone(), &round_function))
+        }
+        ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForMainVM(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForCodeDecommittmentsSorter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForCodeDecommitter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForLogDemuxer(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForKeccakRoundFunction(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForSha256RoundFunction(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForECRecover(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForRAMPermutation(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForStorageSorter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForStorageApplication(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForEventsSorter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForL1MessagesSorter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForL1MessagesHasher(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForTransientStorageSorter(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForSecp256r1Verify(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForEIP4844Repack(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForModexp(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForECAdd(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForECMul(inner)
+        | ZkSyncRecursiveLayerCircuit::LeafLayerCircuitForECPairing(inner) => {
+            Some(compute_encodable_witness_commitment::<
+                RecursionLeafInput<F>,
+                INPUT_OUTPUT_COMMITMENT_LENGTH,
+                _,
+            >(inner.witness.input.clone(), &round_function))
+        }
+    }
+}
+
 /// Creates nodes witnesses, one witness is aggregating up to RECURSION_ARITY (32) leaves (or nodes) of a single circuit type.
 pub fn create_node_witnesses(
     chunks: Vec<(